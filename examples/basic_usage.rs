@@ -1,6 +1,9 @@
 //! Basic usage example for the context manager
 
 use context_manager::prelude::*;
+use context_manager::config::{EmbeddingProviderKind, VectorDbBackend};
+use context_manager::embedding::create_embedding_provider;
+use context_manager::vector_db::create_vector_store;
 use std::collections::HashMap;
 
 #[tokio::main]
@@ -13,29 +16,25 @@ async fn main() -> Result<()> {
     // Load configuration
     let mut config = Config::default_config();
     
-    // Set API token from environment
-    config.embedding.api_token = secrecy::Secret::new(
-        std::env::var("CHUTES_API_TOKEN")
-            .expect("CHUTES_API_TOKEN environment variable not set")
-    );
+    // Use the embeddings API when a token is set, otherwise run offline
+    match std::env::var("CHUTES_API_TOKEN") {
+        Ok(token) => config.embedding.api_token = secrecy::Secret::new(token),
+        Err(_) => {
+            println!("CHUTES_API_TOKEN not set, using offline hashing embeddings and in-memory storage\n");
+            config.embedding.provider = EmbeddingProviderKind::Hashing;
+            config.vector_db.backend = VectorDbBackend::Memory;
+        }
+    }
     
     println!("1. Initializing components...");
     
     // Initialize embedding client
-    let embedding_client = std::sync::Arc::new(
-        EmbeddingClient::new(config.embedding.clone())?
-    );
+    let embedding_client = create_embedding_provider(config.embedding.clone())?;
     println!("   ✓ Embedding client initialized");
     
-    // Initialize vector database
-    let vector_db = std::sync::Arc::new(
-        VectorDbClient::new(config.vector_db.clone()).await?
-    );
-    println!("   ✓ Vector database client initialized");
-    
-    // Initialize collections
-    vector_db.initialize_collections().await?;
-    println!("   ✓ Collections initialized");
+    // Initialize vector database and its collections
+    let vector_db = create_vector_store(config.vector_db.clone()).await?;
+    println!("   ✓ Vector database initialized");
     
     // Initialize HiRAG manager
     let hirag_manager = std::sync::Arc::new(
@@ -62,9 +61,11 @@ async fn main() -> Result<()> {
     
     for (text, level) in contexts {
         let id = hirag_manager.store_context(
+            &Namespace::default(),
             text,
             level,
             HashMap::new(),
+            None,
         ).await?;
         println!("   ✓ Stored: {} (level: {:?}, id: {})", text, level, id);
     }
@@ -78,7 +79,7 @@ async fn main() -> Result<()> {
     );
     
     // Retrieve contexts
-    let response = hirag_manager.retrieve_context(request).await?;
+    let response = hirag_manager.retrieve_context(&Namespace::default(), request).await?;
     
     println!("   Retrieved {} contexts in {}ms", 
         response.contexts.len(), 
@@ -1,11 +1,15 @@
 //! Example demonstrating the communication protocol
 
 use context_manager::prelude::*;
+use context_manager::config::{EmbeddingProviderKind, VectorDbBackend};
+use context_manager::embedding::create_embedding_provider;
+use context_manager::vector_db::create_vector_store;
 use context_manager::protocol::{
     MessagePayload, MessageType, JsonCodec,
 };
 use context_manager::protocol::messages::ContextStorePayload;
 use context_manager::protocol::handler::{MessageHandler, DefaultMessageHandler};
+use context_manager::middleware::Identity;
 use std::collections::HashMap;
 
 #[tokio::main]
@@ -17,23 +21,21 @@ async fn main() -> Result<()> {
     
     // Load configuration
     let mut config = Config::default_config();
-    config.embedding.api_token = secrecy::Secret::new(
-        std::env::var("CHUTES_API_TOKEN")
-            .expect("CHUTES_API_TOKEN environment variable not set")
-    );
+    // Use the embeddings API when a token is set, otherwise run offline
+    match std::env::var("CHUTES_API_TOKEN") {
+        Ok(token) => config.embedding.api_token = secrecy::Secret::new(token),
+        Err(_) => {
+            println!("CHUTES_API_TOKEN not set, using offline hashing embeddings and in-memory storage\n");
+            config.embedding.provider = EmbeddingProviderKind::Hashing;
+            config.vector_db.backend = VectorDbBackend::Memory;
+        }
+    }
     
     println!("1. Initializing system...");
     
     // Initialize components
-    let embedding_client = std::sync::Arc::new(
-        EmbeddingClient::new(config.embedding.clone())?
-    );
-    
-    let vector_db = std::sync::Arc::new(
-        VectorDbClient::new(config.vector_db.clone()).await?
-    );
-    
-    vector_db.initialize_collections().await?;
+    let embedding_client = create_embedding_provider(config.embedding.clone())?;
+    let vector_db = create_vector_store(config.vector_db.clone()).await?;
     
     let hirag_manager = std::sync::Arc::new(
         HiRAGManager::new(
@@ -46,8 +48,12 @@ async fn main() -> Result<()> {
     hirag_manager.initialize().await?;
     println!("   ✓ System initialized\n");
     
-    // Create message handler
+    // Create message handler; the transport would resolve the caller's token to an identity
     let handler = DefaultMessageHandler::new(hirag_manager.clone());
+    let identity = Identity {
+        tenant_id: "example_tenant".to_string(),
+        admin: false,
+    };
     
     // Create codec
     let codec = JsonCodec;
@@ -64,6 +70,7 @@ async fn main() -> Result<()> {
             metadata: HashMap::from([
                 ("topic".to_string(), serde_json::json!("AI")),
             ]),
+            session_id: None,
         }),
     );
     
@@ -78,7 +85,7 @@ async fn main() -> Result<()> {
     println!("   ✓ Message encoded and decoded successfully");
     
     // Handle message
-    let response = handler.handle_message(decoded).await?;
+    let response = handler.handle_message(&identity, decoded).await?;
     
     if let Some(reply) = response {
         println!("\n3. Received response:");
@@ -105,7 +112,7 @@ async fn main() -> Result<()> {
     println!("   Message ID: {}", request_message.id);
     
     // Handle request
-    let response = handler.handle_message(request_message).await?;
+    let response = handler.handle_message(&identity, request_message).await?;
     
     if let Some(reply) = response {
         println!("\n5. Received context response:");
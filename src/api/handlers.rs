@@ -1,9 +1,10 @@
 //! API request handlers
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -11,8 +12,10 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    hirag::{ContextManager, ContextRequest, Priority},
-    vector_db::{ContextLevel, circuit_breaker::CircuitBreaker},
+    config::{RankingWeights, RecencyDecay},
+    hirag::{ContextManager, ContextRequest, Judgment, Namespace, Priority, RerankOptions, SessionMode, models::ContextFilter},
+    middleware::{Identity, InputValidator},
+    vector_db::{ContextLevel, Filter, circuit_breaker::CircuitBreaker},
 };
 
 use crate::vector_db::VectorStore;
@@ -34,6 +37,8 @@ pub struct StoreContextRequest {
     pub level: ContextLevel,
     #[serde(default)]
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// Session the context belongs to
+    pub session_id: Option<String>,
 }
 
 /// Response from storing a context
@@ -52,6 +57,22 @@ pub struct SearchContextRequest {
     #[serde(default)]
     pub priority: Priority,
     pub session_id: Option<String>,
+    /// Whether `session_id` restricts (`only`), boosts (`boost`) or is ignored (`global`)
+    #[serde(default)]
+    pub session_mode: SessionMode,
+    /// Explain each context's score and list the ones cut by the token limit
+    #[serde(default)]
+    pub explain: bool,
+    /// Reranking of the top results, defaults to `hirag.reranking`
+    pub rerank: Option<RerankOptions>,
+    /// Ranking weights overriding `hirag.ranking_weights`
+    pub ranking_weights: Option<RankingWeights>,
+    /// Recency decay overriding `hirag.recency_decay`
+    pub recency_decay: Option<RecencyDecay>,
+    /// Level, tag and date filter
+    pub filter: Option<ContextFilter>,
+    /// Metadata filter document, combined with `filter`
+    pub filters: Option<Filter>,
 }
 
 /// Request to delete a context
@@ -60,6 +81,31 @@ pub struct DeleteContextRequest {
     pub id: Uuid,
 }
 
+/// Request reporting contexts that were actually used
+#[derive(Debug, Deserialize)]
+pub struct MarkUsedRequest {
+    pub ids: Vec<Uuid>,
+}
+
+/// Number of reported contexts found in the namespace
+#[derive(Debug, Serialize)]
+pub struct MarkUsedResponse {
+    pub marked: usize,
+}
+
+/// Helpful/unhelpful ratings of contexts returned for a query
+#[derive(Debug, Deserialize)]
+pub struct FeedbackRequest {
+    pub query: String,
+    pub judgments: Vec<Judgment>,
+}
+
+/// Number of rated contexts found in the namespace
+#[derive(Debug, Serialize)]
+pub struct FeedbackResponse {
+    pub recorded: usize,
+}
+
 /// Generic success response
 #[derive(Debug, Serialize)]
 pub struct SuccessResponse {
@@ -72,9 +118,62 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
+/// Header selecting the namespace to act on; `*` addresses all of them
+pub const NAMESPACE_HEADER: &str = "x-namespace";
+
+/// Namespace a request operates on.
+///
+/// Defaults to the caller's tenant. Admin tokens may pick another namespace
+/// with the `X-Namespace` header; other callers get 403 for anything but
+/// their own. Without an identity (authentication disabled) the default
+/// namespace is used unless the header says otherwise.
+pub struct RequestNamespace(pub Namespace);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestNamespace {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let requested = parts
+            .headers
+            .get(NAMESPACE_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let (own, admin) = match parts.extensions.get::<Identity>() {
+            Some(identity) => (Namespace::agent(identity.tenant_id.clone()), identity.admin),
+            None => (Namespace::default(), true),
+        };
+
+        let namespace = match requested {
+            None => own,
+            Some(requested) if admin => {
+                if requested == "*" {
+                    Namespace::All
+                } else {
+                    Namespace::agent(requested)
+                }
+            }
+            Some(requested) if Namespace::agent(requested.clone()) == own => own,
+            Some(requested) => {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    Json(ErrorResponse {
+                        error: format!("Not allowed to access namespace '{}'", requested),
+                    }),
+                ).into_response());
+            }
+        };
+
+        Ok(Self(namespace))
+    }
+}
+
 /// Store a new context
 pub async fn store_context(
     State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
     Json(req): Json<StoreContextRequest>,
 ) -> impl IntoResponse {
     // Validate metadata before storing
@@ -98,7 +197,7 @@ pub async fn store_context(
         }
     }
     
-    match state.context_manager.store_context(&req.text, req.level, req.metadata).await {
+    match state.context_manager.store_context(&namespace, &req.text, req.level, req.metadata, req.session_id.as_deref()).await {
         Ok(id) => (
             StatusCode::CREATED,
             Json(StoreContextResponse { id }),
@@ -115,18 +214,59 @@ pub async fn store_context(
 /// Search for contexts
 pub async fn search_contexts(
     State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
     Json(req): Json<SearchContextRequest>,
 ) -> impl IntoResponse {
+    let mut filters = req.filters;
+    let mut levels = req.levels;
+    
+    if let Some(filter) = &req.filter {
+        let conditions = filter.to_conditions();
+        if !conditions.is_empty() {
+            filters.get_or_insert_with(Filter::new).must.extend(conditions);
+        }
+        
+        // A level filter also narrows which levels get searched
+        if levels.is_empty() {
+            levels.extend(filter.level);
+        }
+    }
+    
+    if let Some(filters) = &filters {
+        if let Err(e) = InputValidator::validate_filter(filters) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Invalid filter: {}", e),
+                }),
+            ).into_response();
+        }
+    }
+    
     let context_req = ContextRequest {
         query: req.query,
         max_tokens: req.max_tokens,
-        levels: req.levels,
-        filters: None,
+        levels,
+        filters,
         priority: req.priority,
         session_id: req.session_id,
+        session_mode: req.session_mode,
+        explain: req.explain,
+        rerank: req.rerank,
+        ranking_weights: req.ranking_weights,
+        recency_decay: req.recency_decay,
     };
+    
+    if let Err(e) = context_req.validate_ranking_overrides() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response();
+    }
 
-    match state.context_manager.retrieve_context(context_req).await {
+    match state.context_manager.retrieve_context(&namespace, context_req).await {
         Ok(response) => (
             StatusCode::OK,
             Json(response),
@@ -143,9 +283,10 @@ pub async fn search_contexts(
 /// Delete a context
 pub async fn delete_context(
     State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
     Json(req): Json<DeleteContextRequest>,
 ) -> impl IntoResponse {
-    match state.context_manager.delete_context(req.id).await {
+    match state.context_manager.delete_context(&namespace, req.id).await {
         Ok(_) => (
             StatusCode::OK,
             Json(SuccessResponse {
@@ -161,12 +302,71 @@ pub async fn delete_context(
     }
 }
 
+/// Record "used" feedback for contexts, boosting their frequency score
+pub async fn mark_used(
+    State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
+    Json(req): Json<MarkUsedRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = InputValidator::validate_batch_size(req.ids.len()) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response();
+    }
+    
+    match state.context_manager.mark_used(&namespace, &req.ids).await {
+        Ok(marked) => (
+            StatusCode::OK,
+            Json(MarkUsedResponse { marked }),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response(),
+    }
+}
+
+/// Record relevance feedback for fitting ranking weights
+pub async fn record_feedback(
+    State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
+    Json(req): Json<FeedbackRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = InputValidator::validate_text(&req.query) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response();
+    }
+    
+    match state.context_manager.record_feedback(&namespace, &req.query, &req.judgments).await {
+        Ok(recorded) => (
+            StatusCode::OK,
+            Json(FeedbackResponse { recorded }),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response(),
+    }
+}
+
 /// Clear contexts by level
 pub async fn clear_level(
     State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
     Json(level): Json<ContextLevel>,
 ) -> impl IntoResponse {
-    match state.context_manager.clear_level(level).await {
+    match state.context_manager.clear_level(&namespace, level).await {
         Ok(_) => (
             StatusCode::OK,
             Json(SuccessResponse {
@@ -180,4 +380,23 @@ pub async fn clear_level(
             }),
         ).into_response(),
     }
-}
\ No newline at end of file
+}
+
+/// Get storage and retrieval statistics of the caller's namespace
+pub async fn get_stats(
+    State(state): State<AppState>,
+    RequestNamespace(namespace): RequestNamespace,
+) -> impl IntoResponse {
+    match state.context_manager.stats(&namespace).await {
+        Ok(stats) => (
+            StatusCode::OK,
+            Json(stats),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        ).into_response(),
+    }
+}
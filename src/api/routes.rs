@@ -45,7 +45,10 @@ pub fn build_router(
         .route("/api/v1/contexts", post(handlers::store_context))
         .route("/api/v1/contexts/search", post(handlers::search_contexts))
         .route("/api/v1/contexts/delete", post(handlers::delete_context))
+        .route("/api/v1/contexts/used", post(handlers::mark_used))
+        .route("/api/v1/contexts/feedback", post(handlers::record_feedback))
         .route("/api/v1/contexts/clear", post(handlers::clear_level))
+        .route("/api/v1/stats", get(handlers::get_stats))
         .layer(RequestBodyLimitLayer::new(body_limiter.max_body_size()))
         .layer(
             ServiceBuilder::new()
@@ -253,7 +256,7 @@ async fn rate_limit_middleware(
 /// Authentication middleware
 async fn auth_middleware_fn(
     axum::extract::State(auth): axum::extract::State<Arc<AuthMiddleware>>,
-    req: axum::http::Request<axum::body::Body>,
+    mut req: axum::http::Request<axum::body::Body>,
     next: axum::middleware::Next,
 ) -> Result<axum::response::Response, axum::http::StatusCode> {
     // Extract token from Authorization header
@@ -265,7 +268,9 @@ async fn auth_middleware_fn(
 
     match token {
         Some(token) => {
-            if auth.validate_token(token) {
+            if let Some(identity) = auth.identify(token) {
+                // Handlers resolve the caller's namespace from this
+                req.extensions_mut().insert(identity);
                 Ok(next.run(req).await)
             } else {
                 tracing::warn!("Invalid authentication token");
@@ -2,23 +2,33 @@
 //!
 //! This is the main entry point for running the Context Manager as a standalone server.
 //! It sets up the Axum web server with full CRUD API, authentication, and rate limiting.
+//!
+//! Maintenance subcommands run against the configured vector store and exit:
+//!
+//! ```text
+//! context-manager export <collection> <file> [--format jsonl|msgpack] [--no-vectors]
+//! context-manager import <file> <collection> [--resume] [--reembed] [--batch-size N]
+//! context-manager fit-weights [feedback-file] [--step 0.05]
+//! context-manager warm-cache <export-file>
+//! ```
 
 use context_manager::{
     api::{handlers::AppState, routes::build_router},
     config::Config,
-    v2::{EmbeddingClientV2 as EmbeddingClient, HiRAGManagerV2 as HiRAGManager},
-    vector_db::VectorDbClient,
+    v2::HiRAGManagerV2 as HiRAGManager,
+    vector_db::{create_vector_store, ResilientVectorStore, VectorStore, export_collection, import_collection, ExportFormat, ExportOptions, ImportOptions},
     middleware::{
         auth::{AuthMiddleware, AuthConfig},
         rate_limiter::{RateLimiter, RateLimitConfig},
         BodyLimiter, BodyLimitConfig,
     },
-    observability::{HealthChecker, MetricsCollector},
-    hirag::ContextManager,
+    observability::{HealthChecker, InstrumentedEmbeddingProvider, InstrumentedVectorStore, MetricsCollector},
+    hirag::{fit_ranking_weights, ContextManager, FeedbackLog},
+    embedding::{create_embedding_provider, DiskEmbeddingCache, EmbeddingProvider},
 };
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
 use tokio::signal;
-use tracing::info;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -66,6 +76,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Maintenance subcommands run against the configured store and exit
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        return run_command(&config, command, &args[1..]).await;
+    }
+
     use tracing::info;
     info!("Starting Context Manager Server");
     info!("Configuration loaded and validated from {}", config_path);
@@ -75,13 +91,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let metrics = Arc::new(MetricsCollector::new());
 
     // Initialize embedding client
-    let embedding_client = Arc::new(EmbeddingClient::new(config.embedding.clone())?);
-    info!("Embedding client initialized");
+    let embedding_client: Arc<dyn EmbeddingProvider> = Arc::new(InstrumentedEmbeddingProvider::new(
+        create_embedding_provider(config.embedding.clone())?,
+        metrics.clone(),
+    ));
+    if embedding_client.is_production() {
+        info!("Embedding client initialized ({:?} provider)", config.embedding.provider);
+    } else {
+        warn!("Embedding client initialized with the offline {:?} provider, not for production", config.embedding.provider);
+    }
 
-    // Initialize vector database
-    let vector_db = Arc::new(VectorDbClient::new(config.vector_db.clone()).await?);
-    vector_db.initialize_collections().await?;
-    info!("Vector database initialized");
+    // Initialize vector database behind timeouts, retries and a circuit breaker
+    let resilient_db = ResilientVectorStore::new(
+        create_vector_store(config.vector_db.clone()).await?,
+        &config.vector_db,
+    );
+    let circuit_breaker = resilient_db.circuit_breaker();
+    let vector_db: Arc<dyn VectorStore> = Arc::new(InstrumentedVectorStore::new(
+        Arc::new(resilient_db),
+        metrics.clone(),
+    ));
+    info!("Vector database initialized ({:?} backend)", config.vector_db.backend);
 
     // Initialize HiRAG manager
     let hirag_manager_impl = HiRAGManager::new(
@@ -89,8 +119,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         embedding_client.clone(),
         vector_db.clone(),
     )
-    .await?;
+    .await?
+    .with_metrics(metrics.clone())
+    .with_distance(config.vector_db.distance);
     hirag_manager_impl.initialize().await?;
+    let sparse_index = hirag_manager_impl.sparse_index();
     
     let hirag_manager: Arc<dyn ContextManager> = Arc::new(hirag_manager_impl);
     info!("HiRAG manager initialized");
@@ -99,7 +132,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let health_checker = Arc::new(
         HealthChecker::new()
             .with_vector_db(vector_db.clone())
-            .with_embedding_client(embedding_client.clone()),
+            .with_embedding_client(embedding_client.clone())
+            .with_circuit_breaker(circuit_breaker.clone()),
     );
     info!("Health checker initialized");
 
@@ -128,34 +162,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
           config.protocol.max_message_size_mb);
 
     // Initialize authentication
+    // API_TOKENS entries are `token` or `token=tenant`
+    let mut valid_tokens = std::collections::HashSet::new();
+    let mut tenants = std::collections::HashMap::new();
+    for entry in std::env::var("API_TOKENS")
+        .unwrap_or_else(|_| "default-token".to_string())
+        .split(',')
+    {
+        // Trim whitespace from tokens
+        let (token, tenant) = match entry.split_once('=') {
+            Some((token, tenant)) => (token.trim(), Some(tenant.trim())),
+            None => (entry.trim(), None),
+        };
+        if token.is_empty() {
+            continue;
+        }
+        if let Some(tenant) = tenant.filter(|t| !t.is_empty()) {
+            tenants.insert(token.to_string(), tenant.to_string());
+        }
+        valid_tokens.insert(token.to_string());
+    }
+    let admin_tokens = std::env::var("ADMIN_API_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    
     let auth_config = AuthConfig {
         enabled: true,
-        valid_tokens: std::env::var("API_TOKENS")
-            .unwrap_or_else(|_| "default-token".to_string())
-            .split(',')
-            .map(|s| s.trim().to_string()) // Trim whitespace from tokens
-            .collect(),
+        valid_tokens,
         token_prefix: "Bearer".to_string(),
+        tenants,
+        admin_tokens,
     };
     let auth_middleware = Arc::new(AuthMiddleware::new(auth_config));
     info!("Authentication middleware initialized");
 
-    // No circuit breaker available in VectorDbClient
-    let circuit_breaker = None;
-
     // Initialize background GC task if enabled
     if config.hirag.gc_enabled {
         use context_manager::hirag::background::BackgroundTaskManager;
         use std::time::Duration;
         
-        let background_manager = Arc::new(BackgroundTaskManager::new(
+        let mut background_manager = BackgroundTaskManager::new(
             vector_db.clone(),
             Duration::from_secs(config.hirag.gc_interval_secs),
             config.hirag.l2_ttl_secs,
             format!("{}_shortterm", config.vector_db.collection_prefix), // L2 collection name
             format!("{}_longterm", config.vector_db.collection_prefix), // L3 collection name
-            config.vector_db.vector_size,
-        ));
+        );
+        if let Some(index) = sparse_index {
+            background_manager = background_manager.with_sparse_index(index);
+        }
+        let background_manager = Arc::new(background_manager);
         
         background_manager.clone().start();
         
@@ -169,7 +228,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         context_manager: hirag_manager,
         vector_db,
         health_checker: health_checker.clone(),
-        circuit_breaker,
+        circuit_breaker: Some(circuit_breaker),
     };
 
     // Build router with all middleware
@@ -197,6 +256,110 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+const USAGE: &str = "usage:
+  context-manager export <collection> <file> [--format jsonl|msgpack] [--no-vectors]
+  context-manager import <file> <collection> [--resume] [--reembed] [--batch-size N]
+  context-manager fit-weights [feedback-file] [--step 0.05]
+  context-manager warm-cache <export-file>";
+
+/// Run a maintenance subcommand
+async fn run_command(config: &Config, command: &str, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    // Values of these options are not positional arguments
+    let value_options = ["--format", "--batch-size", "--step"];
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with("--") && !(*i > 0 && value_options.contains(&args[i - 1].as_str())))
+        .map(|(_, a)| a)
+        .collect();
+    let flag = |name: &str| args.iter().any(|a| a == name);
+    let option = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+    };
+
+    match (command, positional.as_slice()) {
+        ("export", [collection, file, ..]) => {
+            let path = Path::new(file.as_str());
+            let options = ExportOptions {
+                format: match option("--format") {
+                    Some(format) => format.parse()?,
+                    None => ExportFormat::from_path(path),
+                },
+                with_vectors: !flag("--no-vectors"),
+            };
+
+            let vector_db = ResilientVectorStore::new(create_vector_store(config.vector_db.clone()).await?, &config.vector_db);
+            let exported = export_collection(&vector_db, collection, path, &options).await?;
+            println!("Exported {} points from {} to {}", exported, collection, path.display());
+        }
+        ("import", [file, collection, ..]) => {
+            let mut options = ImportOptions::new(config.vector_db.vector_size);
+            options.resume = flag("--resume");
+            if let Some(batch_size) = option("--batch-size") {
+                options.batch_size = batch_size.parse()?;
+            }
+            if flag("--reembed") {
+                options.embedder = Some(create_embedding_provider(config.embedding.clone())?);
+            }
+
+            let vector_db = ResilientVectorStore::new(create_vector_store(config.vector_db.clone()).await?, &config.vector_db);
+            let report = import_collection(&vector_db, collection, Path::new(file.as_str()), &options).await?;
+            println!(
+                "Imported {} points into {} ({} skipped, {} re-embedded)",
+                report.imported, collection, report.skipped, report.reembedded
+            );
+        }
+        ("fit-weights", [] | [_]) => {
+            let path = Path::new(positional.first().map_or(config.hirag.feedback_path.as_str(), |f| f.as_str()));
+            let step = match option("--step") {
+                Some(step) => step.parse()?,
+                None => 0.05,
+            };
+
+            let records = FeedbackLog::load(path)?;
+            let fit = fit_ranking_weights(&records, &config.hirag.ranking_weights, step);
+            if fit.queries == 0 {
+                return Err(format!("No query in {} has a helpful judgment to fit on", path.display()).into());
+            }
+
+            println!(
+                "Fitted on {} judgments over {} queries from {}",
+                fit.judgments, fit.queries, path.display()
+            );
+            println!("nDCG before: {:.4}", fit.ndcg_before);
+            println!("nDCG after:  {:.4}", fit.ndcg_after);
+            println!();
+            print!("{}", fit.config_diff());
+        }
+        ("warm-cache", [file]) => {
+            let Some(dir) = &config.embedding.disk_cache_dir else {
+                return Err("warm-cache needs embedding.disk_cache_dir to be set".into());
+            };
+
+            let provider = create_embedding_provider(config.embedding.clone())?;
+            let cache = DiskEmbeddingCache::open(
+                dir,
+                Duration::from_secs(config.embedding.disk_cache_ttl_secs),
+                config.embedding.disk_cache_max_bytes,
+            )?;
+            let path = Path::new(file.as_str());
+            let report = cache.warm_from_export(path, &provider.model_id(), provider.embedding_dimension())?;
+            println!(
+                "Cached {} embeddings from {} for {} ({} skipped)",
+                report.loaded,
+                path.display(),
+                provider.model_id(),
+                report.skipped
+            );
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
+
 /// Graceful shutdown signal handler
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -36,7 +36,10 @@ pub fn load_config_with_env<P: AsRef<Path>>(path: P) -> Result<Config> {
 /// Validate configuration values
 fn validate_config(config: &Config) -> Result<()> {
     // Validate embedding config
-    if config.embedding.api_token.expose_secret().is_empty() {
+    if config.embedding.provider == super::EmbeddingProviderKind::Http
+        && config.embedding.api.requires_api_token()
+        && config.embedding.api_token.expose_secret().is_empty()
+    {
         return Err(ContextError::Config(
             "Embedding API token is required".to_string()
         ));
@@ -49,7 +52,7 @@ fn validate_config(config: &Config) -> Result<()> {
     }
     
     // Validate vector DB config
-    if config.vector_db.url.is_empty() {
+    if config.vector_db.backend == super::VectorDbBackend::Qdrant && config.vector_db.url.is_empty() {
         return Err(ContextError::Config(
             "Vector database URL is required".to_string()
         ));
@@ -27,13 +27,30 @@ pub struct Config {
 /// Configuration for the embedding service
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingConfig {
-    /// Chutes API endpoint URL
+    /// Embedding backend
+    #[serde(default)]
+    pub provider: EmbeddingProviderKind,
+    
+    /// Chutes API endpoint URL, required for the `http` provider
+    #[serde(default)]
     pub api_url: String,
     
-    /// API authentication token (secured)
-    #[serde(serialize_with = "serialize_secret", deserialize_with = "deserialize_secret")]
+    /// API authentication token (secured), required for the `http` provider
+    #[serde(default = "default_secret", serialize_with = "serialize_secret", deserialize_with = "deserialize_secret")]
     pub api_token: Secret<String>,
     
+    /// Wire format of the `http` provider's endpoint
+    #[serde(default)]
+    pub api: EmbeddingApi,
+    
+    /// Model name sent to the endpoint, required by the Ollama and Cohere APIs
+    #[serde(default)]
+    pub model: Option<String>,
+    
+    /// Dimension of the produced vectors
+    #[serde(default = "default_vector_size")]
+    pub dimension: usize,
+    
     /// Maximum batch size for embedding requests
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
@@ -58,6 +75,18 @@ pub struct EmbeddingConfig {
     #[serde(default = "default_cache_size")]
     pub cache_size: usize,
     
+    /// Directory of the persistent second cache tier (disabled when unset)
+    #[serde(default)]
+    pub disk_cache_dir: Option<String>,
+    
+    /// Disk cache TTL in seconds
+    #[serde(default = "default_disk_cache_ttl")]
+    pub disk_cache_ttl_secs: u64,
+    
+    /// Disk cache size cap in bytes
+    #[serde(default = "default_disk_cache_max_bytes")]
+    pub disk_cache_max_bytes: u64,
+    
     /// Enable TLS for embedding API connection
     #[serde(default)]
     pub tls_enabled: bool,
@@ -65,12 +94,76 @@ pub struct EmbeddingConfig {
     /// Verify TLS certificates
     #[serde(default = "default_tls_verify")]
     pub tls_verify: bool,
+    
+    /// Providers tried in order when this one fails or its circuit is open;
+    /// they must produce the same dimension and model ID
+    #[serde(default)]
+    pub fallbacks: Vec<EmbeddingConfig>,
+    
+    /// Consecutive failures that open a provider's circuit in a failover chain
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: usize,
+    
+    /// Seconds a provider's circuit stays open before letting a probe through
+    #[serde(default = "default_circuit_open_secs")]
+    pub circuit_open_secs: u64,
+    
+    /// Milliseconds concurrent single-text requests wait to be sent as one batch (0 disables)
+    #[serde(default)]
+    pub coalesce_wait_ms: u64,
+    
+    /// Maximum texts per coalesced batch
+    #[serde(default = "default_batch_size")]
+    pub coalesce_max_batch: usize,
+}
+
+/// Embedding backends
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderKind {
+    /// OpenAI-compatible embeddings API
+    #[default]
+    Http,
+    /// Offline feature hashing; deterministic but not for production
+    Hashing,
+}
+
+/// Wire format of an embeddings endpoint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingApi {
+    /// `{input, model}` -> `{data: [{embedding, index}]}` (OpenAI, Chutes, vLLM)
+    #[default]
+    OpenAi,
+    /// Text Embeddings Inference `/embed`: `{inputs}` -> `[[f32]]`
+    Tei,
+    /// Ollama `/api/embed`: `{model, input}` -> `{embeddings}`
+    Ollama,
+    /// Cohere `/v1/embed` or `/v2/embed`: `{model, texts, input_type}` -> `{embeddings}`
+    Cohere,
 }
 
-/// Configuration for Qdrant vector database
+impl EmbeddingApi {
+    /// Whether the API is normally served behind bearer authentication
+    pub fn requires_api_token(self) -> bool {
+        matches!(self, EmbeddingApi::OpenAi | EmbeddingApi::Cohere)
+    }
+    
+    /// Whether requests must name a model
+    pub fn requires_model(self) -> bool {
+        matches!(self, EmbeddingApi::Ollama | EmbeddingApi::Cohere)
+    }
+}
+
+/// Configuration for the vector database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorDbConfig {
+    /// Storage backend
+    #[serde(default)]
+    pub backend: VectorDbBackend,
+    
     /// Qdrant server URL
+    #[serde(default = "default_vector_db_url")]
     pub url: String,
     
     /// API key (optional, secured)
@@ -103,6 +196,75 @@ pub struct VectorDbConfig {
     /// Verify TLS certificates
     #[serde(default = "default_tls_verify")]
     pub tls_verify: bool,
+
+    /// Data directory for the embedded backend
+    #[serde(default = "default_vector_data_dir")]
+    pub data_dir: String,
+
+    /// WAL size in bytes at which the embedded backend seals a new segment
+    #[serde(default = "default_segment_max_bytes")]
+    pub segment_max_bytes: u64,
+
+    /// Interval between embedded backend compactions in seconds
+    #[serde(default = "default_compaction_interval")]
+    pub compaction_interval_secs: u64,
+
+    /// Retries for idempotent operations after a transient failure
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Base delay between retries in milliseconds, doubled per attempt and jittered
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Consecutive failures that open the circuit breaker
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: usize,
+
+    /// Seconds the circuit stays open before letting a probe through
+    #[serde(default = "default_circuit_open_secs")]
+    pub circuit_open_secs: u64,
+
+    /// Extra metadata fields to index, on top of the HiRAG defaults
+    #[serde(default)]
+    pub indexed_fields: Vec<IndexedField>,
+}
+
+/// Vector storage backends
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorDbBackend {
+    /// Remote Qdrant server
+    #[default]
+    Qdrant,
+    /// In-process store, not persisted
+    Memory,
+    /// Single-node store persisted under `data_dir`
+    Embedded,
+}
+
+/// Payload field declared as indexed in the vector store
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexedField {
+    /// Metadata key, dotted for nested values (e.g. `build.target`)
+    pub field: String,
+
+    /// Index type
+    #[serde(default)]
+    pub kind: PayloadFieldType,
+}
+
+/// Payload index types
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadFieldType {
+    #[default]
+    Keyword,
+    Integer,
+    Float,
+    Bool,
+    Datetime,
+    Text,
 }
 
 /// Distance metrics supported
@@ -119,10 +281,18 @@ pub enum Distance {
 /// HiRAG configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HiRAGConfig {
-    /// L1 (Immediate) context size
+    /// L1 (Immediate) context size, per session
     #[serde(default = "default_l1_size")]
     pub l1_size: usize,
     
+    /// Number of per-session L1 caches each namespace keeps before its least recent is dropped
+    #[serde(default = "default_l1_max_sessions")]
+    pub l1_max_sessions: usize,
+    
+    /// Score multiplier for contexts of the request's session in boost mode
+    #[serde(default = "default_session_boost")]
+    pub session_boost: f32,
+    
     /// L2 (Short-term) context size
     #[serde(default = "default_l2_size")]
     pub l2_size: usize,
@@ -135,7 +305,7 @@ pub struct HiRAGConfig {
     #[serde(default = "default_max_context_tokens")]
     pub max_context_tokens: usize,
     
-    /// Minimum relevance score threshold
+    /// Minimum dense similarity of a search hit; Euclidean distances count as `1 / (1 + d)`
     #[serde(default = "default_relevance_threshold")]
     pub relevance_threshold: f32,
     
@@ -151,6 +321,26 @@ pub struct HiRAGConfig {
     #[serde(default)]
     pub ranking_weights: RankingWeights,
     
+    /// How the recency score decays with context age
+    #[serde(default)]
+    pub recency_decay: RecencyDecay,
+    
+    /// Reranking of the top ranked contexts
+    #[serde(default)]
+    pub reranking: RerankConfig,
+    
+    /// Record retrieval hits and "used" feedback for the frequency score
+    #[serde(default = "default_access_tracking_enabled")]
+    pub access_tracking_enabled: bool,
+    
+    /// Interval in seconds between batched access write-backs
+    #[serde(default = "default_access_flush_interval")]
+    pub access_flush_interval_secs: u64,
+    
+    /// JSONL file relevance feedback is appended to
+    #[serde(default = "default_feedback_path")]
+    pub feedback_path: String,
+    
     /// Enable background garbage collection
     #[serde(default = "default_gc_enabled")]
     pub gc_enabled: bool,
@@ -197,9 +387,33 @@ pub struct RetrievalStrategy {
     #[serde(default = "default_l3_allocation")]
     pub l3_allocation: f32,
     
-    /// Minimum contexts per level
+    /// Minimum contexts per level, kept even below `relevance_threshold`
     #[serde(default = "default_min_contexts")]
     pub min_contexts_per_level: usize,
+    
+    /// Candidates fetched per level and channel before thresholding and token budgeting
+    #[serde(default = "default_search_limit")]
+    pub search_limit: usize,
+    
+    /// Run a sparse (BM25) lexical search alongside the dense vector search
+    #[serde(default)]
+    pub hybrid_enabled: bool,
+    
+    /// How dense and sparse result lists are combined
+    #[serde(default)]
+    pub fusion: FusionMethod,
+    
+    /// Weight of the dense channel during fusion
+    #[serde(default = "default_dense_weight")]
+    pub dense_weight: f32,
+    
+    /// Weight of the sparse channel during fusion
+    #[serde(default = "default_sparse_weight")]
+    pub sparse_weight: f32,
+    
+    /// Rank offset `k` for reciprocal rank fusion
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: f32,
 }
 
 impl Default for RetrievalStrategy {
@@ -209,10 +423,27 @@ impl Default for RetrievalStrategy {
             l2_allocation: 0.4,
             l3_allocation: 0.3,
             min_contexts_per_level: 1,
+            search_limit: default_search_limit(),
+            hybrid_enabled: false,
+            fusion: FusionMethod::default(),
+            dense_weight: default_dense_weight(),
+            sparse_weight: default_sparse_weight(),
+            rrf_k: default_rrf_k(),
         }
     }
 }
 
+/// Methods for fusing dense and sparse result lists
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FusionMethod {
+    /// Weighted reciprocal rank fusion
+    #[default]
+    Rrf,
+    /// Weighted sum of normalized channel scores
+    Weighted,
+}
+
 /// Ranking weights for context scoring
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RankingWeights {
@@ -244,6 +475,95 @@ impl Default for RankingWeights {
     }
 }
 
+/// Decay of the recency score (1.0 for a new context) with context age
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecencyDecay {
+    /// Halves every `half_life_secs`
+    Exponential { half_life_secs: f32 },
+    /// Falls linearly to 0.0 at `window_secs`
+    Linear { window_secs: f32 },
+    /// 1.0 up to `cutoff_secs`, 0.0 after
+    Step { cutoff_secs: f32 },
+}
+
+impl Default for RecencyDecay {
+    fn default() -> Self {
+        // e^(-age_hours / 24): about 0.37 after a day
+        RecencyDecay::Exponential { half_life_secs: 24.0 * 3600.0 * std::f32::consts::LN_2 }
+    }
+}
+
+/// Reranker applied to the top contexts after heuristic ranking
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankerKind {
+    /// Keep the heuristic ranking
+    #[default]
+    None,
+    /// Query term overlap
+    Lexical,
+    /// Cross-encoder behind a rerank HTTP endpoint
+    CrossEncoder,
+}
+
+/// Wire format of a rerank endpoint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RerankApi {
+    /// `{model, query, documents}` -> `{results: [{index, relevance_score}]}` (Cohere, Jina, vLLM)
+    #[default]
+    OpenAi,
+    /// Text Embeddings Inference `/rerank`: `{query, texts}` -> `[{index, score}]`
+    Tei,
+}
+
+/// Reranking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankConfig {
+    /// Reranker used when a request does not pick one
+    #[serde(default)]
+    pub default_reranker: RerankerKind,
+    
+    /// Number of top ranked contexts passed to the reranker, and the most a request may ask for
+    #[serde(default = "default_rerank_top_n")]
+    pub top_n: usize,
+    
+    /// Time allowed for reranking before falling back to the heuristic order, and the most a request may ask for
+    #[serde(default = "default_rerank_timeout_ms")]
+    pub timeout_ms: u64,
+    
+    /// Rerank endpoint URL for the cross-encoder reranker
+    #[serde(default)]
+    pub url: Option<String>,
+    
+    /// Rerank endpoint wire format
+    #[serde(default)]
+    pub api: RerankApi,
+    
+    /// Model name sent to the endpoint
+    #[serde(default)]
+    pub model: Option<String>,
+    
+    /// API key (optional, secured)
+    #[serde(default, serialize_with = "serialize_optional_secret", deserialize_with = "deserialize_optional_secret")]
+    pub api_key: Option<Secret<String>>,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            default_reranker: RerankerKind::default(),
+            top_n: default_rerank_top_n(),
+            timeout_ms: default_rerank_timeout_ms(),
+            url: None,
+            api: RerankApi::default(),
+            model: None,
+            api_key: None,
+        }
+    }
+}
+
 /// Protocol configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtocolConfig {
@@ -298,6 +618,7 @@ pub struct LoggingConfig {
 }
 
 // Default value functions
+fn default_secret() -> Secret<String> { Secret::new(String::new()) }
 fn default_batch_size() -> usize { 32 }
 fn default_timeout() -> u64 { 30 }
 
@@ -306,9 +627,23 @@ fn default_max_retries() -> u32 { 3 }
 fn default_cache_enabled() -> bool { true }
 fn default_cache_ttl() -> u64 { 3600 }
 fn default_cache_size() -> usize { 1000 }
+fn default_disk_cache_ttl() -> u64 { 30 * 24 * 3600 }
+fn default_disk_cache_max_bytes() -> u64 { 1 << 30 }
+fn default_vector_db_url() -> String { "http://localhost:6334".to_string() }
 fn default_collection_prefix() -> String { "contexts".to_string() }
+fn default_vector_data_dir() -> String { "./data/vectors".to_string() }
+fn default_segment_max_bytes() -> u64 { 16 * 1024 * 1024 }
+fn default_compaction_interval() -> u64 { 600 }
+fn default_retry_backoff_ms() -> u64 { 100 }
+fn default_circuit_failure_threshold() -> usize { 5 }
+fn default_circuit_open_secs() -> u64 { 30 }
 fn default_vector_size() -> usize { 1024 }
 fn default_l1_size() -> usize { 10 }
+fn default_l1_max_sessions() -> usize { 100 }
+fn default_session_boost() -> f32 { 1.25 }
+fn default_access_tracking_enabled() -> bool { true }
+fn default_access_flush_interval() -> u64 { 5 }
+fn default_feedback_path() -> String { "./data/feedback.jsonl".to_string() }
 fn default_l2_size() -> usize { 100 }
 fn default_l3_enabled() -> bool { true }
 fn default_max_context_tokens() -> usize { 4000 }
@@ -317,10 +652,16 @@ fn default_l1_allocation() -> f32 { 0.3 }
 fn default_l2_allocation() -> f32 { 0.4 }
 fn default_l3_allocation() -> f32 { 0.3 }
 fn default_min_contexts() -> usize { 1 }
+fn default_search_limit() -> usize { 100 }
+fn default_dense_weight() -> f32 { 0.5 }
+fn default_sparse_weight() -> f32 { 0.5 }
+fn default_rrf_k() -> f32 { 60.0 }
 fn default_similarity_weight() -> f32 { 0.5 }
 fn default_recency_weight() -> f32 { 0.2 }
 fn default_level_weight() -> f32 { 0.2 }
 fn default_frequency_weight() -> f32 { 0.1 }
+fn default_rerank_top_n() -> usize { 20 }
+fn default_rerank_timeout_ms() -> u64 { 2000 }
 fn default_protocol_version() -> String { "1.0.0".to_string() }
 fn default_max_message_size() -> usize { 10 }
 fn default_log_level() -> String { "info".to_string() }
@@ -361,6 +702,7 @@ impl Config {
     pub fn default_config() -> Self {
         Self {
             embedding: EmbeddingConfig {
+                provider: EmbeddingProviderKind::default(),
                 api_url: "https://chutes-intfloat-multilingual-e5-large.chutes.ai/v1/embeddings".to_string(),
                 api_token: Secret::new(std::env::var("CHUTES_API_TOKEN").unwrap_or_default()),
                 batch_size: default_batch_size(),
@@ -371,9 +713,21 @@ impl Config {
                 cache_size: default_cache_size(),
                 tls_enabled: false,
                 tls_verify: true,
+                api: EmbeddingApi::default(),
+                model: None,
+                dimension: default_vector_size(),
+                fallbacks: Vec::new(),
+                circuit_failure_threshold: default_circuit_failure_threshold(),
+                circuit_open_secs: default_circuit_open_secs(),
+                coalesce_wait_ms: 0,
+                coalesce_max_batch: default_batch_size(),
+                disk_cache_dir: None,
+                disk_cache_ttl_secs: default_disk_cache_ttl(),
+                disk_cache_max_bytes: default_disk_cache_max_bytes(),
             },
             vector_db: VectorDbConfig {
-                url: "http://localhost:6334".to_string(),
+                backend: VectorDbBackend::default(),
+                url: default_vector_db_url(),
                 api_key: None,
                 collection_prefix: default_collection_prefix(),
                 vector_size: default_vector_size(),
@@ -382,9 +736,19 @@ impl Config {
                 tls_enabled: false,
                 tls_cert_path: None,
                 tls_verify: true,
+                data_dir: default_vector_data_dir(),
+                segment_max_bytes: default_segment_max_bytes(),
+                compaction_interval_secs: default_compaction_interval(),
+                max_retries: default_max_retries(),
+                retry_backoff_ms: default_retry_backoff_ms(),
+                circuit_failure_threshold: default_circuit_failure_threshold(),
+                circuit_open_secs: default_circuit_open_secs(),
+                indexed_fields: Vec::new(),
             },
             hirag: HiRAGConfig {
                 l1_size: default_l1_size(),
+                l1_max_sessions: default_l1_max_sessions(),
+                session_boost: default_session_boost(),
                 l2_size: default_l2_size(),
                 l3_enabled: default_l3_enabled(),
                 max_context_tokens: default_max_context_tokens(),
@@ -392,6 +756,11 @@ impl Config {
                 token_estimator: TokenEstimator::default(),
                 retrieval_strategy: RetrievalStrategy::default(),
                 ranking_weights: RankingWeights::default(),
+                recency_decay: RecencyDecay::default(),
+                reranking: RerankConfig::default(),
+                access_tracking_enabled: default_access_tracking_enabled(),
+                access_flush_interval_secs: default_access_flush_interval(),
+                feedback_path: default_feedback_path(),
                 gc_enabled: default_gc_enabled(),
                 gc_interval_secs: default_gc_interval(),
                 l2_ttl_secs: default_l2_ttl(),
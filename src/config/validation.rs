@@ -8,13 +8,89 @@ pub fn validate_config(config: &Config) -> Result<()> {
     validate_embedding_config(&config.embedding)?;
     validate_vector_db_config(&config.vector_db)?;
     validate_hirag_config(&config.hirag)?;
+    
+    if config.embedding.provider == EmbeddingProviderKind::Hashing
+        && config.embedding.dimension != config.vector_db.vector_size
+    {
+        return Err(ContextError::Configuration(format!(
+            "Hashing embedding dimension {} does not match vector_db.vector_size {}",
+            config.embedding.dimension, config.vector_db.vector_size
+        )));
+    }
+    
     validate_protocol_config(&config.protocol)?;
     validate_server_config(&config.server)?;
     Ok(())
 }
 
+/// Model ID a failover chain member reports; HTTP members must set `model`
+/// rather than fall back to their URL, which differs between members
+fn chain_model_id(config: &EmbeddingConfig) -> Result<String> {
+    match config.provider {
+        // Same as `HashingEmbedder::model_id`
+        EmbeddingProviderKind::Hashing => Ok(format!("hashing-{}", config.dimension)),
+        EmbeddingProviderKind::Http => config.model.clone().filter(|m| !m.is_empty()).ok_or_else(|| {
+            ContextError::Configuration(format!(
+                "Embedding model is required for {} when fallbacks are configured",
+                config.api_url
+            ))
+        }),
+    }
+}
+
 /// Validate embedding configuration
 fn validate_embedding_config(config: &EmbeddingConfig) -> Result<()> {
+    // Validate failover chain
+    if !config.fallbacks.is_empty() {
+        if config.circuit_failure_threshold == 0 || config.circuit_open_secs == 0 {
+            return Err(ContextError::Configuration(
+                "Embedding circuit breaker threshold and open duration must be greater than 0".to_string()
+            ));
+        }
+        
+        // Vectors from every member must be able to share a collection
+        let model_id = chain_model_id(config)?;
+        for fallback in &config.fallbacks {
+            if !fallback.fallbacks.is_empty() {
+                return Err(ContextError::Configuration(
+                    "Embedding fallbacks cannot have fallbacks of their own".to_string()
+                ));
+            }
+            let fallback_model_id = chain_model_id(fallback)?;
+            if fallback.dimension != config.dimension || fallback_model_id != model_id {
+                return Err(ContextError::Configuration(format!(
+                    "Embedding fallback {} ({} dims) does not match {} ({} dims)",
+                    fallback_model_id, fallback.dimension, model_id, config.dimension
+                )));
+            }
+            validate_embedding_config(fallback)?;
+        }
+    }
+    
+    // Validate request coalescing
+    if config.coalesce_wait_ms > 1000 {
+        return Err(ContextError::Configuration(
+            "Embedding coalesce wait too large (max: 1000 ms)".to_string()
+        ));
+    }
+    
+    if config.coalesce_wait_ms > 0 && config.coalesce_max_batch == 0 {
+        return Err(ContextError::Configuration(
+            "Embedding coalesce batch size must be greater than 0".to_string()
+        ));
+    }
+    
+    if config.provider == EmbeddingProviderKind::Hashing {
+        if config.dimension == 0 {
+            return Err(ContextError::Configuration(
+                "Hashing embedding dimension must be greater than 0".to_string()
+            ));
+        }
+        
+        // The API settings below are unused
+        return Ok(());
+    }
+    
     // Validate API URL
     if config.api_url.is_empty() {
         return Err(ContextError::Config(
@@ -29,12 +105,19 @@ fn validate_embedding_config(config: &EmbeddingConfig) -> Result<()> {
     }
     
     // Validate API token
-    if config.api_token.expose_secret().is_empty() {
+    if config.api.requires_api_token() && config.api_token.expose_secret().is_empty() {
         return Err(ContextError::Config(
             "Embedding API token is required".to_string()
         ));
     }
     
+    if config.api.requires_model() && config.model.as_deref().is_none_or(str::is_empty) {
+        return Err(ContextError::Configuration(format!(
+            "Embedding model is required for the {:?} API",
+            config.api
+        )));
+    }
+    
     // Validate batch size
     if config.batch_size == 0 {
         return Err(ContextError::Config(
@@ -83,19 +166,42 @@ fn validate_embedding_config(config: &EmbeddingConfig) -> Result<()> {
         }
     }
     
+    if config.disk_cache_dir.is_some() {
+        if !config.cache_enabled {
+            return Err(ContextError::Configuration(
+                "Disk embedding cache requires cache_enabled".to_string()
+            ));
+        }
+        
+        if config.disk_cache_dir.as_deref() == Some("") {
+            return Err(ContextError::Configuration(
+                "Disk embedding cache directory cannot be empty".to_string()
+            ));
+        }
+        
+        if config.disk_cache_ttl_secs == 0 || config.disk_cache_max_bytes == 0 {
+            return Err(ContextError::Configuration(
+                "Disk embedding cache TTL and size cap must be greater than 0".to_string()
+            ));
+        }
+    }
+    
     Ok(())
 }
 
 /// Validate vector database configuration
 fn validate_vector_db_config(config: &VectorDbConfig) -> Result<()> {
-    // Validate URL
-    if config.url.is_empty() {
+    // Validate URL (only the Qdrant backend connects anywhere)
+    if config.backend == VectorDbBackend::Qdrant && config.url.is_empty() {
         return Err(ContextError::Config(
             "Vector database URL cannot be empty".to_string()
         ));
     }
     
-    if !config.url.starts_with("http://") && !config.url.starts_with("https://") {
+    if config.backend == VectorDbBackend::Qdrant
+        && !config.url.starts_with("http://")
+        && !config.url.starts_with("https://")
+    {
         return Err(ContextError::Config(
             "Vector database URL must start with http:// or https://".to_string()
         ));
@@ -141,6 +247,43 @@ fn validate_vector_db_config(config: &VectorDbConfig) -> Result<()> {
         ));
     }
     
+    // Validate embedded storage settings
+    if config.backend == VectorDbBackend::Embedded {
+        if config.data_dir.is_empty() {
+            return Err(ContextError::Configuration(
+                "Data directory cannot be empty for the embedded backend".to_string()
+            ));
+        }
+
+        if config.segment_max_bytes == 0 || config.compaction_interval_secs == 0 {
+            return Err(ContextError::Configuration(
+                "Segment size and compaction interval must be greater than 0".to_string()
+            ));
+        }
+    }
+
+    // Validate resilience settings
+    if config.max_retries > 10 {
+        return Err(ContextError::Configuration(
+            "Vector database retries too large (max: 10)".to_string()
+        ));
+    }
+
+    if config.circuit_failure_threshold == 0 || config.circuit_open_secs == 0 {
+        return Err(ContextError::Configuration(
+            "Circuit breaker threshold and open duration must be greater than 0".to_string()
+        ));
+    }
+
+    // Validate indexed payload fields
+    for indexed in &config.indexed_fields {
+        if indexed.field.is_empty() || indexed.field.split('.').any(|s| s.is_empty()) {
+            return Err(ContextError::Configuration(
+                format!("Invalid indexed field name: '{}'", indexed.field)
+            ));
+        }
+    }
+
     // Fatal error if TLS verify disabled in release mode
     #[cfg(not(debug_assertions))]
     {
@@ -169,6 +312,30 @@ fn validate_hirag_config(config: &HiRAGConfig) -> Result<()> {
         ));
     }
     
+    if config.l1_max_sessions == 0 {
+        return Err(ContextError::Configuration(
+            "L1 session cache count must be greater than 0".to_string()
+        ));
+    }
+    
+    if config.session_boost < 1.0 {
+        return Err(ContextError::Configuration(
+            "Session boost must be at least 1.0".to_string()
+        ));
+    }
+    
+    if config.access_tracking_enabled && config.access_flush_interval_secs == 0 {
+        return Err(ContextError::Configuration(
+            "Access flush interval must be greater than 0".to_string()
+        ));
+    }
+    
+    if config.feedback_path.trim().is_empty() {
+        return Err(ContextError::Configuration(
+            "Feedback path must not be empty".to_string()
+        ));
+    }
+    
     // Validate L2 size
     if config.l2_size == 0 {
         return Err(ContextError::Config(
@@ -202,8 +369,54 @@ fn validate_hirag_config(config: &HiRAGConfig) -> Result<()> {
         ));
     }
     
-    // Validate ranking weights
-    let weights = &config.ranking_weights;
+    // Validate hybrid retrieval settings
+    let strategy = &config.retrieval_strategy;
+    if strategy.dense_weight < 0.0 || strategy.sparse_weight < 0.0 {
+        return Err(ContextError::Configuration(
+            "Dense and sparse weights must not be negative".to_string()
+        ));
+    }
+    
+    if strategy.hybrid_enabled && strategy.dense_weight + strategy.sparse_weight <= 0.0 {
+        return Err(ContextError::Configuration(
+            "At least one of dense and sparse weight must be positive when hybrid retrieval is enabled".to_string()
+        ));
+    }
+    
+    if strategy.rrf_k < 0.0 {
+        return Err(ContextError::Configuration(
+            "RRF k must not be negative".to_string()
+        ));
+    }
+    
+    if strategy.search_limit == 0 || strategy.search_limit < strategy.min_contexts_per_level {
+        return Err(ContextError::Configuration(
+            "Search limit must be positive and at least min_contexts_per_level".to_string()
+        ));
+    }
+    
+    // Validate reranking settings
+    let reranking = &config.reranking;
+    if reranking.top_n == 0 || reranking.timeout_ms == 0 {
+        return Err(ContextError::Configuration(
+            "Rerank top_n and timeout must be greater than 0".to_string()
+        ));
+    }
+    
+    if reranking.default_reranker == RerankerKind::CrossEncoder && reranking.url.is_none() {
+        return Err(ContextError::Configuration(
+            "Cross-encoder reranking requires reranking.url".to_string()
+        ));
+    }
+    
+    validate_ranking_weights(&config.ranking_weights)?;
+    validate_recency_decay(&config.recency_decay)?;
+    
+    Ok(())
+}
+
+/// Validate ranking weights, from config or a request override
+pub fn validate_ranking_weights(weights: &RankingWeights) -> Result<()> {
     if weights.similarity_weight < 0.0 || weights.similarity_weight > 1.0 {
         return Err(ContextError::Config(
             "Similarity weight must be between 0.0 and 1.0".to_string()
@@ -239,6 +452,23 @@ fn validate_hirag_config(config: &HiRAGConfig) -> Result<()> {
     Ok(())
 }
 
+/// Validate a recency decay, from config or a request override
+pub fn validate_recency_decay(decay: &RecencyDecay) -> Result<()> {
+    let (name, secs) = match *decay {
+        RecencyDecay::Exponential { half_life_secs } => ("half-life", half_life_secs),
+        RecencyDecay::Linear { window_secs } => ("window", window_secs),
+        RecencyDecay::Step { cutoff_secs } => ("cutoff", cutoff_secs),
+    };
+    
+    if !secs.is_finite() || secs <= 0.0 {
+        return Err(ContextError::Configuration(
+            format!("Recency decay {} must be a positive number of seconds", name)
+        ));
+    }
+    
+    Ok(())
+}
+
 /// Validate protocol configuration
 fn validate_protocol_config(config: &ProtocolConfig) -> Result<()> {
     // Validate version
@@ -299,6 +529,65 @@ mod tests {
         assert!(validate_config(&config).is_ok());
     }
     
+    #[test]
+    fn test_hashing_provider_needs_no_api_token() {
+        let mut config = Config::default_config();
+        config.embedding.provider = EmbeddingProviderKind::Hashing;
+        config.embedding.api_token = Secret::new(String::new());
+        assert!(validate_config(&config).is_ok());
+        
+        config.embedding.dimension = 384;
+        assert!(validate_config(&config).is_err());
+    }
+    
+    #[test]
+    fn test_embedding_api_requirements() {
+        let mut config = Config::default_config();
+        config.embedding.api_token = Secret::new(String::new());
+        config.embedding.api = EmbeddingApi::Ollama;
+        assert!(validate_embedding_config(&config.embedding).is_err());
+
+        config.embedding.model = Some("nomic-embed-text".to_string());
+        assert!(validate_embedding_config(&config.embedding).is_ok());
+
+        config.embedding.api = EmbeddingApi::Cohere;
+        assert!(validate_embedding_config(&config.embedding).is_err());
+    }
+
+    #[test]
+    fn test_embedding_fallbacks() {
+        let mut config = Config::default_config();
+        config.embedding.api_token = Secret::new("test_token".to_string());
+        config.embedding.model = Some("bge-m3".to_string());
+        let mut fallback = config.embedding.clone();
+        fallback.api = EmbeddingApi::Tei;
+        fallback.api_url = "http://localhost:8080/embed".to_string();
+        config.embedding.fallbacks = vec![fallback.clone()];
+        assert!(validate_embedding_config(&config.embedding).is_ok());
+        
+        config.embedding.fallbacks[0].dimension = 768;
+        assert!(validate_embedding_config(&config.embedding).is_err());
+        
+        config.embedding.fallbacks[0] = EmbeddingConfig {
+            model: Some("e5-large".to_string()),
+            ..fallback.clone()
+        };
+        assert!(validate_embedding_config(&config.embedding).is_err());
+        
+        // Without a model the members would report their URLs
+        config.embedding.fallbacks[0] = EmbeddingConfig {
+            model: None,
+            ..fallback.clone()
+        };
+        assert!(validate_embedding_config(&config.embedding).is_err());
+        
+        config.embedding.fallbacks[0] = EmbeddingConfig {
+            fallbacks: vec![fallback.clone()],
+            ..fallback
+        };
+        assert!(validate_embedding_config(&config.embedding).is_err());
+    }
+    
     #[test]
     fn test_invalid_embedding_url() {
         let mut config = Config::default_config();
@@ -325,6 +614,17 @@ mod tests {
         assert!(validate_vector_db_config(&config.vector_db).is_err());
     }
     
+    #[test]
+    fn test_invalid_indexed_field() {
+        let mut config = Config::default_config();
+        config.vector_db.indexed_fields.push(IndexedField {
+            field: "metadata..project".to_string(),
+            kind: PayloadFieldType::Keyword,
+        });
+        
+        assert!(validate_vector_db_config(&config.vector_db).is_err());
+    }
+    
     #[test]
     fn test_invalid_relevance_threshold() {
         let mut config = Config::default_config();
@@ -343,4 +643,13 @@ mod tests {
         
         assert!(validate_hirag_config(&config.hirag).is_err());
     }
+    
+    #[test]
+    fn test_invalid_recency_decay() {
+        let mut config = Config::default_config();
+        config.hirag.recency_decay = RecencyDecay::Linear { window_secs: 0.0 };
+        
+        assert!(validate_hirag_config(&config.hirag).is_err());
+        assert!(validate_recency_decay(&RecencyDecay::Step { cutoff_secs: 600.0 }).is_ok());
+    }
 }
\ No newline at end of file
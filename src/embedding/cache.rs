@@ -1,8 +1,12 @@
-//! High-performance caching layer for embeddings using moka
+//! High-performance caching layer for embeddings using moka, optionally
+//! backed by a persistent `DiskEmbeddingCache` tier
 
+use super::DiskEmbeddingCache;
+use crate::metrics::METRICS;
 use moka::future::Cache;
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Statistics about cache performance
 #[derive(Debug, Clone)]
@@ -13,9 +17,14 @@ pub struct CacheStats {
     pub hit_rate: f64,
 }
 
-/// High-performance async cache for embeddings using moka
+/// High-performance async cache for embeddings using moka.
+///
+/// With a disk tier, lookups read through: memory misses are served from
+/// disk and promoted into memory, and writes go to both tiers. Disk access
+/// runs on the blocking pool so it never stalls the async workers.
 pub struct EmbeddingCache {
     cache: Cache<String, Vec<f32>>,
+    disk: Option<Arc<DiskEmbeddingCache>>,
 }
 
 impl EmbeddingCache {
@@ -29,24 +38,74 @@ impl EmbeddingCache {
             .time_to_idle(ttl / 2) // Evict if not accessed for half the TTL
             .build();
         
-        Self { cache }
+        Self { cache, disk: None }
+    }
+    
+    /// Back the in-memory cache with a persistent tier
+    pub fn with_disk_tier(mut self, disk: Arc<DiskEmbeddingCache>) -> Self {
+        self.disk = Some(disk);
+        self
+    }
+    
+    /// Cache key of `text` embedded by `model_id`.
+    ///
+    /// Surrounding whitespace is trimmed and inner whitespace runs collapse to
+    /// one space, so formatting-only differences share an entry.
+    pub fn key(model_id: &str, text: &str) -> String {
+        use sha2::{Sha256, Digest};
+        
+        let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut hasher = Sha256::new();
+        hasher.update(model_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(normalized.as_bytes());
+        format!("emb_{:x}", hasher.finalize())
     }
     
     /// Get embedding from cache
     pub async fn get(&self, key: &str) -> Option<Vec<f32>> {
         let result = self.cache.get(key).await;
+        METRICS.record_embedding_cache("memory", result.is_some());
         
         if result.is_some() {
             debug!("Cache hit for key: {}", key);
-        } else {
-            debug!("Cache miss for key: {}", key);
+            return result;
         }
         
-        result
+        let disk = self.disk.clone()?;
+        let disk_key = key.to_string();
+        let result = match tokio::task::spawn_blocking(move || disk.get(&disk_key)).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Disk cache lookup for key {} failed: {}", key, e);
+                None
+            }
+        };
+        METRICS.record_embedding_cache("disk", result.is_some());
+        
+        match result {
+            Some(embedding) => {
+                debug!("Disk cache hit for key: {}", key);
+                self.cache.insert(key.to_string(), embedding.clone()).await;
+                Some(embedding)
+            }
+            None => {
+                debug!("Cache miss for key: {}", key);
+                None
+            }
+        }
     }
     
     /// Store embedding in cache
     pub async fn put(&self, key: String, embedding: Vec<f32>) {
+        if let Some(disk) = self.disk.clone() {
+            let (disk_key, disk_embedding) = (key.clone(), embedding.clone());
+            match tokio::task::spawn_blocking(move || disk.put(&disk_key, &disk_embedding)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Failed to persist embedding for key {}: {}", key, e),
+                Err(e) => warn!("Failed to persist embedding for key {}: {}", key, e),
+            }
+        }
         self.cache.insert(key.clone(), embedding).await;
         debug!("Cached embedding for key: {}", key);
     }
@@ -58,10 +117,15 @@ impl EmbeddingCache {
         self.cache.run_pending_tasks().await;
     }
     
-    /// Clear all entries
+    /// Clear all entries of both tiers
     pub async fn clear(&self) {
         self.cache.invalidate_all();
         self.cache.run_pending_tasks().await;
+        if let Some(disk) = self.disk.clone() {
+            if let Err(e) = tokio::task::spawn_blocking(move || disk.clear()).await {
+                warn!("Failed to clear disk cache: {}", e);
+            }
+        }
         info!("Cache cleared");
     }
     
@@ -126,6 +190,30 @@ mod tests {
         assert_eq!(result, None);
     }
     
+    #[test]
+    fn test_key_depends_on_model_and_normalized_text() {
+        let key = EmbeddingCache::key("e5", "hello   world");
+        assert_eq!(key, EmbeddingCache::key("e5", " hello world\n"));
+        assert_ne!(key, EmbeddingCache::key("e5", "hello world!"));
+        assert_ne!(key, EmbeddingCache::key("bge", "hello world"));
+    }
+    
+    #[tokio::test]
+    async fn test_reads_through_to_disk_tier() {
+        let dir = std::env::temp_dir().join(format!("tiered-cache-{}", uuid::Uuid::new_v4()));
+        let disk = Arc::new(DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap());
+        
+        let cache = EmbeddingCache::new(10, Duration::from_secs(60)).with_disk_tier(disk.clone());
+        cache.put("test".to_string(), vec![1.0, 2.0]).await;
+        
+        // A restarted process starts with an empty memory tier
+        let cache = EmbeddingCache::new(10, Duration::from_secs(60)).with_disk_tier(disk);
+        assert_eq!(cache.get("test").await, Some(vec![1.0, 2.0]));
+        assert_eq!(cache.inner().get("test").await, Some(vec![1.0, 2.0]));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
     #[tokio::test]
     async fn test_cache_stats() {
         let cache = EmbeddingCache::new(10, Duration::from_secs(60));
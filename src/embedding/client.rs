@@ -284,10 +284,12 @@ impl EmbeddingProvider for EmbeddingClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{EmbeddingApi, EmbeddingProviderKind};
     
     #[test]
     fn test_cache_key_generation() {
         let config = EmbeddingConfig {
+            provider: EmbeddingProviderKind::Http,
             api_url: "http://test".to_string(),
             api_token: secrecy::Secret::new("test".to_string()),
             batch_size: 32,
@@ -298,6 +300,17 @@ mod tests {
             cache_size: 1000,
             tls_enabled: false,
             tls_verify: true,
+            api: EmbeddingApi::OpenAi,
+            model: None,
+            dimension: 1024,
+            fallbacks: Vec::new(),
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+            coalesce_wait_ms: 0,
+            coalesce_max_batch: 32,
+            disk_cache_dir: None,
+            disk_cache_ttl_secs: 3600,
+            disk_cache_max_bytes: 1 << 20,
         };
         
         let client = EmbeddingClient::new(config).unwrap();
@@ -1,7 +1,8 @@
 //! Enhanced embedding client with improved cache handling and error recovery
 
-use super::{EmbeddingProvider, EmbeddingCache, models::*};
-use crate::config::EmbeddingConfig;
+use super::dialect::{self, InputType};
+use super::{DiskEmbeddingCache, EmbeddingProvider, EmbeddingCache};
+use crate::config::{EmbeddingApi, EmbeddingConfig};
 use crate::error::{EmbeddingError, Result, ContextError};
 use crate::middleware::InputValidator;
 use crate::vector_db::{CircuitBreaker, CircuitBreakerConfig};
@@ -59,14 +60,7 @@ impl EmbeddingClientV2 {
             .build()
             .map_err(|e| ContextError::Embedding(EmbeddingError::NetworkError(e)))?;
         
-        let cache = if config.cache_enabled {
-            Some(Arc::new(EmbeddingCache::new(
-                config.cache_size,
-                Duration::from_secs(config.cache_ttl_secs),
-            )))
-        } else {
-            None
-        };
+        let cache = build_cache(&config)?;
         
         info!("Initialized enhanced embedding client with cache_enabled={}", config.cache_enabled);
         
@@ -97,14 +91,7 @@ impl EmbeddingClientV2 {
             ));
         }
         
-        let cache = if config.cache_enabled {
-            Some(Arc::new(EmbeddingCache::new(
-                config.cache_size,
-                Duration::from_secs(config.cache_ttl_secs),
-            )))
-        } else {
-            None
-        };
+        let cache = build_cache(&config)?;
         
         // Warn about TLS verification in release builds if custom client is provided
         #[cfg(not(debug_assertions))]
@@ -128,17 +115,49 @@ impl EmbeddingClientV2 {
         self
     }
     
-    /// Generate cache key for text using SHA-256
-    fn cache_key(&self, text: &str) -> String {
-        use sha2::{Sha256, Digest};
+    /// Cache key of `text` for this client's model; queries embedded
+    /// separately get their own keys
+    fn cache_key(&self, text: &str, input_type: InputType) -> String {
+        match input_type {
+            InputType::Query if self.embeds_queries_separately() => {
+                EmbeddingCache::key(&format!("{}#query", self.model_id()), text)
+            }
+            _ => EmbeddingCache::key(&self.model_id(), text),
+        }
+    }
+    
+    /// Embed one text through the cache
+    async fn embed_one(&self, text: &str, input_type: InputType) -> Result<Vec<f32>> {
+        // Validate input
+        InputValidator::validate_text(text)?;
+        
+        // Check cache first
+        let cache_key = self.cache_key(text, input_type);
+        if let Some(cache) = &self.cache {
+            if let Some(cached) = cache.get(&cache_key).await {
+                debug!("Cache hit for embedding");
+                return Ok(cached);
+            }
+        }
+        
+        // Make request
+        let embedding = self
+            .make_request(&[text.to_string()], input_type)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ContextError::Embedding(EmbeddingError::ApiError("No embedding in response".to_string())))?;
+        
+        // Store in cache
+        if let Some(cache) = &self.cache {
+            cache.put(cache_key, embedding.clone()).await;
+        }
         
-        let mut hasher = Sha256::new();
-        hasher.update(text.as_bytes());
-        format!("emb_{:x}", hasher.finalize())
+        Ok(embedding)
     }
     
-    /// Make API request with retry logic and adaptive backoff
-    async fn make_request(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+    /// Embed `texts` in one API request with retry logic and adaptive backoff
+    async fn make_request(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
         // Check circuit breaker first
         if let Some(cb) = &self.circuit_breaker {
             if !cb.allow_request().await {
@@ -149,19 +168,21 @@ impl EmbeddingClientV2 {
             }
         }
         
+        let api = self.config.api;
+        let body = dialect::request_body(api, self.config.model.as_deref(), texts, input_type);
         let mut attempts = 0;
         let max_retries = self.config.max_retries;
         
         loop {
             attempts += 1;
             
-            match self.http_client
-                .post(&self.config.api_url)
-                .bearer_auth(self.config.api_token.expose_secret())
-                .json(request)
-                .send()
-                .await
-            {
+            let mut request = self.http_client.post(&self.config.api_url).json(&body);
+            // Local TEI and Ollama servers usually run without authentication
+            if !self.config.api_token.expose_secret().is_empty() {
+                request = request.bearer_auth(self.config.api_token.expose_secret());
+            }
+            
+            match request.send().await {
                 Ok(response) => {
                     // Record success for circuit breaker
                     if let Some(cb) = &self.circuit_breaker {
@@ -170,10 +191,21 @@ impl EmbeddingClientV2 {
                     
                     let status = response.status();
                     if status.is_success() {
-                        match response.json::<EmbeddingResponse>().await {
-                            Ok(embedding_response) => {
+                        let parsed = match response.bytes().await {
+                            Ok(bytes) => dialect::parse_response(api, &bytes).map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        match parsed {
+                            Ok(embeddings) if embeddings.len() == texts.len() => {
                                 debug!("Embedding request successful after {} attempts", attempts);
-                                return Ok(embedding_response);
+                                return Ok(embeddings);
+                            }
+                            Ok(embeddings) => {
+                                return Err(ContextError::Embedding(EmbeddingError::ApiError(format!(
+                                    "Expected {} embeddings, got {}",
+                                    texts.len(),
+                                    embeddings.len()
+                                ))));
                             }
                             Err(e) => {
                                 error!("Failed to parse embedding response: {}", e);
@@ -193,8 +225,14 @@ impl EmbeddingClientV2 {
                             }
                         }
                     } else {
+                        // A rejected input says nothing about the service's health
+                        let rejected_input = matches!(
+                            status,
+                            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::UNPROCESSABLE_ENTITY
+                        );
+                        
                         // Record failure for circuit breaker
-                        if let Some(cb) = &self.circuit_breaker {
+                        if let Some(cb) = self.circuit_breaker.as_ref().filter(|_| !rejected_input) {
                             cb.record_failure().await;
                         }
                         
@@ -202,6 +240,12 @@ impl EmbeddingClientV2 {
                         error!("Embedding API error {}: {}", status, error_text);
                         
                         match status {
+                            _ if rejected_input => {
+                                return Err(ContextError::Embedding(EmbeddingError::InvalidInput(format!(
+                                    "API error {}: {}",
+                                    status, error_text
+                                ))));
+                            }
                             StatusCode::TOO_MANY_REQUESTS => {
                                 if attempts <= max_retries {
                                     // Exponential backoff with jitter for rate limiting
@@ -251,44 +295,33 @@ impl EmbeddingClientV2 {
     }
 }
 
+/// In-memory cache from config, backed by the disk tier when `disk_cache_dir` is set
+fn build_cache(config: &EmbeddingConfig) -> Result<Option<Arc<EmbeddingCache>>> {
+    if !config.cache_enabled {
+        return Ok(None);
+    }
+    
+    let mut cache = EmbeddingCache::new(config.cache_size, Duration::from_secs(config.cache_ttl_secs));
+    if let Some(dir) = &config.disk_cache_dir {
+        cache = cache.with_disk_tier(Arc::new(DiskEmbeddingCache::open(
+            dir,
+            Duration::from_secs(config.disk_cache_ttl_secs),
+            config.disk_cache_max_bytes,
+        )?));
+    }
+    Ok(Some(Arc::new(cache)))
+}
+
 #[async_trait]
 impl EmbeddingProvider for EmbeddingClientV2 {
     /// Generate embedding for a single text
     async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
-        // Validate input
-        InputValidator::validate_text(text)?;
-        
-        // Check cache first
-        if let Some(cache) = &self.cache {
-            if let Some(cached) = cache.get(text).await {
-                debug!("Cache hit for embedding");
-                return Ok(cached);
-            }
-        }
-        
-        // Generate cache key
-        let cache_key = self.cache_key(text);
-        
-        // Prepare request
-        let request = EmbeddingRequest::single(text.to_string());
-        
-        // Make request
-        let response = self.make_request(&request).await?;
-        
-        // Extract embedding
-        let embedding = response
-            .data
-            .into_iter()
-            .next()
-            .ok_or_else(|| ContextError::Embedding(EmbeddingError::ApiError("No embedding in response".to_string())))?
-            .embedding;
-        
-        // Store in cache
-        if let Some(cache) = &self.cache {
-            cache.put(cache_key, embedding.clone()).await;
-        }
-        
-        Ok(embedding)
+        self.embed_one(text, InputType::Document).await
+    }
+    
+    /// Generate embedding for a search query
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_one(text, InputType::Query).await
     }
     
     /// Generate embeddings for multiple texts
@@ -310,7 +343,7 @@ impl EmbeddingProvider for EmbeddingClientV2 {
             
             if let Some(cache) = &self.cache {
                 for (i, text) in chunk.iter().enumerate() {
-                    if let Some(cached) = cache.get(text).await {
+                    if let Some(cached) = cache.get(&self.cache_key(text, InputType::Document)).await {
                         batch_results.push((i, cached));
                     } else {
                         uncached_texts.push(text.clone());
@@ -324,15 +357,12 @@ impl EmbeddingProvider for EmbeddingClientV2 {
             
             // Generate embeddings for uncached texts
             if !uncached_texts.is_empty() {
-                let request = EmbeddingRequest::batch(uncached_texts.clone());
-                
-                let response = self.make_request(&request).await?;
+                let embeddings = self.make_request(&uncached_texts, InputType::Document).await?;
                 
-                // Extract embeddings and store in cache
-                for (i, embedding_data) in response.data.into_iter().enumerate() {
-                    let embedding = embedding_data.embedding;
+                // Store in cache
+                for (i, embedding) in embeddings.into_iter().enumerate() {
                     if let Some(cache) = &self.cache {
-                        cache.put(self.cache_key(&uncached_texts[i]), embedding.clone()).await;
+                        cache.put(self.cache_key(&uncached_texts[i], InputType::Document), embedding.clone()).await;
                     }
                     batch_results.push((uncached_indices[i], embedding));
                 }
@@ -348,19 +378,36 @@ impl EmbeddingProvider for EmbeddingClientV2 {
     
     /// Get the dimension of embeddings
     fn embedding_dimension(&self) -> usize {
-        // multilingual-e5-large has 1024 dimensions
-        1024
+        self.config.dimension
+    }
+    
+    /// The configured model, or the endpoint URL when the API takes no model;
+    /// config validation requires a model in failover chains
+    fn model_id(&self) -> String {
+        self.config.model.clone().unwrap_or_else(|| self.config.api_url.clone())
+    }
+    
+    /// Cohere embeds queries with the `search_query` input type
+    fn embeds_queries_separately(&self) -> bool {
+        self.config.api == EmbeddingApi::Cohere
+    }
+    
+    fn circuit_breaker(&self) -> Option<Arc<CircuitBreaker>> {
+        self.circuit_breaker.clone()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::EmbeddingProviderKind;
+    use mockito::Matcher;
     use secrecy::Secret;
     
     #[tokio::test]
     async fn test_cache_key_generation() {
         let config = EmbeddingConfig {
+            provider: EmbeddingProviderKind::Http,
             api_url: "https://api.example.com".to_string(),
             api_token: Secret::new("test-token".to_string()),
             batch_size: 32,
@@ -371,14 +418,151 @@ mod tests {
             cache_size: 1000,
             tls_enabled: false,
             tls_verify: true,
+            api: EmbeddingApi::OpenAi,
+            model: None,
+            dimension: 1024,
+            fallbacks: Vec::new(),
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+            coalesce_wait_ms: 0,
+            coalesce_max_batch: 32,
+            disk_cache_dir: None,
+            disk_cache_ttl_secs: 3600,
+            disk_cache_max_bytes: 1 << 20,
         };
         
         let client = EmbeddingClientV2::new(config).unwrap();
-        let key1 = client.cache_key("test text");
-        let key2 = client.cache_key("test text");
-        let key3 = client.cache_key("different text");
+        let key1 = client.cache_key("test text", InputType::Document);
+        let key2 = client.cache_key("test text", InputType::Document);
+        let key3 = client.cache_key("different text", InputType::Document);
         
         assert_eq!(key1, key2);
         assert_ne!(key1, key3);
+        // Queries share keys with documents unless the API embeds them differently
+        assert_eq!(client.cache_key("test text", InputType::Query), key1);
+    }
+    
+    fn dialect_client(url: String, api: EmbeddingApi, model: Option<&str>, token: &str) -> EmbeddingClientV2 {
+        let mut config = crate::config::Config::default_config().embedding;
+        config.api_url = url;
+        config.api = api;
+        config.model = model.map(str::to_string);
+        config.api_token = Secret::new(token.to_string());
+        config.max_retries = 0;
+        EmbeddingClientV2::new(config).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_openai_dialect() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/embeddings")
+            .match_header("authorization", "Bearer secret")
+            .match_body(Matcher::PartialJson(serde_json::json!({"input": ["a", "b"], "model": "e5"})))
+            .with_body(
+                r#"{"data": [{"embedding": [0.0, 1.0], "index": 1, "object": "embedding"},
+                             {"embedding": [1.0, 0.0], "index": 0, "object": "embedding"}],
+                    "model": "e5", "usage": {"prompt_tokens": 2, "total_tokens": 2}}"#,
+            )
+            .create_async()
+            .await;
+        
+        let client = dialect_client(format!("{}/v1/embeddings", server.url()), EmbeddingApi::OpenAi, Some("e5"), "secret");
+        let embeddings = client.embed_batch(&["a".to_string(), "b".to_string()]).await.unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        mock.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_tei_dialect_shares_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/embed")
+            .match_header("authorization", Matcher::Missing)
+            .match_body(Matcher::Json(serde_json::json!({"inputs": ["hello"]})))
+            .with_body("[[0.5, 0.5]]")
+            .expect(1)
+            .create_async()
+            .await;
+        
+        let client = dialect_client(format!("{}/embed", server.url()), EmbeddingApi::Tei, None, "");
+        assert_eq!(client.embed_single("hello").await.unwrap(), vec![0.5, 0.5]);
+        assert_eq!(client.embed_single("hello").await.unwrap(), vec![0.5, 0.5]);
+        mock.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_ollama_dialect() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/embed")
+            .match_body(Matcher::Json(serde_json::json!({"model": "nomic-embed-text", "input": ["a", "b"]})))
+            .with_body(r#"{"model": "nomic-embed-text", "embeddings": [[0.1, 0.2], [0.3, 0.4]]}"#)
+            .create_async()
+            .await;
+        
+        let client = dialect_client(format!("{}/api/embed", server.url()), EmbeddingApi::Ollama, Some("nomic-embed-text"), "");
+        let embeddings = client.embed_batch(&["a".to_string(), "b".to_string()]).await.unwrap();
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        mock.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_cohere_dialect_parses_both_versions() {
+        let mut server = mockito::Server::new_async().await;
+        let body = Matcher::PartialJson(serde_json::json!({
+            "model": "embed-english-v3.0",
+            "texts": ["hello"],
+            "input_type": "search_document",
+        }));
+        let v1 = server
+            .mock("POST", "/v1/embed")
+            .match_body(body.clone())
+            .with_body(r#"{"id": "1", "embeddings": [[0.25, 0.75]], "texts": ["hello"]}"#)
+            .create_async()
+            .await;
+        let v2 = server
+            .mock("POST", "/v2/embed")
+            .match_body(body)
+            .with_body(r#"{"id": "2", "embeddings": {"float": [[0.75, 0.25]]}, "texts": ["hello"]}"#)
+            .create_async()
+            .await;
+        
+        let model = Some("embed-english-v3.0");
+        let client = dialect_client(format!("{}/v1/embed", server.url()), EmbeddingApi::Cohere, model, "key");
+        assert_eq!(client.embed_single("hello").await.unwrap(), vec![0.25, 0.75]);
+        let client = dialect_client(format!("{}/v2/embed", server.url()), EmbeddingApi::Cohere, model, "key");
+        assert_eq!(client.embed_single("hello").await.unwrap(), vec![0.75, 0.25]);
+        
+        v1.assert_async().await;
+        v2.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_cohere_queries_use_search_query() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v2/embed")
+            .match_body(Matcher::PartialJson(serde_json::json!({"texts": ["hello"], "input_type": "search_query"})))
+            .with_body(r#"{"id": "1", "embeddings": {"float": [[1.0, 0.0]]}, "texts": ["hello"]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        
+        let client = dialect_client(format!("{}/v2/embed", server.url()), EmbeddingApi::Cohere, Some("embed-english-v3.0"), "key");
+        assert_eq!(client.embed_query("hello").await.unwrap(), vec![1.0, 0.0]);
+        // Cached apart from the document embedding of the same text
+        assert_eq!(client.embed_query("hello").await.unwrap(), vec![1.0, 0.0]);
+        assert_ne!(client.cache_key("hello", InputType::Query), client.cache_key("hello", InputType::Document));
+        mock.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_embedding_count_mismatch_is_an_error() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server.mock("POST", "/embed").with_body("[[0.5, 0.5]]").create_async().await;
+        
+        let client = dialect_client(format!("{}/embed", server.url()), EmbeddingApi::Tei, None, "");
+        assert!(client.embed_batch(&["a".to_string(), "b".to_string()]).await.is_err());
     }
 }
\ No newline at end of file
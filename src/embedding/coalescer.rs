@@ -0,0 +1,276 @@
+//! Micro-batching of concurrent `embed_single` calls
+
+use super::EmbeddingProvider;
+use crate::error::{ContextError, EmbeddingError, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::time::{timeout_at, Instant};
+use tracing::debug;
+
+/// A queued `embed_single` call awaiting its batch
+type Pending = (String, oneshot::Sender<Result<Vec<f32>>>);
+
+/// Batches embedded at once; further batches wait for one to finish
+const MAX_IN_FLIGHT_BATCHES: usize = 4;
+
+/// Coalesces concurrent `embed_single` calls into `embed_batch` calls.
+///
+/// A batch is sent once it holds `max_batch` texts or `max_wait` after its
+/// first text arrived. When the provider rejects an input of a batch, its
+/// texts are retried one by one so a single bad input does not fail the
+/// others; any other failure, such as an outage, is returned to every caller
+/// without retrying. At most `MAX_IN_FLIGHT_BATCHES` batches are in flight
+/// and as many more are queued; callers beyond that wait for room.
+/// `embed_batch` calls bypass the queue, as do `embed_query` calls when the
+/// inner provider embeds queries separately.
+pub struct CoalescingEmbedder {
+    inner: Arc<dyn EmbeddingProvider>,
+    queue: mpsc::Sender<Pending>,
+}
+
+impl CoalescingEmbedder {
+    /// Wrap `inner`; spawns the batching task, so it must be called within a Tokio runtime
+    pub fn new(inner: Arc<dyn EmbeddingProvider>, max_wait: Duration, max_batch: usize) -> Self {
+        let max_batch = max_batch.max(1);
+        let (queue, receiver) = mpsc::channel(max_batch * MAX_IN_FLIGHT_BATCHES);
+        tokio::spawn(collect(inner.clone(), receiver, max_wait, max_batch));
+        Self { inner, queue }
+    }
+}
+
+/// Gather queued calls into batches until every sender is dropped
+async fn collect(
+    inner: Arc<dyn EmbeddingProvider>,
+    mut receiver: mpsc::Receiver<Pending>,
+    max_wait: Duration,
+    max_batch: usize,
+) {
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT_BATCHES));
+
+    while let Some(first) = receiver.recv().await {
+        let deadline = Instant::now() + max_wait;
+        let mut batch = vec![first];
+
+        while batch.len() < max_batch {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                _ => break,
+            }
+        }
+
+        // Keep collecting the next batch while this one is in flight; the
+        // semaphore is never closed, so acquiring only waits
+        let Ok(permit) = in_flight.clone().acquire_owned().await else {
+            return;
+        };
+        tokio::spawn(flush(inner.clone(), batch, permit));
+    }
+}
+
+/// Whether `error` is about the input rather than the provider, so the
+/// other texts of its batch may still embed on their own
+fn is_input_error(error: &ContextError) -> bool {
+    matches!(
+        error,
+        ContextError::Embedding(EmbeddingError::InvalidInput(_)) | ContextError::Validation(_)
+    )
+}
+
+/// Copy of a batch `error` for one of its callers, keeping the variant where it can be rebuilt
+fn copy_error(error: &ContextError) -> ContextError {
+    let embedding = match error {
+        ContextError::Embedding(e) => match e {
+            EmbeddingError::ApiError(message) => EmbeddingError::ApiError(message.clone()),
+            EmbeddingError::InvalidInput(message) => EmbeddingError::InvalidInput(message.clone()),
+            EmbeddingError::RateLimitExceeded => EmbeddingError::RateLimitExceeded,
+            EmbeddingError::AuthenticationFailed => EmbeddingError::AuthenticationFailed,
+            EmbeddingError::Timeout(secs) => EmbeddingError::Timeout(*secs),
+            EmbeddingError::ServiceUnavailable(message) => EmbeddingError::ServiceUnavailable(message.clone()),
+            EmbeddingError::CacheError(message) => EmbeddingError::CacheError(message.clone()),
+            EmbeddingError::NetworkError(_) | EmbeddingError::SerializationError(_) => {
+                EmbeddingError::ApiError(e.to_string())
+            }
+        },
+        other => return ContextError::Internal(other.to_string()),
+    };
+    ContextError::Embedding(embedding)
+}
+
+/// Embed one batch and hand each caller its result, then release `_permit`
+async fn flush(inner: Arc<dyn EmbeddingProvider>, batch: Vec<Pending>, _permit: OwnedSemaphorePermit) {
+    let (texts, senders): (Vec<String>, Vec<_>) = batch.into_iter().unzip();
+    debug!("Coalesced {} embed_single calls into one batch", texts.len());
+
+    let result = match inner.embed_batch(&texts).await {
+        Ok(vectors) if vectors.len() != texts.len() => Err(ContextError::Embedding(EmbeddingError::ApiError(
+            format!("Expected {} embeddings, got {}", texts.len(), vectors.len()),
+        ))),
+        result => result,
+    };
+
+    match result {
+        Ok(vectors) => {
+            for (sender, vector) in senders.into_iter().zip(vectors) {
+                let _ = sender.send(Ok(vector));
+            }
+        }
+        Err(e) if texts.len() == 1 => {
+            let _ = senders.into_iter().next().expect("batch is never empty").send(Err(e));
+        }
+        Err(e) if !is_input_error(&e) => {
+            for sender in senders {
+                let _ = sender.send(Err(copy_error(&e)));
+            }
+        }
+        Err(e) => {
+            debug!("Batch of {} rejected an input, embedding texts one by one: {}", texts.len(), e);
+            let results = join_all(texts.iter().map(|text| inner.embed_single(text))).await;
+            for (sender, result) in senders.into_iter().zip(results) {
+                let _ = sender.send(result);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CoalescingEmbedder {
+    async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+        let (sender, receiver) = oneshot::channel();
+        let closed = || ContextError::Embedding(EmbeddingError::ServiceUnavailable("Embedding batcher stopped".to_string()));
+
+        // Waits for room when the queue is full
+        self.queue.send((text.to_string(), sender)).await.map_err(|_| closed())?;
+        receiver.await.map_err(|_| closed())?
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed_batch(texts).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        // Batches are embedded as stored texts, so distinct query embeddings skip the queue
+        if self.inner.embeds_queries_separately() {
+            return self.inner.embed_query(text).await;
+        }
+        self.embed_single(text).await
+    }
+
+    fn embedding_dimension(&self) -> usize {
+        self.inner.embedding_dimension()
+    }
+
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    fn is_production(&self) -> bool {
+        self.inner.is_production()
+    }
+
+    fn embeds_queries_separately(&self) -> bool {
+        self.inner.embeds_queries_separately()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embeds a text as `[len]`, rejects "bad", fails on "down" and counts calls
+    #[derive(Default)]
+    struct CountingProvider {
+        batches: AtomicUsize,
+        singles: AtomicUsize,
+    }
+
+    impl CountingProvider {
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            match text {
+                "bad" => Err(ContextError::Embedding(EmbeddingError::InvalidInput(text.to_string()))),
+                "down" => Err(ContextError::Embedding(EmbeddingError::ServiceUnavailable(text.to_string()))),
+                _ => Ok(vec![text.len() as f32]),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+            self.singles.fetch_add(1, Ordering::SeqCst);
+            self.embed(text)
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            let mut vectors = Vec::with_capacity(texts.len());
+            for text in texts {
+                vectors.push(self.embed(text)?);
+            }
+            Ok(vectors)
+        }
+
+        fn embedding_dimension(&self) -> usize {
+            1
+        }
+    }
+
+    async fn embed_concurrently(embedder: &CoalescingEmbedder, texts: &[&str]) -> Vec<Result<Vec<f32>>> {
+        join_all(texts.iter().map(|text| embedder.embed_single(text))).await
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_batches() {
+        let inner = Arc::new(CountingProvider::default());
+        let embedder = CoalescingEmbedder::new(inner.clone(), Duration::from_millis(50), 2);
+
+        let results = embed_concurrently(&embedder, &["a", "bb", "ccc", "dddd", "eeeee"]).await;
+        let vectors: Vec<_> = results.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(vectors, vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]);
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_returned_per_item() {
+        let inner = Arc::new(CountingProvider::default());
+        let embedder = CoalescingEmbedder::new(inner.clone(), Duration::from_millis(50), 8);
+
+        let results = embed_concurrently(&embedder, &["a", "bad", "ccc"]).await;
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 1);
+        assert_eq!(results[0].as_ref().unwrap(), &vec![1.0]);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), &vec![3.0]);
+        assert_eq!(inner.singles.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_provider_failure_is_not_retried_per_item() {
+        let inner = Arc::new(CountingProvider::default());
+        let embedder = CoalescingEmbedder::new(inner.clone(), Duration::from_millis(50), 8);
+
+        let results = embed_concurrently(&embedder, &["a", "down", "ccc"]).await;
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 1);
+        assert_eq!(inner.singles.load(Ordering::SeqCst), 0);
+        for result in results {
+            assert!(matches!(
+                result,
+                Err(ContextError::Embedding(EmbeddingError::ServiceUnavailable(_)))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_waits_for_room() {
+        let inner = Arc::new(CountingProvider::default());
+        let embedder = CoalescingEmbedder::new(inner.clone(), Duration::from_millis(1), 1);
+
+        // Far more callers than the queue and in-flight batches hold
+        let texts = vec!["a"; 64];
+        let results = embed_concurrently(&embedder, &texts).await;
+        assert!(results.iter().all(|r| r.as_ref().unwrap() == &vec![1.0]));
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 64);
+    }
+}
@@ -0,0 +1,94 @@
+//! Request building and response parsing for the supported embedding APIs
+
+use super::models::{EmbeddingInput, EmbeddingRequest, EmbeddingResponse};
+use crate::config::EmbeddingApi;
+use serde::Deserialize;
+
+/// What the embedded texts are used for; only Cohere embeds the two differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputType {
+    /// Texts stored for retrieval
+    Document,
+    /// Search queries matched against stored documents
+    Query,
+}
+
+impl InputType {
+    /// Cohere `input_type` of the texts
+    fn cohere(self) -> &'static str {
+        match self {
+            InputType::Document => "search_document",
+            InputType::Query => "search_query",
+        }
+    }
+}
+
+/// Ollama `/api/embed` response
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Cohere `/v1/embed` and `/v2/embed` response
+#[derive(Debug, Deserialize)]
+struct CohereResponse {
+    embeddings: CohereEmbeddings,
+}
+
+/// Cohere returns a bare list unless `embedding_types` is given, which `/v2/embed` requires
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CohereEmbeddings {
+    Float(Vec<Vec<f32>>),
+    ByType { float: Vec<Vec<f32>> },
+}
+
+/// JSON body embedding `texts` in the format of `api`
+pub(crate) fn request_body(
+    api: EmbeddingApi,
+    model: Option<&str>,
+    texts: &[String],
+    input_type: InputType,
+) -> serde_json::Value {
+    match api {
+        EmbeddingApi::OpenAi => {
+            let input = match texts {
+                [text] => EmbeddingInput::Single(text.clone()),
+                _ => EmbeddingInput::Batch(texts.to_vec()),
+            };
+            serde_json::json!(EmbeddingRequest {
+                input,
+                model: model.map(str::to_string),
+            })
+        }
+        EmbeddingApi::Tei => serde_json::json!({
+            "inputs": texts,
+        }),
+        EmbeddingApi::Ollama => serde_json::json!({
+            "model": model,
+            "input": texts,
+        }),
+        EmbeddingApi::Cohere => serde_json::json!({
+            "model": model,
+            "texts": texts,
+            "input_type": input_type.cohere(),
+            "embedding_types": ["float"],
+        }),
+    }
+}
+
+/// Embeddings in a response `body` of `api`, in request order
+pub(crate) fn parse_response(api: EmbeddingApi, body: &[u8]) -> serde_json::Result<Vec<Vec<f32>>> {
+    match api {
+        EmbeddingApi::OpenAi => {
+            let mut data = serde_json::from_slice::<EmbeddingResponse>(body)?.data;
+            data.sort_by_key(|d| d.index);
+            Ok(data.into_iter().map(|d| d.embedding).collect())
+        }
+        EmbeddingApi::Tei => serde_json::from_slice(body),
+        EmbeddingApi::Ollama => serde_json::from_slice::<OllamaResponse>(body).map(|r| r.embeddings),
+        EmbeddingApi::Cohere => serde_json::from_slice::<CohereResponse>(body).map(|r| match r.embeddings {
+            CohereEmbeddings::Float(embeddings) | CohereEmbeddings::ByType { float: embeddings } => embeddings,
+        }),
+    }
+}
@@ -0,0 +1,456 @@
+//! Persistent second tier of the embedding cache
+//!
+//! Entries are stored one per file, named by the SHA-256 of their cache key
+//! and sharded by its first two hex digits:
+//!
+//! ```text
+//! <dir>/3f/3fa9...c1.emb
+//! ```
+//!
+//! Each file holds a MessagePack `DiskEntry`, written to a temporary file and
+//! renamed into place so readers never see a partial entry. The size, age and
+//! last use of every entry are indexed in memory (rebuilt from file metadata
+//! on open, which counts as the last use) to evict the least recently used
+//! entries once the cache outgrows `max_bytes`.
+
+use super::{EmbeddingCache, EMBEDDING_MODEL_KEY};
+use crate::error::{ContextError, EmbeddingError, Result};
+use crate::vector_db::ExportReader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+const ENTRY_SUFFIX: &str = ".emb";
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Eviction shrinks the cache to this fraction of `max_bytes`, so it does not run on every write
+const EVICTION_TARGET: f64 = 0.9;
+
+/// Numbers temporary files, so concurrent writes of one entry never share one
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Contents of one entry file
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskEntry {
+    /// Unix time in milliseconds
+    created_at: i64,
+    embedding: Vec<f32>,
+}
+
+/// Indexed facts about one entry file; times are unix millis
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    bytes: u64,
+    created_at: i64,
+    last_used: i64,
+}
+
+/// Every entry by file stem
+#[derive(Debug, Default)]
+struct DiskIndex {
+    entries: HashMap<String, IndexEntry>,
+    total_bytes: u64,
+}
+
+impl DiskIndex {
+    fn insert(&mut self, name: String, entry: IndexEntry) {
+        if let Some(old) = self.entries.insert(name, entry) {
+            self.total_bytes -= old.bytes;
+        }
+        self.total_bytes += entry.bytes;
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some(entry) = self.entries.remove(name) {
+            self.total_bytes -= entry.bytes;
+        }
+    }
+}
+
+/// Outcome of warming the cache from an export file
+#[derive(Debug, Clone, Default)]
+pub struct WarmupReport {
+    /// Embeddings written to the cache
+    pub loaded: usize,
+    /// Records without a vector, or with one of another dimension or model
+    pub skipped: usize,
+}
+
+/// Embedding cache persisted to a local directory, bounded by age and total size
+pub struct DiskEmbeddingCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+    index: Mutex<DiskIndex>,
+}
+
+impl DiskEmbeddingCache {
+    /// Open the cache at `dir`, indexing the entries already stored there
+    pub fn open(dir: impl Into<PathBuf>, ttl: Duration, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(cache_err)?;
+
+        let mut index = DiskIndex::default();
+        for shard in fs::read_dir(&dir).map_err(cache_err)? {
+            let shard = shard.map_err(cache_err)?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+
+            for file in fs::read_dir(&shard).map_err(cache_err)? {
+                let path = file.map_err(cache_err)?.path();
+                let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+
+                if file_name.ends_with(TEMP_SUFFIX) {
+                    // Left behind by a crash mid-write
+                    let _ = fs::remove_file(&path);
+                } else if let Some(name) = file_name.strip_suffix(ENTRY_SUFFIX) {
+                    let metadata = fs::metadata(&path).map_err(cache_err)?;
+                    let created_at = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map_or(0, |d| d.as_millis() as i64);
+                    index.insert(
+                        name.to_string(),
+                        IndexEntry {
+                            bytes: metadata.len(),
+                            created_at,
+                            last_used: created_at,
+                        },
+                    );
+                }
+            }
+        }
+
+        info!(
+            "Opened disk embedding cache at {} with {} entries ({} bytes)",
+            dir.display(),
+            index.entries.len(),
+            index.total_bytes
+        );
+
+        let cache = Self {
+            dir,
+            ttl,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Embedding stored under `key`, unless missing or older than the TTL; a hit marks the entry as recently used
+    pub fn get(&self, key: &str) -> Option<Vec<f32>> {
+        let name = file_stem(key);
+        let path = self.path(&name);
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                // Deleted by eviction or another process since it was indexed
+                self.lock().remove(&name);
+                return None;
+            }
+            Err(e) => {
+                warn!("Failed to read disk cache entry {}: {}", path.display(), e);
+                return None;
+            }
+        };
+
+        match rmp_serde::from_slice::<DiskEntry>(&bytes) {
+            Ok(entry) if !self.expired(entry.created_at) => {
+                let now = chrono::Utc::now().timestamp_millis();
+                let mut index = self.lock();
+                match index.entries.get_mut(&name) {
+                    Some(indexed) => indexed.last_used = now,
+                    // Entries written by another process are indexed on first read
+                    None => index.insert(
+                        name,
+                        IndexEntry {
+                            bytes: bytes.len() as u64,
+                            created_at: entry.created_at,
+                            last_used: now,
+                        },
+                    ),
+                }
+                drop(index);
+                Some(entry.embedding)
+            }
+            Ok(_) => {
+                self.remove(&name);
+                None
+            }
+            Err(e) => {
+                warn!("Dropping corrupt disk cache entry {}: {}", path.display(), e);
+                self.remove(&name);
+                None
+            }
+        }
+    }
+
+    /// Store `embedding` under `key`, evicting the least recently used entries if the cache is full
+    pub fn put(&self, key: &str, embedding: &[f32]) -> Result<()> {
+        let name = file_stem(key);
+        let path = self.path(&name);
+        let created_at = chrono::Utc::now().timestamp_millis();
+
+        let bytes = rmp_serde::to_vec(&DiskEntry {
+            created_at,
+            embedding: embedding.to_vec(),
+        })
+        .map_err(|e| ContextError::Embedding(EmbeddingError::CacheError(e.to_string())))?;
+
+        if let Some(shard) = path.parent() {
+            fs::create_dir_all(shard).map_err(cache_err)?;
+        }
+        let temp = path.with_file_name(format!(
+            "{}.{}-{}{}",
+            name,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+            TEMP_SUFFIX
+        ));
+        if let Err(e) = fs::write(&temp, &bytes).and_then(|_| fs::rename(&temp, &path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(cache_err(e));
+        }
+
+        let over_capacity = {
+            let mut index = self.lock();
+            index.insert(
+                name,
+                IndexEntry {
+                    bytes: bytes.len() as u64,
+                    created_at,
+                    last_used: created_at,
+                },
+            );
+            index.total_bytes > self.max_bytes
+        };
+        if over_capacity {
+            self.evict();
+        }
+        Ok(())
+    }
+
+    /// Remove every entry
+    pub fn clear(&self) {
+        let index = std::mem::take(&mut *self.lock());
+        for name in index.entries.keys() {
+            let _ = fs::remove_file(self.path(name));
+        }
+    }
+
+    /// Number of indexed entries
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total size of indexed entries in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.lock().total_bytes
+    }
+
+    /// Load the vectors of an export file as embeddings of their texts by `model_id`.
+    ///
+    /// Records need a vector of `dimension` values. Records tagged with another
+    /// model are skipped; untagged records (exported before model IDs were
+    /// recorded) are trusted when their dimension matches.
+    pub fn warm_from_export(&self, path: &Path, model_id: &str, dimension: usize) -> Result<WarmupReport> {
+        let mut reader = ExportReader::open(path)?;
+        let mut report = WarmupReport::default();
+
+        while let Some(record) = reader.next_record()? {
+            let recorded_model = record.payload.metadata.get(EMBEDDING_MODEL_KEY).and_then(|v| v.as_str());
+            match record.vector {
+                Some(vector) if vector.len() == dimension && recorded_model.is_none_or(|m| m == model_id) => {
+                    self.put(&EmbeddingCache::key(model_id, &record.payload.text), &vector)?;
+                    report.loaded += 1;
+                }
+                _ => report.skipped += 1,
+            }
+        }
+
+        info!(
+            "Warmed disk embedding cache from {}: {} loaded, {} skipped",
+            path.display(),
+            report.loaded,
+            report.skipped
+        );
+        Ok(report)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(&name[..2]).join(format!("{}{}", name, ENTRY_SUFFIX))
+    }
+
+    fn expired(&self, created_at: i64) -> bool {
+        chrono::Utc::now().timestamp_millis() - created_at > self.ttl.as_millis() as i64
+    }
+
+    fn remove(&self, name: &str) {
+        let _ = fs::remove_file(self.path(name));
+        self.lock().remove(name);
+    }
+
+    /// Delete expired entries, then the least recently used ones until the cache fits its size cap
+    fn evict(&self) {
+        let target = (self.max_bytes as f64 * EVICTION_TARGET) as u64;
+
+        // Unindex the victims under the lock, but delete their files after releasing it
+        let (victims, bytes_left) = {
+            let mut index = self.lock();
+            let mut entries: Vec<(String, IndexEntry)> = index.entries.iter().map(|(name, entry)| (name.clone(), *entry)).collect();
+            entries.sort_by_key(|(_, entry)| entry.last_used);
+
+            let mut victims = Vec::new();
+            for (name, entry) in entries {
+                if self.expired(entry.created_at) || index.total_bytes > target {
+                    index.remove(&name);
+                    victims.push(name);
+                }
+            }
+            (victims, index.total_bytes)
+        };
+
+        for name in &victims {
+            let _ = fs::remove_file(self.path(name));
+        }
+        if !victims.is_empty() {
+            debug!("Evicted {} disk cache entries, {} bytes left", victims.len(), bytes_left);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DiskIndex> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// File name of `key`: hex SHA-256, so any key maps to a safe, evenly sharded path
+fn file_stem(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn cache_err(e: std::io::Error) -> ContextError {
+    ContextError::Embedding(EmbeddingError::CacheError(format!("Disk cache: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("disk-cache-{}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_entries_survive_reopen() {
+        let dir = temp_dir();
+        let cache = DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap();
+        cache.put("a", &[1.0, 2.0]).unwrap();
+        assert_eq!(cache.get("a"), Some(vec![1.0, 2.0]));
+        assert_eq!(cache.get("b"), None);
+        drop(cache);
+
+        let cache = DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("a"), Some(vec![1.0, 2.0]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_size_cap_evicts_oldest() {
+        let dir = temp_dir();
+        let cache = DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap();
+        cache.put("probe", &[0.0; 64]).unwrap();
+        let entry_bytes = cache.size_bytes();
+        cache.clear();
+
+        let cache = DiskEmbeddingCache { max_bytes: entry_bytes * 3, ..cache };
+        for key in ["k0", "k1", "k2", "k3"] {
+            cache.put(key, &[0.0; 64]).unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        assert!(cache.size_bytes() <= entry_bytes * 3);
+        assert!(cache.get("k0").is_none());
+        assert!(cache.get("k3").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_size_cap_evicts_least_recently_used() {
+        let dir = temp_dir();
+        let cache = DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap();
+        cache.put("probe", &[0.0; 64]).unwrap();
+        let entry_bytes = cache.size_bytes();
+        cache.clear();
+
+        let cache = DiskEmbeddingCache { max_bytes: entry_bytes * 3, ..cache };
+        for key in ["k0", "k1", "k2"] {
+            cache.put(key, &[0.0; 64]).unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        // Reading the oldest entry keeps it over the unread ones
+        assert!(cache.get("k0").is_some());
+        std::thread::sleep(Duration::from_millis(2));
+        cache.put("k3", &[0.0; 64]).unwrap();
+
+        assert!(cache.get("k0").is_some());
+        assert!(cache.get("k1").is_none());
+        assert!(cache.get("k3").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writes_of_one_key() {
+        let dir = temp_dir();
+        let cache = Arc::new(DiskEmbeddingCache::open(&dir, Duration::from_secs(60), 1 << 20).unwrap());
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                std::thread::spawn(move || cache.put("a", &[1.0, 2.0]))
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap().unwrap();
+        }
+
+        assert_eq!(cache.get("a"), Some(vec![1.0, 2.0]));
+        assert_eq!(cache.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expired_entries_are_misses() {
+        let dir = temp_dir();
+        let cache = DiskEmbeddingCache::open(&dir, Duration::ZERO, 1 << 20).unwrap();
+        cache.put("a", &[1.0]).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(cache.get("a"), None);
+        assert!(cache.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+//! Composite embedding provider failing over across an ordered provider chain
+
+use super::EmbeddingProvider;
+use crate::error::{ContextError, EmbeddingError, Result};
+use crate::vector_db::{CircuitBreaker, CircuitBreakerConfig};
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// A provider of the chain with its circuit breaker
+struct Member {
+    provider: Arc<dyn EmbeddingProvider>,
+    breaker: Arc<CircuitBreaker>,
+    /// The breaker was created by the chain rather than taken from the
+    /// provider, which checks and updates its own breaker on every request
+    chain_owned: bool,
+}
+
+/// Tries providers in order, skipping those whose circuit is open.
+///
+/// A provider with its own circuit breaker keeps it; the others get one from
+/// the chain's breaker config. Embedding errors count against the failing
+/// provider's breaker and move on to the next one; invalid input and errors
+/// outside embedding are returned as-is without touching any breaker.
+/// All providers must produce the same dimension and model ID so vectors
+/// from different members can share a collection.
+pub struct FailoverEmbedder {
+    members: Vec<Member>,
+}
+
+impl FailoverEmbedder {
+    /// Chain `providers`, highest priority first; those without a breaker of their own get one built from `breaker_config`
+    pub fn new(providers: Vec<Arc<dyn EmbeddingProvider>>, breaker_config: CircuitBreakerConfig) -> Result<Self> {
+        let Some(primary) = providers.first() else {
+            return Err(ContextError::Configuration("Embedding failover chain is empty".to_string()));
+        };
+
+        let (dimension, model_id) = (primary.embedding_dimension(), primary.model_id());
+        for provider in &providers[1..] {
+            if provider.embedding_dimension() != dimension || provider.model_id() != model_id {
+                return Err(ContextError::Configuration(format!(
+                    "Embedding fallback {} ({} dims) cannot share collections with {} ({} dims)",
+                    provider.model_id(),
+                    provider.embedding_dimension(),
+                    model_id,
+                    dimension
+                )));
+            }
+        }
+
+        let members = providers
+            .into_iter()
+            .map(|provider| match provider.circuit_breaker() {
+                Some(breaker) => Member {
+                    provider,
+                    breaker,
+                    chain_owned: false,
+                },
+                None => Member {
+                    provider,
+                    breaker: Arc::new(CircuitBreaker::new(breaker_config.clone())),
+                    chain_owned: true,
+                },
+            })
+            .collect();
+        Ok(Self { members })
+    }
+
+    /// Circuit breakers of the chain in priority order, for health checks and metrics
+    pub fn circuit_breakers(&self) -> Vec<Arc<CircuitBreaker>> {
+        self.members.iter().map(|m| m.breaker.clone()).collect()
+    }
+
+    /// Run `op` on the first available provider that succeeds
+    async fn call<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn(Arc<dyn EmbeddingProvider>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+
+        for (position, member) in self.members.iter().enumerate() {
+            // A provider's own breaker is checked and updated by the provider
+            if member.chain_owned && !member.breaker.allow_request().await {
+                debug!("Embedding provider {} skipped, circuit open", position);
+                continue;
+            }
+
+            match op(member.provider.clone()).await {
+                Ok(value) => {
+                    if member.chain_owned {
+                        member.breaker.record_success().await;
+                    }
+                    return Ok(value);
+                }
+                // Every provider would reject the same input, and it says nothing about their health
+                Err(e @ ContextError::Embedding(EmbeddingError::InvalidInput(_))) => return Err(e),
+                Err(ContextError::Embedding(e)) => {
+                    if member.chain_owned {
+                        member.breaker.record_failure().await;
+                    }
+                    warn!("Embedding provider {} failed, trying next: {}", position, e);
+                    last_error = Some(ContextError::Embedding(e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ContextError::Embedding(EmbeddingError::ServiceUnavailable(
+                "All embedding providers have open circuits".to_string(),
+            ))
+        }))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for FailoverEmbedder {
+    async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+        self.call(|provider| async move { provider.embed_single(text).await }).await
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.call(|provider| async move { provider.embed_batch(texts).await }).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.call(|provider| async move { provider.embed_query(text).await }).await
+    }
+
+    fn embedding_dimension(&self) -> usize {
+        self.members[0].provider.embedding_dimension()
+    }
+
+    fn model_id(&self) -> String {
+        self.members[0].provider.model_id()
+    }
+
+    fn is_production(&self) -> bool {
+        self.members.iter().all(|m| m.provider.is_production())
+    }
+
+    fn embeds_queries_separately(&self) -> bool {
+        self.members.iter().any(|m| m.provider.embeds_queries_separately())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::HashingEmbedder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Fails every call and counts them
+    #[derive(Default)]
+    struct DownProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for DownProvider {
+        async fn embed_single(&self, _text: &str) -> Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(ContextError::Embedding(EmbeddingError::ServiceUnavailable("down".to_string())))
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.embed_single(&texts[0]).await.map(|v| vec![v])
+        }
+
+        fn embedding_dimension(&self) -> usize {
+            8
+        }
+
+        fn model_id(&self) -> String {
+            "hashing-8".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_and_skips_open_circuit() {
+        let down = Arc::new(DownProvider::default());
+        let chain = FailoverEmbedder::new(
+            vec![down.clone(), Arc::new(HashingEmbedder::new(8))],
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                timeout: Duration::from_secs(60),
+                ..CircuitBreakerConfig::default()
+            },
+        )
+        .unwrap();
+
+        for _ in 0..4 {
+            assert_eq!(chain.embed_single("hello").await.unwrap(), HashingEmbedder::new(8).embed("hello"));
+        }
+        // The primary's circuit opened after two failures
+        assert_eq!(down.calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Rejects every input and counts calls
+    #[derive(Default)]
+    struct RejectingProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for RejectingProvider {
+        async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(ContextError::Embedding(EmbeddingError::InvalidInput(text.to_string())))
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.embed_single(&texts[0]).await.map(|v| vec![v])
+        }
+
+        fn embedding_dimension(&self) -> usize {
+            8
+        }
+
+        fn model_id(&self) -> String {
+            "hashing-8".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input_is_returned_without_failover() {
+        let rejecting = Arc::new(RejectingProvider::default());
+        let fallback = Arc::new(DownProvider::default());
+        let chain = FailoverEmbedder::new(
+            vec![rejecting.clone(), fallback.clone()],
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                timeout: Duration::from_secs(60),
+                ..CircuitBreakerConfig::default()
+            },
+        )
+        .unwrap();
+
+        for _ in 0..3 {
+            let err = chain.embed_single("bad").await.unwrap_err();
+            assert!(matches!(err, ContextError::Embedding(EmbeddingError::InvalidInput(_))));
+        }
+        // The primary was tried every time, so its circuit never opened
+        assert_eq!(rejecting.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(fallback.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(chain.circuit_breakers()[0].stats().await.total_failures, 0);
+    }
+
+    #[test]
+    fn test_refuses_mismatched_providers() {
+        let result = FailoverEmbedder::new(
+            vec![Arc::new(HashingEmbedder::new(8)), Arc::new(HashingEmbedder::new(16))],
+            CircuitBreakerConfig::default(),
+        );
+        assert!(result.is_err());
+        assert!(FailoverEmbedder::new(Vec::new(), CircuitBreakerConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Offline embedding provider based on feature hashing
+//!
+//! Words, word bigrams and character trigrams are hashed into a fixed number
+//! of signed buckets and the vector is L2-normalized. No network or model is
+//! involved and FNV-1a keeps the output identical across runs and platforms,
+//! so it suits tests, examples and local development, not production
+//! retrieval quality.
+
+use super::EmbeddingProvider;
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Weight of a word bigram relative to a word
+const BIGRAM_WEIGHT: f32 = 0.5;
+
+/// Weight of a character trigram relative to a word
+const TRIGRAM_WEIGHT: f32 = 0.25;
+
+/// Deterministic embeddings from hashed word and character n-grams
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimension: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+
+    /// Embed `text`; texts without any alphanumeric word give a zero vector
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimension];
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+
+        for word in &words {
+            self.add(&mut vector, "w", word, 1.0);
+
+            let padded: Vec<char> = format!("<{}>", word).chars().collect();
+            for gram in padded.windows(3) {
+                self.add(&mut vector, "c", &gram.iter().collect::<String>(), TRIGRAM_WEIGHT);
+            }
+        }
+
+        for pair in words.windows(2) {
+            self.add(&mut vector, "b", &format!("{} {}", pair[0], pair[1]), BIGRAM_WEIGHT);
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+
+    /// Add `weight` to the bucket of `feature`, signed by the hash's top bit
+    fn add(&self, vector: &mut [f32], kind: &str, feature: &str, weight: f32) {
+        let hash = fnv1a(kind.as_bytes().iter().chain(&[0]).chain(feature.as_bytes()));
+        let index = (hash % self.dimension as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    }
+}
+
+/// 64-bit FNV-1a, stable unlike `std`'s `DefaultHasher`
+fn fnv1a<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    bytes
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashingEmbedder {
+    async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed(text))
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed(text)).collect())
+    }
+
+    fn embedding_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn model_id(&self) -> String {
+        format!("hashing-{}", self.dimension)
+    }
+
+    fn is_production(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_embeddings_are_deterministic_and_topical() {
+        let embedder = HashingEmbedder::new(256);
+        let query = embedder.embed("Rust borrow checker");
+
+        assert_eq!(query.len(), 256);
+        assert_eq!(query, HashingEmbedder::new(256).embed("rust BORROW checker"));
+        assert!((cosine(&query, &query) - 1.0).abs() < 1e-5);
+
+        let related = embedder.embed("fixing borrow checker errors in Rust");
+        let unrelated = embedder.embed("chocolate cake recipe");
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+        assert!(embedder.embed("...").iter().all(|x| *x == 0.0));
+    }
+}
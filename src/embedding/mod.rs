@@ -3,15 +3,31 @@
 pub mod client;
 pub mod client_v2;
 pub mod cache;
+pub mod coalescer;
+pub mod disk_cache;
+pub mod failover;
+pub mod hashing;
 pub mod models;
+mod dialect;
 
 pub use client::EmbeddingClient;
 pub use client_v2::EmbeddingClientV2;
 pub use models::{EmbeddingRequest, EmbeddingResponse, EmbeddingInput};
 pub use cache::EmbeddingCache;
+pub use coalescer::CoalescingEmbedder;
+pub use disk_cache::{DiskEmbeddingCache, WarmupReport};
+pub use failover::FailoverEmbedder;
+pub use hashing::HashingEmbedder;
 
 use async_trait::async_trait;
-use crate::error::Result;
+use crate::config::{EmbeddingConfig, EmbeddingProviderKind};
+use crate::error::{ContextError, Result};
+use crate::vector_db::{CircuitBreaker, CircuitBreakerConfig, Condition, Filter, VectorStore};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Payload metadata key recording the model ID that produced a vector
+pub const EMBEDDING_MODEL_KEY: &str = "embedding_model";
 
 /// Trait for embedding providers
 #[async_trait]
@@ -22,6 +38,150 @@ pub trait EmbeddingProvider: Send + Sync {
     /// Generate embeddings for multiple texts
     async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
     
+    /// Generate the embedding of a search query. Only providers for which
+    /// `embeds_queries_separately` is true embed it differently from a
+    /// stored text.
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_single(text).await
+    }
+    
+    /// Whether `embed_query` differs from `embed_single`, as for Cohere's
+    /// `search_query` input type
+    fn embeds_queries_separately(&self) -> bool {
+        false
+    }
+    
     /// Get the dimension of embeddings
     fn embedding_dimension(&self) -> usize;
-}
\ No newline at end of file
+    
+    /// Identifier of the model behind the embeddings, recorded with every
+    /// stored vector so vectors of different models never share a collection
+    fn model_id(&self) -> String {
+        "unknown".to_string()
+    }
+    
+    /// Whether embeddings are fit for production retrieval; offline
+    /// providers return false and are flagged by the health check
+    fn is_production(&self) -> bool {
+        true
+    }
+    
+    /// Circuit breaker the provider checks and updates on every request, if any
+    fn circuit_breaker(&self) -> Option<Arc<CircuitBreaker>> {
+        None
+    }
+}
+
+/// Create the embedding provider selected by `config.provider`, chained with
+/// its `fallbacks` in a `FailoverEmbedder` when any are configured and behind
+/// a `CoalescingEmbedder` when `coalesce_wait_ms` is set
+pub fn create_embedding_provider(config: EmbeddingConfig) -> Result<Arc<dyn EmbeddingProvider>> {
+    let max_wait = Duration::from_millis(config.coalesce_wait_ms);
+    let max_batch = config.coalesce_max_batch;
+    
+    let provider = create_chained_provider(config)?;
+    if max_wait.is_zero() {
+        return Ok(provider);
+    }
+    Ok(Arc::new(CoalescingEmbedder::new(provider, max_wait, max_batch)))
+}
+
+fn create_chained_provider(mut config: EmbeddingConfig) -> Result<Arc<dyn EmbeddingProvider>> {
+    if config.fallbacks.is_empty() {
+        return create_single_provider(config, None);
+    }
+    
+    let breaker_config = CircuitBreakerConfig {
+        failure_threshold: config.circuit_failure_threshold,
+        timeout: Duration::from_secs(config.circuit_open_secs),
+        ..CircuitBreakerConfig::default()
+    };
+    let fallbacks = std::mem::take(&mut config.fallbacks);
+    let providers = std::iter::once(config)
+        .chain(fallbacks)
+        .map(|config| create_single_provider(config, Some(&breaker_config)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(FailoverEmbedder::new(providers, breaker_config)?))
+}
+
+/// HTTP providers get a circuit breaker from `breaker_config` when one is given
+fn create_single_provider(
+    config: EmbeddingConfig,
+    breaker_config: Option<&CircuitBreakerConfig>,
+) -> Result<Arc<dyn EmbeddingProvider>> {
+    match config.provider {
+        EmbeddingProviderKind::Http => {
+            let client = EmbeddingClientV2::new(config)?;
+            Ok(Arc::new(match breaker_config {
+                Some(breaker_config) => client.with_circuit_breaker(breaker_config.clone()),
+                None => client,
+            }))
+        }
+        EmbeddingProviderKind::Hashing => Ok(Arc::new(HashingEmbedder::new(config.dimension))),
+    }
+}
+
+/// Refuse to add vectors of `model_id` to `collection` when any vector in it
+/// is recorded as coming from another model
+pub async fn check_collection_model(vector_db: &dyn VectorStore, collection: &str, model_id: &str) -> Result<()> {
+    let other_model = Filter::new()
+        .must_not(Condition::IsEmpty { key: EMBEDDING_MODEL_KEY.to_string() })
+        .must_not(Condition::Match { key: EMBEDDING_MODEL_KEY.to_string(), value: model_id.into() });
+    let page = vector_db.scroll(collection, Some(other_model), 1, None).await?;
+    let stored = page
+        .points
+        .first()
+        .and_then(|p| p.payload.metadata.get(EMBEDDING_MODEL_KEY))
+        .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string));
+    
+    match stored {
+        Some(stored) => Err(ContextError::Configuration(format!(
+            "Collection {} holds embeddings from {}, not {}; re-embed it or use another collection prefix",
+            collection, stored, model_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::{ContextLevel, InMemoryVectorStore, Payload, VectorPoint};
+    use std::collections::HashMap;
+    
+    fn point(model: Option<&str>) -> VectorPoint {
+        let metadata = model
+            .map(|m| HashMap::from([(EMBEDDING_MODEL_KEY.to_string(), serde_json::json!(m))]))
+            .unwrap_or_default();
+        VectorPoint {
+            id: uuid::Uuid::new_v4(),
+            vector: vec![1.0, 0.0],
+            payload: Payload {
+                text: "text".to_string(),
+                level: ContextLevel::LongTerm,
+                timestamp: 0,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata,
+            },
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_collection_model_checks_every_point() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = InMemoryVectorStore::new(config);
+        store.create_collection("c").await.unwrap();
+        
+        store.insert_points("c", vec![point(Some("bge-m3")), point(None)]).await.unwrap();
+        assert!(check_collection_model(&store, "c", "bge-m3").await.is_ok());
+        
+        // Found wherever it sorts among the other points
+        let points: Vec<_> = (0..20).map(|_| point(Some("bge-m3"))).chain([point(Some("e5-large"))]).collect();
+        store.insert_points("c", points).await.unwrap();
+        let error = check_collection_model(&store, "c", "bge-m3").await.unwrap_err();
+        assert!(error.to_string().contains("e5-large"));
+    }
+}
@@ -105,6 +105,15 @@ pub enum VectorDbError {
     
     #[error("Qdrant client error: {0}")]
     QdrantError(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    #[error("Timeout after {0} ms")]
+    Timeout(u64),
+
+    #[error("Circuit breaker open, vector database unavailable")]
+    CircuitOpen,
 }
 
 /// Errors related to HiRAG operations
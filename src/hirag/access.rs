@@ -0,0 +1,278 @@
+//! Access tracking feeding the frequency component of ranking
+//!
+//! Retrieval hits and explicit "used" feedback are counted in memory and
+//! written back to the contexts' metadata in batches, off the request path.
+
+use crate::vector_db::{get_points, VectorStore};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::interval;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Metadata key counting how often a context was returned by retrieval
+pub const ACCESS_COUNT_KEY: &str = "access_count";
+
+/// Metadata key counting how often a client reported a context as used
+pub const USED_COUNT_KEY: &str = "used_count";
+
+/// Metadata key holding the unix timestamp of the last hit or use
+pub const LAST_ACCESSED_KEY: &str = "last_accessed";
+
+/// Metadata keys written back by a flush
+const ACCESS_KEYS: [&str; 3] = [ACCESS_COUNT_KEY, USED_COUNT_KEY, LAST_ACCESSED_KEY];
+
+/// Contexts read and updated per store call during a flush
+const FLUSH_BATCH_SIZE: usize = 256;
+
+/// Accesses of one context not yet written back
+#[derive(Debug, Clone, Copy, Default)]
+struct PendingAccess {
+    hits: u64,
+    used: u64,
+    last_accessed: i64,
+}
+
+impl PendingAccess {
+    fn merge(&mut self, other: PendingAccess) {
+        self.hits += other.hits;
+        self.used += other.used;
+        self.last_accessed = self.last_accessed.max(other.last_accessed);
+    }
+}
+
+/// Add `hits` and `used` to the counters in `metadata` and stamp the access time
+pub fn record_access(
+    metadata: &mut HashMap<String, serde_json::Value>,
+    hits: u64,
+    used: u64,
+    timestamp: i64,
+) {
+    for (key, delta) in [(ACCESS_COUNT_KEY, hits), (USED_COUNT_KEY, used)] {
+        if delta > 0 {
+            let count = metadata.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+            metadata.insert(key.to_string(), (count + delta).into());
+        }
+    }
+    let last = metadata.get(LAST_ACCESSED_KEY).and_then(|v| v.as_i64()).unwrap_or(i64::MIN);
+    metadata.insert(LAST_ACCESSED_KEY.to_string(), last.max(timestamp).into());
+}
+
+/// Buffers context accesses and batches their write-back per collection.
+///
+/// Recording only touches an in-memory map; `flush` (run periodically once
+/// `start` is called) reads the touched points in batches and sets only their
+/// access metadata keys, so other metadata is left alone and deleted contexts
+/// stay deleted. Accesses whose write-back fails are kept for the next flush;
+/// accesses pending when the tracker is dropped are lost.
+pub struct AccessTracker {
+    vector_db: Arc<dyn VectorStore>,
+    pending: DashMap<(String, Uuid), PendingAccess>,
+}
+
+impl AccessTracker {
+    pub fn new(vector_db: Arc<dyn VectorStore>) -> Self {
+        Self {
+            vector_db,
+            pending: DashMap::new(),
+        }
+    }
+
+    /// Spawn the periodic flush; it stops once the tracker is dropped
+    pub fn start(self: &Arc<Self>, flush_interval: Duration) {
+        let tracker = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = interval(flush_interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                let Some(tracker) = tracker.upgrade() else { break };
+                let updated = tracker.flush().await;
+                if updated > 0 {
+                    debug!("Access tracking: wrote back {} contexts", updated);
+                }
+            }
+        });
+    }
+
+    /// Count a retrieval hit of context `id` stored in `collection`
+    pub fn record_hit(&self, collection: &str, id: Uuid) {
+        self.record(collection, id, PendingAccess {
+            hits: 1,
+            used: 0,
+            last_accessed: chrono::Utc::now().timestamp(),
+        });
+    }
+
+    /// Count a "used" report for context `id` stored in `collection`
+    pub fn record_used(&self, collection: &str, id: Uuid) {
+        self.record(collection, id, PendingAccess {
+            hits: 0,
+            used: 1,
+            last_accessed: chrono::Utc::now().timestamp(),
+        });
+    }
+
+    /// Number of contexts with accesses not yet written back
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn record(&self, collection: &str, id: Uuid, access: PendingAccess) {
+        self.pending.entry((collection.to_string(), id)).or_default().merge(access);
+    }
+
+    /// Write pending accesses back to the store, returning the number of contexts updated
+    pub async fn flush(&self) -> usize {
+        let keys: Vec<_> = self.pending.iter().map(|entry| entry.key().clone()).collect();
+        let mut by_collection: HashMap<String, Vec<(Uuid, PendingAccess)>> = HashMap::new();
+        for key in keys {
+            if let Some(((collection, id), access)) = self.pending.remove(&key) {
+                by_collection.entry(collection).or_default().push((id, access));
+            }
+        }
+
+        let mut updated = 0;
+        for (collection, accesses) in by_collection {
+            for batch in accesses.chunks(FLUSH_BATCH_SIZE) {
+                updated += self.flush_batch(&collection, batch).await;
+            }
+        }
+
+        updated
+    }
+
+    /// Write back one batch of `collection`, returning the number of contexts updated
+    async fn flush_batch(&self, collection: &str, accesses: &[(Uuid, PendingAccess)]) -> usize {
+        let ids = accesses.iter().map(|(id, _)| *id).collect();
+        let stored: HashMap<Uuid, _> = match get_points(self.vector_db.as_ref(), collection, ids, None).await {
+            Ok(points) => points.into_iter().map(|p| (p.id, p.payload.metadata)).collect(),
+            Err(e) => {
+                warn!("Access tracking: failed to read {} contexts: {}", accesses.len(), e);
+                self.requeue(collection, accesses.iter().copied());
+                return 0;
+            }
+        };
+
+        // Contexts deleted since they were accessed are not in `stored` and are dropped
+        let mut written = Vec::with_capacity(stored.len());
+        let mut updates = Vec::with_capacity(stored.len());
+        for &(id, access) in accesses {
+            let Some(current) = stored.get(&id) else { continue };
+            let mut metadata: HashMap<String, serde_json::Value> = ACCESS_KEYS
+                .iter()
+                .filter_map(|key| current.get(*key).map(|value| (key.to_string(), value.clone())))
+                .collect();
+            record_access(&mut metadata, access.hits, access.used, access.last_accessed);
+            updates.push((id, metadata));
+            written.push((id, access));
+        }
+
+        if updates.is_empty() {
+            return 0;
+        }
+
+        match self.vector_db.set_metadata(collection, updates).await {
+            Ok(()) => written.len(),
+            Err(e) => {
+                warn!("Access tracking: failed to write back {} contexts: {}", written.len(), e);
+                self.requeue(collection, written);
+                0
+            }
+        }
+    }
+
+    /// Keep accesses whose write-back failed for the next flush
+    fn requeue(&self, collection: &str, accesses: impl IntoIterator<Item = (Uuid, PendingAccess)>) {
+        for (id, access) in accesses {
+            self.record(collection, id, access);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::{ContextLevel, InMemoryVectorStore, Payload, VectorPoint};
+
+    #[tokio::test]
+    async fn test_flush_batches_hits_and_used() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let point = VectorPoint {
+            id: Uuid::new_v4(),
+            vector: vec![1.0, 0.0],
+            payload: Payload {
+                text: "text".to_string(),
+                level: ContextLevel::ShortTerm,
+                timestamp: 0,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata: HashMap::new(),
+            },
+        };
+        store.insert_points("c", vec![point.clone()]).await.unwrap();
+
+        let tracker = AccessTracker::new(store.clone());
+        tracker.record_hit("c", point.id);
+        tracker.record_hit("c", point.id);
+        tracker.record_used("c", point.id);
+        tracker.record_hit("c", Uuid::new_v4());
+        assert_eq!(tracker.pending(), 2);
+
+        assert_eq!(tracker.flush().await, 1);
+        assert_eq!(tracker.pending(), 0);
+
+        let metadata = store.get_point("c", point.id).await.unwrap().unwrap().payload.metadata;
+        assert_eq!(metadata[ACCESS_COUNT_KEY], 2);
+        assert_eq!(metadata[USED_COUNT_KEY], 1);
+        assert!(metadata[LAST_ACCESSED_KEY].as_i64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_keeps_metadata_and_deletions() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let mut kept = VectorPoint {
+            id: Uuid::new_v4(),
+            vector: vec![1.0, 0.0],
+            payload: Payload {
+                text: "text".to_string(),
+                level: ContextLevel::ShortTerm,
+                timestamp: 0,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata: HashMap::new(),
+            },
+        };
+        let mut deleted = kept.clone();
+        deleted.id = Uuid::new_v4();
+        store.insert_points("c", vec![kept.clone(), deleted.clone()]).await.unwrap();
+
+        let tracker = AccessTracker::new(store.clone());
+        tracker.record_hit("c", kept.id);
+        tracker.record_hit("c", deleted.id);
+
+        // The context is updated and the other one deleted before the flush
+        kept.payload.metadata.insert("topic".to_string(), serde_json::json!("rust"));
+        store.insert_points("c", vec![kept.clone()]).await.unwrap();
+        store.delete_points("c", vec![deleted.id]).await.unwrap();
+
+        assert_eq!(tracker.flush().await, 1);
+        let metadata = store.get_point("c", kept.id).await.unwrap().unwrap().payload.metadata;
+        assert_eq!(metadata["topic"], "rust");
+        assert_eq!(metadata[ACCESS_COUNT_KEY], 1);
+        assert!(store.get_point("c", deleted.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,389 @@
+//! Relevance feedback on retrieved contexts and fitting ranking weights to it
+//!
+//! Judgments are stored with the unweighted ranking features the context had
+//! when it was judged, so weights can be refitted offline without replaying
+//! searches.
+
+use super::models::Namespace;
+use super::ranker::{ContextRanker, RankFeatures};
+use super::retriever::ContextRetriever;
+use crate::config::RankingWeights;
+use crate::error::{HiRAGError, Result};
+use crate::vector_db::ContextLevel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// A client's rating of one returned context for a query
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Judgment {
+    pub id: Uuid,
+    pub helpful: bool,
+}
+
+/// Stored judgment with the ranking features of the judged context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackRecord {
+    pub query: String,
+    pub namespace: String,
+    pub context_id: Uuid,
+    pub level: ContextLevel,
+    pub helpful: bool,
+    pub features: RankFeatures,
+    pub timestamp: i64,
+}
+
+/// Append-only JSONL log of feedback records
+pub struct FeedbackLog {
+    path: PathBuf,
+    /// Only locked on the blocking pool
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl FeedbackLog {
+    /// Log at `path`; the file is created on the first append
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `records`, one JSON object per line; file I/O runs on the blocking pool
+    pub async fn append(&self, records: &[FeedbackRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut lines, record)
+                .map_err(|e| HiRAGError::StorageError(format!("Failed to encode feedback: {}", e)))?;
+            lines.push(b'\n');
+        }
+
+        let path = self.path.clone();
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            if file.is_none() {
+                if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                    fs::create_dir_all(dir).map_err(storage_err)?;
+                }
+                *file = Some(OpenOptions::new().create(true).append(true).open(&path).map_err(storage_err)?);
+            }
+
+            let file = file.as_mut().expect("feedback log opened above");
+            file.write_all(&lines).map_err(storage_err)?;
+            file.flush().map_err(storage_err)?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| HiRAGError::StorageError(format!("Feedback log: {}", e)))?
+    }
+
+    /// Read every record of the log at `path`
+    pub fn load(path: &Path) -> Result<Vec<FeedbackRecord>> {
+        let file = File::open(path).map_err(storage_err)?;
+        let mut records = Vec::new();
+
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(storage_err)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|e| {
+                HiRAGError::StorageError(format!("Invalid feedback record on line {}: {}", number + 1, e))
+            })?;
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+}
+
+fn storage_err(e: std::io::Error) -> HiRAGError {
+    HiRAGError::StorageError(format!("Feedback log: {}", e))
+}
+
+/// Build feedback records for `judgments` of `query`.
+///
+/// Judged contexts are scored by `retriever` in `collections`, so the
+/// similarity feature is the dense or fused score the ranker would see for
+/// this query. Contexts outside `namespace` or no longer stored are skipped;
+/// the last judgment of a context wins.
+pub(crate) async fn snapshot_judgments(
+    retriever: &ContextRetriever,
+    ranker: &ContextRanker,
+    collections: &[(ContextLevel, String)],
+    namespace: &Namespace,
+    query: &str,
+    query_vector: &[f32],
+    judgments: &[Judgment],
+) -> Result<Vec<FeedbackRecord>> {
+    let now = chrono::Utc::now().timestamp();
+    let mut remaining: HashMap<Uuid, bool> = judgments.iter().map(|j| (j.id, j.helpful)).collect();
+    let mut records = Vec::with_capacity(remaining.len());
+
+    for (level, collection) in collections {
+        if remaining.is_empty() {
+            break;
+        }
+
+        let ids = remaining.keys().copied().collect();
+        let contexts = retriever
+            .score_contexts(collection, query, query_vector.to_vec(), ids, namespace.scope(None))
+            .await?;
+
+        for context in contexts {
+            let Some(helpful) = remaining.remove(&context.id) else {
+                continue;
+            };
+
+            records.push(FeedbackRecord {
+                query: query.to_string(),
+                namespace: context.agent_id.clone(),
+                context_id: context.id,
+                level: *level,
+                helpful,
+                features: ranker.features(&context, now),
+                timestamp: now,
+            });
+        }
+    }
+
+    Ok(records)
+}
+
+/// Ranking weights fitted to collected feedback
+#[derive(Debug, Clone)]
+pub struct WeightFit {
+    pub before: RankingWeights,
+    pub after: RankingWeights,
+    pub ndcg_before: f32,
+    pub ndcg_after: f32,
+    /// Judged queries with at least one helpful context, which nDCG averages over
+    pub queries: usize,
+    pub judgments: usize,
+}
+
+impl WeightFit {
+    /// Proposed change to the `[hirag.ranking_weights]` config section
+    pub fn config_diff(&self) -> String {
+        let fields = [
+            ("similarity_weight", self.before.similarity_weight, self.after.similarity_weight),
+            ("recency_weight", self.before.recency_weight, self.after.recency_weight),
+            ("level_weight", self.before.level_weight, self.after.level_weight),
+            ("frequency_weight", self.before.frequency_weight, self.after.frequency_weight),
+        ];
+
+        let mut diff = String::from(" [hirag.ranking_weights]\n");
+        for (name, before, after) in fields {
+            if (before - after).abs() < 1e-6 {
+                diff.push_str(&format!(" {} = {}\n", name, before));
+            } else {
+                diff.push_str(&format!("-{} = {}\n+{} = {}\n", name, before, name, after));
+            }
+        }
+        diff
+    }
+}
+
+/// Latest judgment per context, grouped by (namespace, query)
+fn judged_queries(records: &[FeedbackRecord]) -> Vec<Vec<&FeedbackRecord>> {
+    let mut groups: HashMap<(&str, &str), HashMap<Uuid, &FeedbackRecord>> = HashMap::new();
+    for record in records {
+        let group = groups.entry((record.namespace.as_str(), record.query.as_str())).or_default();
+        match group.get(&record.context_id) {
+            Some(existing) if existing.timestamp > record.timestamp => {}
+            _ => {
+                group.insert(record.context_id, record);
+            }
+        }
+    }
+
+    groups
+        .into_values()
+        .map(|group| {
+            let mut judged: Vec<_> = group.into_values().collect();
+            // Fixed order so ties rank the same way under every weighting
+            judged.sort_by_key(|r| r.context_id);
+            judged
+        })
+        .filter(|judged| judged.iter().any(|r| r.helpful))
+        .collect()
+}
+
+fn ndcg(judged: &[&FeedbackRecord], weights: &RankingWeights) -> f32 {
+    let mut scored: Vec<_> = judged.iter().map(|r| (r.features.score(weights), r.helpful)).collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let discount = |rank: usize| 1.0 / ((rank + 2) as f32).log2();
+    let dcg: f32 = scored.iter().enumerate().filter(|(_, (_, helpful))| *helpful).map(|(rank, _)| discount(rank)).sum();
+    let ideal: f32 = (0..scored.iter().filter(|(_, helpful)| *helpful).count()).map(discount).sum();
+    dcg / ideal
+}
+
+fn mean_ndcg(queries: &[Vec<&FeedbackRecord>], weights: &RankingWeights) -> f32 {
+    if queries.is_empty() {
+        return 0.0;
+    }
+    queries.iter().map(|judged| ndcg(judged, weights)).sum::<f32>() / queries.len() as f32
+}
+
+/// Grid search over weights summing to 1.0 in increments of `step` for the
+/// best mean nDCG on `records`.
+///
+/// `current` is kept unless a candidate beats it; among equally good
+/// candidates the one closest to `current` wins.
+pub fn fit_ranking_weights(records: &[FeedbackRecord], current: &RankingWeights, step: f32) -> WeightFit {
+    let queries = judged_queries(records);
+    let ndcg_before = mean_ndcg(&queries, current);
+
+    let distance = |w: &RankingWeights| {
+        (w.similarity_weight - current.similarity_weight).abs()
+            + (w.recency_weight - current.recency_weight).abs()
+            + (w.level_weight - current.level_weight).abs()
+            + (w.frequency_weight - current.frequency_weight).abs()
+    };
+
+    let mut best = (current.clone(), ndcg_before, 0.0);
+    let steps = (1.0 / step.clamp(0.01, 1.0)).round() as usize;
+    for similarity in 0..=steps {
+        for recency in 0..=steps - similarity {
+            for level in 0..=steps - similarity - recency {
+                let frequency = steps - similarity - recency - level;
+                let candidate = RankingWeights {
+                    similarity_weight: similarity as f32 / steps as f32,
+                    recency_weight: recency as f32 / steps as f32,
+                    level_weight: level as f32 / steps as f32,
+                    frequency_weight: frequency as f32 / steps as f32,
+                };
+
+                let score = mean_ndcg(&queries, &candidate);
+                let candidate_distance = distance(&candidate);
+                if score > best.1 + 1e-6 || (score >= best.1 - 1e-6 && candidate_distance < best.2) {
+                    best = (candidate, score, candidate_distance);
+                }
+            }
+        }
+    }
+
+    WeightFit {
+        before: current.clone(),
+        after: best.0,
+        ndcg_before,
+        ndcg_after: best.1,
+        queries: queries.len(),
+        judgments: queries.iter().map(Vec::len).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, Distance, RetrievalStrategy, TokenEstimator as EstimatorConfig};
+    use crate::hirag::token_estimator::TokenEstimator;
+    use crate::vector_db::{InMemoryVectorStore, Payload, VectorPoint, VectorStore};
+
+    fn record(query: &str, helpful: bool, similarity: f32, recency: f32) -> FeedbackRecord {
+        FeedbackRecord {
+            query: query.to_string(),
+            namespace: "default".to_string(),
+            context_id: Uuid::new_v4(),
+            level: ContextLevel::ShortTerm,
+            helpful,
+            features: RankFeatures {
+                similarity,
+                recency,
+                level: 0.7,
+                frequency: 0.0,
+            },
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_fit_prefers_signal_matching_feedback() {
+        // Helpful contexts are recent but less similar than the unhelpful ones
+        let records: Vec<_> = ["q1", "q2", "q3"]
+            .into_iter()
+            .flat_map(|q| [record(q, true, 0.5, 0.65), record(q, false, 0.9, 0.1)])
+            .collect();
+
+        let fit = fit_ranking_weights(&records, &RankingWeights::default(), 0.1);
+        assert_eq!(fit.queries, 3);
+        assert_eq!(fit.judgments, 6);
+        assert!(fit.ndcg_before < 1.0);
+        assert!((fit.ndcg_after - 1.0).abs() < 1e-6);
+        assert!(fit.after.recency_weight > fit.before.recency_weight);
+        assert!(fit.config_diff().contains("+recency_weight"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_uses_retrieval_score() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        config.distance = Distance::Euclidean;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let point = VectorPoint {
+            id: Uuid::new_v4(),
+            vector: vec![2.0, 0.0],
+            payload: Payload {
+                text: "text".to_string(),
+                level: ContextLevel::ShortTerm,
+                timestamp: 0,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata: HashMap::new(),
+            },
+        };
+        store.insert_points("c", vec![point.clone()]).await.unwrap();
+
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever =
+            ContextRetriever::new(store, estimator, RetrievalStrategy::default()).with_distance(Distance::Euclidean);
+        let judgments = [Judgment { id: point.id, helpful: true }, Judgment { id: Uuid::new_v4(), helpful: false }];
+
+        let records = snapshot_judgments(
+            &retriever,
+            &ContextRanker::new(RankingWeights::default()),
+            &[(ContextLevel::ShortTerm, "c".to_string())],
+            &Namespace::agent("default"),
+            "query",
+            &[1.0, 0.0],
+            &judgments,
+        )
+        .await
+        .unwrap();
+
+        // Distance 1.0 is a similarity of 0.5, where cosine would have given 1.0
+        assert_eq!(records.len(), 1);
+        assert!((records[0].features.similarity - 0.5).abs() < 1e-6);
+        assert!(records[0].helpful);
+    }
+
+    #[tokio::test]
+    async fn test_log_round_trip() {
+        let path = std::env::temp_dir().join(format!("feedback-{}", Uuid::new_v4())).join("feedback.jsonl");
+        let log = FeedbackLog::new(&path);
+        log.append(&[record("q", true, 0.5, 0.5)]).await.unwrap();
+        log.append(&[record("q", false, 0.4, 0.5)]).await.unwrap();
+
+        let records = FeedbackLog::load(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].helpful && !records[1].helpful);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
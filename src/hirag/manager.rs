@@ -1,14 +1,16 @@
 //! HiRAG manager implementation
 
-use super::{ContextManager, models::*, retriever::ContextRetriever, ranker::ContextRanker, token_estimator::TokenEstimator};
-use crate::config::HiRAGConfig;
-use crate::embedding::EmbeddingProvider;
+use super::{ContextManager, access::{record_access, AccessTracker}, feedback::{self, FeedbackLog, Judgment}, models::*, rerank::{RerankStage, Reranker}, retriever::ContextRetriever, ranker::ContextRanker, token_estimator::TokenEstimator};
+use crate::config::{HiRAGConfig, RerankerKind};
+use crate::embedding::{check_collection_model, EmbeddingProvider, EMBEDDING_MODEL_KEY};
 use crate::error::{HiRAGError, Result};
-use crate::vector_db::{ContextLevel, VectorPoint, VectorStore, Payload};
+use crate::middleware::InputValidator;
+use crate::vector_db::{get_points, ContextLevel, Filter, VectorPoint, VectorStore, Payload};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info};
 use uuid::Uuid;
@@ -21,7 +23,11 @@ pub struct HiRAGManager {
     l1_cache: Arc<RwLock<VecDeque<Context>>>,
     retriever: ContextRetriever,
     ranker: ContextRanker,
+    rerank: RerankStage,
+    access: Option<Arc<AccessTracker>>,
+    feedback: FeedbackLog,
     token_estimator: TokenEstimator,
+    metrics: Option<Arc<crate::observability::MetricsCollector>>,
 }
 
 impl HiRAGManager {
@@ -38,8 +44,15 @@ impl HiRAGManager {
             vector_db.clone(),
             TokenEstimator::new(config.token_estimator),
             config.retrieval_strategy.clone(),
-        );
-        let ranker = ContextRanker::new(config.ranking_weights.clone());
+        ).with_relevance_threshold(config.relevance_threshold);
+        let ranker = ContextRanker::new(config.ranking_weights.clone()).with_recency_decay(config.recency_decay);
+        let rerank = RerankStage::new(config.reranking.clone());
+        let access = config.access_tracking_enabled.then(|| {
+            let tracker = Arc::new(AccessTracker::new(vector_db.clone()));
+            tracker.start(Duration::from_secs(config.access_flush_interval_secs));
+            tracker
+        });
+        let feedback = FeedbackLog::new(&config.feedback_path);
         
         Ok(Self {
             config,
@@ -48,10 +61,37 @@ impl HiRAGManager {
             l1_cache: Arc::new(RwLock::new(VecDeque::new())),
             retriever,
             ranker,
+            rerank,
+            access,
+            feedback,
             token_estimator,
+            metrics: None,
         })
     }
     
+    /// Set metrics collector
+    pub fn with_metrics(mut self, metrics: Arc<crate::observability::MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+    
+    /// Distance metric of `vector_db`, so the relevance threshold applies to similarities
+    pub fn with_distance(mut self, distance: crate::config::Distance) -> Self {
+        self.retriever = self.retriever.with_distance(distance);
+        self
+    }
+    
+    /// Plug in a custom reranker for `kind`
+    pub fn with_reranker(mut self, kind: RerankerKind, reranker: Arc<dyn Reranker>) -> Self {
+        self.rerank = self.rerank.with_reranker(kind, reranker);
+        self
+    }
+    
+    /// BM25 index of stored contexts, for background tasks that delete them
+    pub fn sparse_index(&self) -> Option<Arc<super::sparse::SparseIndex>> {
+        self.retriever.sparse_index()
+    }
+    
     /// Initialize the manager
     pub async fn initialize(&self) -> Result<()> {
         info!("Initializing HiRAG collections");
@@ -62,6 +102,9 @@ impl HiRAGManager {
             
             // Try to create collection (will fail if exists, which is fine)
             let _ = self.vector_db.create_collection(&collection_name).await;
+            check_collection_model(self.vector_db.as_ref(), &collection_name, &self.embedding_client.model_id()).await?;
+            
+            self.retriever.rebuild_index(&collection_name).await?;
         }
         
         Ok(())
@@ -87,13 +130,15 @@ impl HiRAGManager {
         debug!("L1 cache updated, size: {}", cache.len());
     }
     
-    /// Get contexts from L1 cache
-    async fn get_l1_contexts(&self, max_tokens: usize) -> Vec<Context> {
+    /// Get L1 contexts matching `filter`, newest first, within `max_tokens`
+    async fn get_l1_contexts(&self, max_tokens: usize, filter: Option<&Filter>) -> Vec<Context> {
         let cache = self.l1_cache.read().await;
         let mut contexts = Vec::new();
         let mut total_tokens = 0;
         
-        for context in cache.iter() {
+        // Filter first so other namespaces' contexts never use up the budget
+        let visible = cache.iter().filter(|c| filter.is_none_or(|f| c.matches_filter(f)));
+        for context in visible {
             if total_tokens + context.token_count <= max_tokens {
                 contexts.push(context.clone());
                 total_tokens += context.token_count;
@@ -105,21 +150,44 @@ impl HiRAGManager {
         debug!("Retrieved {} contexts from L1 cache", contexts.len());
         contexts
     }
+    
+    /// Queue access write-backs for returned contexts, updating cached L1 copies in place
+    async fn record_hits(&self, contexts: &[Context]) {
+        let Some(access) = &self.access else { return };
+        let now = Utc::now().timestamp();
+        
+        for context in contexts {
+            access.record_hit(&self.collection_name(context.level), context.id);
+        }
+        
+        let mut cache = self.l1_cache.write().await;
+        for cached in cache.iter_mut().filter(|c| contexts.iter().any(|hit| hit.id == c.id)) {
+            record_access(&mut cached.metadata, 1, 0, now);
+        }
+    }
 }
 
 #[async_trait]
 impl ContextManager for HiRAGManager {
     async fn store_context(
         &self,
+        namespace: &Namespace,
         text: &str,
         level: ContextLevel,
-        metadata: HashMap<String, serde_json::Value>,
+        mut metadata: HashMap<String, serde_json::Value>,
+        session_id: Option<&str>,
     ) -> Result<Uuid> {
+        for key in metadata.keys() {
+            InputValidator::validate_metadata_key(key)?;
+        }
+        
         debug!("Storing context at level: {:?}", level);
         
         // Generate embedding
         let embedding = self.embedding_client.embed_single(text).await?;
         
+        metadata.insert(EMBEDDING_MODEL_KEY.to_string(), self.embedding_client.model_id().into());
+        
         // Create point
         let id = Uuid::new_v4();
         let timestamp = Utc::now().timestamp();
@@ -132,8 +200,8 @@ impl ContextManager for HiRAGManager {
                 text: text.to_string(),
                 level,
                 timestamp,
-                agent_id: "default".to_string(),
-                session_id: None,
+                agent_id: namespace.owner().to_string(),
+                session_id: session_id.map(str::to_string),
                 metadata: metadata.clone(),
             },
         };
@@ -141,6 +209,7 @@ impl ContextManager for HiRAGManager {
         // Store in vector database
         let collection = self.collection_name(level);
         self.vector_db.insert_points(&collection, vec![point]).await?;
+        self.retriever.index_context(&collection, id, text);
         
         // Update L1 cache if immediate context
         if level == ContextLevel::Immediate {
@@ -152,6 +221,10 @@ impl ContextManager for HiRAGManager {
                 token_count,
                 timestamp,
                 metadata,
+                agent_id: namespace.owner().to_string(),
+                session_id: session_id.map(str::to_string),
+                channels: Vec::new(),
+                explanation: None,
             };
             self.update_l1_cache(context).await;
         }
@@ -160,12 +233,13 @@ impl ContextManager for HiRAGManager {
         Ok(id)
     }
     
-    async fn retrieve_context(&self, request: ContextRequest) -> Result<ContextResponse> {
+    async fn retrieve_context(&self, namespace: &Namespace, request: ContextRequest) -> Result<ContextResponse> {
         let start_time = std::time::Instant::now();
+        request.validate_ranking_overrides()?;
         debug!("Retrieving context for query: {}", request.query);
         
         // Generate query embedding
-        let query_embedding = self.embedding_client.embed_single(&request.query).await?;
+        let query_embedding = self.embedding_client.embed_query(&request.query).await?;
         
         // Determine which levels to search
         let levels = if request.levels.is_empty() {
@@ -174,10 +248,11 @@ impl ContextManager for HiRAGManager {
             request.levels.clone()
         };
         
-        // Calculate token allocations
-        let (l1_tokens, l2_tokens, l3_tokens) = self.retriever.calculate_allocations(request.max_tokens);
+        // Only contexts inside the caller's namespace (and session, if requested) are visible
+        let filters = request.scope_session(namespace.scope(request.filters.clone()));
         
-        let mut all_contexts = Vec::new();
+        let mut level_candidates = Vec::new();
+        let mut filtered = FilteredCounts::default();
         let mut cache_hits = 0;
         let mut total_searched = 0;
         
@@ -185,42 +260,38 @@ impl ContextManager for HiRAGManager {
         let mut tasks = Vec::new();
         
         for level in levels {
-            let max_tokens = match level {
-                ContextLevel::Immediate => l1_tokens,
-                ContextLevel::ShortTerm => l2_tokens,
-                ContextLevel::LongTerm => l3_tokens,
-            };
-            
             if level == ContextLevel::Immediate {
-                // Use L1 cache (synchronous)
+                // Use L1 cache (synchronous); token budgets are applied below
                 cache_hits += 1;
-                let contexts = self.get_l1_contexts(max_tokens).await;
+                let contexts = self.get_l1_contexts(request.max_tokens, filters.as_ref()).await;
                 total_searched += contexts.len();
-                all_contexts.extend(contexts);
+                level_candidates.push((level, contexts));
             } else {
                 // Search vector database in parallel
                 let collection = self.collection_name(level);
                 let retriever = self.retriever.clone();
                 let embedding = query_embedding.clone();
-                let filters = request.filters.clone();
+                let filters = filters.clone();
+                let query = request.query.clone();
                 
-                tasks.push(tokio::spawn(async move {
-                    retriever.retrieve_from_level(
+                tasks.push((level, tokio::spawn(async move {
+                    retriever.search_level(
                         &collection,
+                        &query,
                         embedding,
-                        max_tokens,
                         filters,
                     ).await
-                }));
+                })));
             }
         }
         
         // Wait for all parallel tasks to complete
-        for task in tasks {
+        for (level, task) in tasks {
             match task.await {
-                Ok(Ok(contexts)) => {
-                    total_searched += contexts.len();
-                    all_contexts.extend(contexts);
+                Ok(Ok(candidates)) => {
+                    total_searched += candidates.contexts.len() + candidates.below_threshold;
+                    filtered.below_threshold += candidates.below_threshold;
+                    level_candidates.push((level, candidates.contexts));
                 }
                 Ok(Err(e)) => {
                     error!("Error retrieving contexts: {}", e);
@@ -233,20 +304,44 @@ impl ContextManager for HiRAGManager {
             }
         }
         
-        // Rank contexts
-        let ranked_contexts = self.ranker.rank_contexts(all_contexts);
+        if request.explain {
+            for (_, contexts) in &mut level_candidates {
+                contexts.iter_mut().for_each(Context::start_explanation);
+            }
+        }
+        
+        // Deduplicate and fit each level into its (redistributed) token budget
+        let packed = self.retriever.pack_levels(request.max_tokens, level_candidates);
+        filtered.duplicates = packed.duplicates;
+        filtered.over_budget = packed.over_budget.len();
+        
+        // Rank contexts, with the request's weights and decay if it sets them
+        let ranker = self.ranker.with_overrides(request.ranking_weights.as_ref(), request.recency_decay);
+        let mut ranked_contexts = ranker.rank_contexts(packed.contexts);
+        if let Some(session_id) = request.session_boosted() {
+            ranked_contexts = ranker.boost_session(ranked_contexts, session_id, self.config.session_boost);
+        }
+        let ranked_contexts = self.rerank.apply(&request.query, ranked_contexts, request.rerank.as_ref()).await;
         
         // Apply token limit
         let mut final_contexts = Vec::new();
+        let mut excluded = Vec::new();
         let mut total_tokens = 0;
         
-        for context in ranked_contexts {
+        for mut context in ranked_contexts {
             if total_tokens + context.token_count <= request.max_tokens {
                 total_tokens += context.token_count;
                 final_contexts.push(context);
+            } else if let Some(explanation) = &mut context.explanation {
+                explanation.cut_by_token_limit = true;
+                excluded.push(context);
             }
         }
         
+        if request.explain {
+            excluded.extend(ranker.rank_contexts(packed.over_budget));
+        }
+        
         // Calculate metadata
         let mut level_distribution = HashMap::new();
         for context in &final_contexts {
@@ -259,6 +354,8 @@ impl ContextManager for HiRAGManager {
             0.0
         };
         
+        self.record_hits(&final_contexts).await;
+        
         let retrieval_time_ms = start_time.elapsed().as_millis() as u64;
         
         info!(
@@ -268,6 +365,10 @@ impl ContextManager for HiRAGManager {
             total_tokens
         );
         
+        if let Some(metrics) = &self.metrics {
+            metrics.record_retrieval(start_time.elapsed(), avg_relevance);
+        }
+        
         Ok(ContextResponse {
             contexts: final_contexts,
             total_tokens,
@@ -277,15 +378,22 @@ impl ContextManager for HiRAGManager {
                 avg_relevance,
                 cache_hits,
                 total_searched,
+                excluded,
+                filtered,
             },
         })
     }
     
     async fn update_context(
         &self,
+        namespace: &Namespace,
         id: Uuid,
         metadata: HashMap<String, serde_json::Value>,
     ) -> Result<()> {
+        for key in metadata.keys() {
+            InputValidator::validate_metadata_key(key)?;
+        }
+        
         debug!("Updating context: {}", id);
         
         // Try to find and update the context in all collections
@@ -294,6 +402,11 @@ impl ContextManager for HiRAGManager {
             
             // Try to get the existing point
             if let Some(mut point) = self.vector_db.get_point(&collection, id).await? {
+                // Contexts of other namespaces are reported as missing
+                if !namespace.contains(&point.payload.agent_id) {
+                    continue;
+                }
+                
                 // Update metadata
                 for (key, value) in metadata.iter() {
                     point.payload.metadata.insert(key.clone(), value.clone());
@@ -313,39 +426,121 @@ impl ContextManager for HiRAGManager {
         Err(HiRAGError::StorageError(format!("Context {} not found", id)).into())
     }
     
-    async fn delete_context(&self, id: Uuid) -> Result<()> {
+    async fn delete_context(&self, namespace: &Namespace, id: Uuid) -> Result<()> {
         debug!("Deleting context: {}", id);
         
-        // Try to delete from all collections
+        // Try to delete from all collections, leaving other namespaces' contexts alone
         for level in &[ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
             let collection = self.collection_name(*level);
-            let _ = self.vector_db.delete_points(&collection, vec![id]).await;
+            let owned = match self.vector_db.get_point(&collection, id).await {
+                Ok(Some(point)) => namespace.contains(&point.payload.agent_id),
+                _ => false,
+            };
+            
+            if owned {
+                let _ = self.vector_db.delete_points(&collection, vec![id]).await;
+                self.retriever.unindex_context(&collection, id);
+            }
         }
         
         // Remove from L1 cache
         let mut cache = self.l1_cache.write().await;
-        cache.retain(|c| c.id != id);
+        cache.retain(|c| c.id != id || !namespace.contains(&c.agent_id));
         
         info!("Context deleted: {}", id);
         Ok(())
     }
     
-    async fn clear_level(&self, level: ContextLevel) -> Result<()> {
-        debug!("Clearing level: {:?}", level);
+    async fn mark_used(&self, namespace: &Namespace, ids: &[Uuid]) -> Result<usize> {
+        InputValidator::validate_batch_size(ids.len())?;
+        
+        let owned = namespace.scope(None);
+        let mut remaining = ids.to_vec();
+        let mut found = Vec::new();
+        
+        for level in [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
+            if remaining.is_empty() {
+                break;
+            }
+            
+            let collection = self.collection_name(level);
+            for point in get_points(self.vector_db.as_ref(), &collection, remaining.clone(), owned.as_ref()).await? {
+                if let Some(access) = &self.access {
+                    access.record_used(&collection, point.id);
+                }
+                found.push(point.id);
+            }
+            remaining.retain(|id| !found.contains(id));
+        }
+        
+        if self.access.is_some() {
+            let now = Utc::now().timestamp();
+            let mut cache = self.l1_cache.write().await;
+            for cached in cache.iter_mut().filter(|c| found.contains(&c.id)) {
+                record_access(&mut cached.metadata, 0, 1, now);
+            }
+        }
+        
+        debug!("Marked {} of {} contexts as used", found.len(), ids.len());
+        Ok(found.len())
+    }
+    
+    async fn record_feedback(&self, namespace: &Namespace, query: &str, judgments: &[Judgment]) -> Result<usize> {
+        let query_embedding = self.embedding_client.embed_query(query).await?;
+        let collections: Vec<_> = [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm]
+            .into_iter()
+            .map(|level| (level, self.collection_name(level)))
+            .collect();
+        
+        let records = feedback::snapshot_judgments(
+            &self.retriever,
+            &self.ranker,
+            &collections,
+            namespace,
+            query,
+            &query_embedding,
+            judgments,
+        ).await?;
+        self.feedback.append(&records).await?;
+        
+        debug!("Recorded feedback on {} of {} contexts", records.len(), judgments.len());
+        Ok(records.len())
+    }
+    
+    async fn clear_level(&self, namespace: &Namespace, level: ContextLevel) -> Result<()> {
+        debug!("Clearing level: {:?} in {:?}", level, namespace);
         
         let collection = self.collection_name(level);
         
-        // Delete and recreate collection
-        let _ = self.vector_db.delete_collection(&collection).await;
-        self.vector_db.create_collection(&collection).await?;
+        match namespace.condition() {
+            // Only remove this namespace's contexts
+            Some(condition) => {
+                self.retriever.delete_matching(&collection, Filter::new().must(condition)).await?;
+            }
+            // Delete and recreate collection
+            None => {
+                let _ = self.vector_db.delete_collection(&collection).await;
+                self.vector_db.create_collection(&collection).await?;
+                self.retriever.clear_index(&collection);
+            }
+        }
         
         // Clear L1 cache if immediate level
         if level == ContextLevel::Immediate {
             let mut cache = self.l1_cache.write().await;
-            cache.clear();
+            cache.retain(|c| !namespace.contains(&c.agent_id));
         }
         
         info!("Level cleared: {:?}", level);
         Ok(())
     }
-}
\ No newline at end of file
+    
+    async fn stats(&self, namespace: &Namespace) -> Result<HiRAGStats> {
+        let collections: Vec<_> = [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm]
+            .into_iter()
+            .map(|level| (level, self.collection_name(level)))
+            .collect();
+        
+        HiRAGStats::collect(self.vector_db.as_ref(), &collections, namespace, self.metrics.as_deref()).await
+    }
+}
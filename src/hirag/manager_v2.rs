@@ -1,29 +1,38 @@
 //! Enhanced HiRAG manager with improved concurrency and error handling
 
-use super::{ContextManager, models::*, retriever::ContextRetriever, ranker::ContextRanker, token_estimator::TokenEstimator};
-use crate::config::HiRAGConfig;
-use crate::embedding::EmbeddingProvider;
+use super::{ContextManager, access::{record_access, AccessTracker}, feedback::{self, FeedbackLog, Judgment}, models::*, rerank::{RerankStage, Reranker}, retriever::ContextRetriever, ranker::ContextRanker, token_estimator::TokenEstimator};
+use crate::config::{HiRAGConfig, RerankerKind};
+use crate::embedding::{check_collection_model, EmbeddingProvider, EMBEDDING_MODEL_KEY};
 use crate::error::{HiRAGError, Result};
-use crate::vector_db::{ContextLevel, VectorPoint, VectorStore, Payload};
+use crate::vector_db::{get_points, ContextLevel, Filter, VectorPoint, VectorStore, Payload};
 use crate::middleware::InputValidator;
 use async_trait::async_trait;
 use chrono::Utc;
 use dashmap::DashMap;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Key of an L1 cache: the owning agent (namespace) and the session, if any
+type L1Key = (String, Option<String>);
+
 /// Enhanced HiRAG manager with improved concurrency safety
 pub struct HiRAGManagerV2 {
     config: HiRAGConfig,
     embedding_client: Arc<dyn EmbeddingProvider>,
     vector_db: Arc<dyn VectorStore>,
-    l1_cache: Arc<DashMap<Uuid, Context>>,
+    /// Immediate contexts per namespace and session; a `None` session holds
+    /// the namespace's contexts stored without one
+    l1_cache: Arc<DashMap<L1Key, DashMap<Uuid, Context>>>,
     l1_cache_size: Arc<AtomicUsize>,
     retriever: ContextRetriever,
     ranker: ContextRanker,
+    rerank: RerankStage,
+    access: Option<Arc<AccessTracker>>,
+    feedback: FeedbackLog,
     token_estimator: TokenEstimator,
     metrics: Option<Arc<crate::observability::MetricsCollector>>,
 }
@@ -42,8 +51,15 @@ impl HiRAGManagerV2 {
             vector_db.clone(),
             TokenEstimator::new(config.token_estimator),
             config.retrieval_strategy.clone(),
-        );
-        let ranker = ContextRanker::new(config.ranking_weights.clone());
+        ).with_relevance_threshold(config.relevance_threshold);
+        let ranker = ContextRanker::new(config.ranking_weights.clone()).with_recency_decay(config.recency_decay);
+        let rerank = RerankStage::new(config.reranking.clone());
+        let access = config.access_tracking_enabled.then(|| {
+            let tracker = Arc::new(AccessTracker::new(vector_db.clone()));
+            tracker.start(Duration::from_secs(config.access_flush_interval_secs));
+            tracker
+        });
+        let feedback = FeedbackLog::new(&config.feedback_path);
         
         Ok(Self {
             config,
@@ -53,6 +69,9 @@ impl HiRAGManagerV2 {
             l1_cache_size: Arc::new(AtomicUsize::new(0)),
             retriever,
             ranker,
+            rerank,
+            access,
+            feedback,
             token_estimator,
             metrics: None,
         })
@@ -64,6 +83,23 @@ impl HiRAGManagerV2 {
         self
     }
     
+    /// Distance metric of `vector_db`, so the relevance threshold applies to similarities
+    pub fn with_distance(mut self, distance: crate::config::Distance) -> Self {
+        self.retriever = self.retriever.with_distance(distance);
+        self
+    }
+    
+    /// Plug in a custom reranker for `kind`
+    pub fn with_reranker(mut self, kind: RerankerKind, reranker: Arc<dyn Reranker>) -> Self {
+        self.rerank = self.rerank.with_reranker(kind, reranker);
+        self
+    }
+    
+    /// BM25 index of stored contexts, for background tasks that delete them
+    pub fn sparse_index(&self) -> Option<Arc<super::sparse::SparseIndex>> {
+        self.retriever.sparse_index()
+    }
+    
     /// Initialize the manager
     pub async fn initialize(&self) -> Result<()> {
         info!("Initializing HiRAG collections");
@@ -74,6 +110,9 @@ impl HiRAGManagerV2 {
             
             // Try to create collection (will fail if exists, which is fine)
             let _ = self.vector_db.create_collection(&collection_name).await;
+            check_collection_model(self.vector_db.as_ref(), &collection_name, &self.embedding_client.model_id()).await?;
+            
+            self.retriever.rebuild_index(&collection_name).await?;
         }
         
         Ok(())
@@ -84,51 +123,92 @@ impl HiRAGManagerV2 {
         format!("contexts_{}", level.as_str().to_lowercase())
     }
     
-    /// Update L1 cache with lock-free DashMap
+    /// Update the L1 cache of the context's namespace and session with lock-free DashMap
     async fn update_l1_cache(&self, context: Context) {
-        let context_id = context.id;
-        
-        // Insert or update context (atomic operation)
-        self.l1_cache.insert(context_id, context);
+        let key: L1Key = (context.agent_id.clone(), context.session_id.clone());
         
-        // Update size counter
-        let current_size = self.l1_cache.len();
-        self.l1_cache_size.store(current_size, Ordering::Relaxed);
+        {
+            // Insert or update context (atomic operation)
+            let cache = self.l1_cache.entry(key.clone()).or_default();
+            cache.insert(context.id, context);
+            
+            // Maintain per-session size limit by removing oldest entries
+            if cache.len() > self.config.l1_size {
+                // Collect IDs to remove (oldest entries)
+                let mut entries: Vec<_> = cache.iter()
+                    .map(|entry| (*entry.key(), entry.value().timestamp))
+                    .collect();
+                
+                // Sort by timestamp (oldest first)
+                entries.sort_by_key(|(_, ts)| *ts);
+                
+                // Remove excess entries
+                let to_remove = entries.len() - self.config.l1_size;
+                for (id, _) in entries.iter().take(to_remove) {
+                    if let Some((_, removed)) = cache.remove(id) {
+                        debug!("Evicted context {} from L1 cache", removed.id);
+                    }
+                }
+            }
+        }
         
-        // Maintain size limit by removing oldest entries
-        if current_size > self.config.l1_size {
-            // Collect IDs to remove (oldest entries)
-            let mut entries: Vec<_> = self.l1_cache.iter()
-                .map(|entry| (*entry.key(), entry.value().timestamp))
+        // Drop the caches of the namespace's least recently written sessions
+        // beyond the limit; other namespaces' caches are never touched
+        let session_count = self.l1_cache.iter().filter(|entry| entry.key().0 == key.0).count();
+        if session_count > self.config.l1_max_sessions {
+            let mut sessions: Vec<_> = self.l1_cache.iter()
+                .filter(|entry| entry.key().0 == key.0 && *entry.key() != key)
+                .map(|entry| {
+                    let newest = entry.value().iter().map(|c| c.timestamp).max().unwrap_or(i64::MIN);
+                    (entry.key().clone(), newest)
+                })
                 .collect();
             
-            // Sort by timestamp (oldest first)
-            entries.sort_by_key(|(_, ts)| *ts);
+            sessions.sort_by_key(|(_, ts)| *ts);
             
-            // Remove excess entries
-            let to_remove = current_size - self.config.l1_size;
-            for (id, _) in entries.iter().take(to_remove) {
-                if let Some((_, removed)) = self.l1_cache.remove(id) {
-                    debug!("Evicted context {} from L1 cache", removed.id);
-                }
+            for (key, _) in sessions.into_iter().take(session_count - self.config.l1_max_sessions) {
+                self.l1_cache.remove(&key);
+                debug!("Evicted L1 cache of session {:?}", key);
             }
-            
-            self.l1_cache_size.store(self.l1_cache.len(), Ordering::Relaxed);
         }
         
-        debug!("L1 cache updated, size: {}", self.l1_cache.len());
+        self.refresh_l1_size();
+        debug!("L1 cache updated, size: {}", self.l1_cache_size.load(Ordering::Relaxed));
     }
     
-    /// Get contexts from L1 cache with lock-free access
-    async fn get_l1_contexts(&self, max_tokens: usize) -> Vec<Context> {
+    /// Recount contexts across all session caches
+    fn refresh_l1_size(&self) {
+        let size = self.l1_cache.iter().map(|cache| cache.len()).sum();
+        self.l1_cache_size.store(size, Ordering::Relaxed);
+    }
+    
+    /// Get contexts from L1 cache with lock-free access.
+    ///
+    /// Only caches of `namespace` are read, and with `session` set only that
+    /// session's. Contexts failing `filter` are skipped before the token budget.
+    async fn get_l1_contexts(
+        &self,
+        max_tokens: usize,
+        namespace: &Namespace,
+        session: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Vec<Context> {
         let mut contexts = Vec::new();
         let mut total_tokens = 0;
         
         // Collect all contexts and sort by timestamp (newest first)
-        let mut all_contexts: Vec<_> = self.l1_cache.iter()
-            .map(|entry| entry.value().clone())
+        let mut all_contexts: Vec<Context> = self.l1_cache.iter()
+            .filter(|cache| {
+                let (agent_id, cached_session) = cache.key();
+                namespace.contains(agent_id) && session.is_none_or(|s| cached_session.as_deref() == Some(s))
+            })
+            .flat_map(|cache| cache.iter().map(|entry| entry.value().clone()).collect::<Vec<_>>())
             .collect();
         
+        if let Some(filter) = filter {
+            all_contexts.retain(|c| c.matches_filter(filter));
+        }
+        
         all_contexts.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
         
         for context in all_contexts {
@@ -144,22 +224,21 @@ impl HiRAGManagerV2 {
         contexts
     }
     
-    /// Deduplicate contexts by ID
-    fn deduplicate_contexts(&self, contexts: Vec<Context>) -> Vec<Context> {
-        let mut seen_ids = HashSet::new();
-        let mut deduplicated = Vec::new();
-        let original_count = contexts.len();
+    /// Queue access write-backs for returned contexts, updating cached L1 copies in place
+    fn record_hits(&self, contexts: &[Context]) {
+        let Some(access) = &self.access else { return };
+        let now = Utc::now().timestamp();
         
         for context in contexts {
-            if seen_ids.insert(context.id) {
-                deduplicated.push(context);
-            } else {
-                debug!("Removed duplicate context: {}", context.id);
+            access.record_hit(&self.collection_name(context.level), context.id);
+            if context.level == ContextLevel::Immediate {
+                if let Some(cache) = self.l1_cache.get(&(context.agent_id.clone(), context.session_id.clone())) {
+                    if let Some(mut cached) = cache.get_mut(&context.id) {
+                        record_access(&mut cached.metadata, 1, 0, now);
+                    }
+                }
             }
         }
-        
-        debug!("Deduplicated {} -> {} contexts", original_count, deduplicated.len());
-        deduplicated
     }
 }
 
@@ -167,9 +246,11 @@ impl HiRAGManagerV2 {
 impl ContextManager for HiRAGManagerV2 {
     async fn store_context(
         &self,
+        namespace: &Namespace,
         text: &str,
         level: ContextLevel,
-        metadata: HashMap<String, serde_json::Value>,
+        mut metadata: HashMap<String, serde_json::Value>,
+        session_id: Option<&str>,
     ) -> Result<Uuid> {
         // Validate input
         InputValidator::validate_text(text)?;
@@ -187,9 +268,11 @@ impl ContextManager for HiRAGManagerV2 {
         // Validate vector dimension
         InputValidator::validate_vector_dimension(
             embedding.len(),
-            1024, // Expected dimension for multilingual-e5-large
+            self.embedding_client.embedding_dimension(),
         )?;
         
+        metadata.insert(EMBEDDING_MODEL_KEY.to_string(), self.embedding_client.model_id().into());
+        
         // Create point
         let id = Uuid::new_v4();
         let timestamp = Utc::now().timestamp();
@@ -202,8 +285,8 @@ impl ContextManager for HiRAGManagerV2 {
                 text: text.to_string(),
                 level,
                 timestamp,
-                agent_id: "default".to_string(),
-                session_id: None,
+                agent_id: namespace.owner().to_string(),
+                session_id: session_id.map(str::to_string),
                 metadata: metadata.clone(),
             },
         };
@@ -211,6 +294,7 @@ impl ContextManager for HiRAGManagerV2 {
         // Store in vector database
         let collection = self.collection_name(level);
         self.vector_db.insert_points(&collection, vec![point]).await?;
+        self.retriever.index_context(&collection, id, text);
         
         // Update L1 cache if immediate context
         if level == ContextLevel::Immediate {
@@ -222,6 +306,10 @@ impl ContextManager for HiRAGManagerV2 {
                 token_count,
                 timestamp,
                 metadata,
+                agent_id: namespace.owner().to_string(),
+                session_id: session_id.map(str::to_string),
+                channels: Vec::new(),
+                explanation: None,
             };
             self.update_l1_cache(context).await;
         }
@@ -236,17 +324,18 @@ impl ContextManager for HiRAGManagerV2 {
         Ok(id)
     }
     
-    async fn retrieve_context(&self, request: ContextRequest) -> Result<ContextResponse> {
+    async fn retrieve_context(&self, namespace: &Namespace, request: ContextRequest) -> Result<ContextResponse> {
         let start_time = std::time::Instant::now();
         
         // Validate input
         InputValidator::validate_text(&request.query)?;
         InputValidator::validate_token_count(request.max_tokens, 100000)?;
+        request.validate_ranking_overrides()?;
         
         debug!("Retrieving context for query: {}", request.query);
         
         // Generate query embedding
-        let query_embedding = self.embedding_client.embed_single(&request.query).await?;
+        let query_embedding = self.embedding_client.embed_query(&request.query).await?;
         
         // Determine which levels to search
         let levels = if request.levels.is_empty() {
@@ -255,10 +344,11 @@ impl ContextManager for HiRAGManagerV2 {
             request.levels.clone()
         };
         
-        // Calculate token allocations
-        let (l1_tokens, l2_tokens, l3_tokens) = self.retriever.calculate_allocations(request.max_tokens);
+        // Only contexts inside the caller's namespace (and session, if requested) are visible
+        let filters = request.scope_session(namespace.scope(request.filters.clone()));
         
-        let mut all_contexts = Vec::new();
+        let mut level_candidates = Vec::new();
+        let mut filtered = FilteredCounts::default();
         let mut cache_hits = 0;
         let mut total_searched = 0;
         
@@ -266,42 +356,40 @@ impl ContextManager for HiRAGManagerV2 {
         let mut tasks = Vec::new();
         
         for level in levels {
-            let max_tokens = match level {
-                ContextLevel::Immediate => l1_tokens,
-                ContextLevel::ShortTerm => l2_tokens,
-                ContextLevel::LongTerm => l3_tokens,
-            };
-            
             if level == ContextLevel::Immediate {
-                // Use L1 cache (synchronous)
+                // Use L1 cache (synchronous); token budgets are applied below
                 cache_hits += 1;
-                let contexts = self.get_l1_contexts(max_tokens).await;
+                let contexts = self
+                    .get_l1_contexts(request.max_tokens, namespace, request.session_only(), filters.as_ref())
+                    .await;
                 total_searched += contexts.len();
-                all_contexts.extend(contexts);
+                level_candidates.push((level, contexts));
             } else {
                 // Search vector database in parallel
                 let collection = self.collection_name(level);
                 let retriever = self.retriever.clone();
                 let embedding = query_embedding.clone();
-                let filters = request.filters.clone();
+                let filters = filters.clone();
+                let query = request.query.clone();
                 
-                tasks.push(tokio::spawn(async move {
-                    retriever.retrieve_from_level(
+                tasks.push((level, tokio::spawn(async move {
+                    retriever.search_level(
                         &collection,
+                        &query,
                         embedding,
-                        max_tokens,
                         filters,
                     ).await
-                }));
+                })));
             }
         }
         
         // Wait for all parallel tasks with partial failure handling
-        for task in tasks {
+        for (level, task) in tasks {
             match task.await {
-                Ok(Ok(contexts)) => {
-                    total_searched += contexts.len();
-                    all_contexts.extend(contexts);
+                Ok(Ok(candidates)) => {
+                    total_searched += candidates.contexts.len() + candidates.below_threshold;
+                    filtered.below_threshold += candidates.below_threshold;
+                    level_candidates.push((level, candidates.contexts));
                 }
                 Ok(Err(e)) => {
                     warn!("Error retrieving contexts from one level: {}", e);
@@ -314,23 +402,44 @@ impl ContextManager for HiRAGManagerV2 {
             }
         }
         
-        // Deduplicate contexts
-        all_contexts = self.deduplicate_contexts(all_contexts);
+        if request.explain {
+            for (_, contexts) in &mut level_candidates {
+                contexts.iter_mut().for_each(Context::start_explanation);
+            }
+        }
         
-        // Rank contexts
-        let ranked_contexts = self.ranker.rank_contexts(all_contexts);
+        // Deduplicate and fit each level into its (redistributed) token budget
+        let packed = self.retriever.pack_levels(request.max_tokens, level_candidates);
+        filtered.duplicates = packed.duplicates;
+        filtered.over_budget = packed.over_budget.len();
+        
+        // Rank contexts, with the request's weights and decay if it sets them
+        let ranker = self.ranker.with_overrides(request.ranking_weights.as_ref(), request.recency_decay);
+        let mut ranked_contexts = ranker.rank_contexts(packed.contexts);
+        if let Some(session_id) = request.session_boosted() {
+            ranked_contexts = ranker.boost_session(ranked_contexts, session_id, self.config.session_boost);
+        }
+        let ranked_contexts = self.rerank.apply(&request.query, ranked_contexts, request.rerank.as_ref()).await;
         
         // Apply token limit
         let mut final_contexts = Vec::new();
+        let mut excluded = Vec::new();
         let mut total_tokens = 0;
         
-        for context in ranked_contexts {
+        for mut context in ranked_contexts {
             if total_tokens + context.token_count <= request.max_tokens {
                 total_tokens += context.token_count;
                 final_contexts.push(context);
+            } else if let Some(explanation) = &mut context.explanation {
+                explanation.cut_by_token_limit = true;
+                excluded.push(context);
             }
         }
         
+        if request.explain {
+            excluded.extend(ranker.rank_contexts(packed.over_budget));
+        }
+        
         // Calculate metadata
         let mut level_distribution = HashMap::new();
         for context in &final_contexts {
@@ -343,6 +452,8 @@ impl ContextManager for HiRAGManagerV2 {
             0.0
         };
         
+        self.record_hits(&final_contexts);
+        
         let retrieval_time_ms = start_time.elapsed().as_millis() as u64;
         
         info!(
@@ -355,6 +466,7 @@ impl ContextManager for HiRAGManagerV2 {
         // Record metrics
         if let Some(metrics) = &self.metrics {
             metrics.record_request(start_time.elapsed());
+            metrics.record_retrieval(start_time.elapsed(), avg_relevance);
             // Record cache hits
             for _ in 0..cache_hits {
                 metrics.record_cache_hit();
@@ -370,12 +482,15 @@ impl ContextManager for HiRAGManagerV2 {
                 avg_relevance,
                 cache_hits,
                 total_searched,
+                excluded,
+                filtered,
             },
         })
     }
     
     async fn update_context(
         &self,
+        namespace: &Namespace,
         id: Uuid,
         metadata: HashMap<String, serde_json::Value>,
     ) -> Result<()> {
@@ -392,6 +507,11 @@ impl ContextManager for HiRAGManagerV2 {
             
             // Try to get the existing point
             if let Some(mut point) = self.vector_db.get_point(&collection, id).await? {
+                // Contexts of other namespaces are reported as missing
+                if !namespace.contains(&point.payload.agent_id) {
+                    continue;
+                }
+                
                 // Update metadata
                 for (key, value) in metadata.iter() {
                     point.payload.metadata.insert(key.clone(), value.clone());
@@ -414,6 +534,10 @@ impl ContextManager for HiRAGManagerV2 {
                         token_count,
                         timestamp: point.payload.timestamp,
                         metadata: point.payload.metadata,
+                        agent_id: point.payload.agent_id,
+                        session_id: point.payload.session_id,
+                        channels: Vec::new(),
+                        explanation: None,
                     };
                     self.update_l1_cache(context).await;
                 }
@@ -426,39 +550,130 @@ impl ContextManager for HiRAGManagerV2 {
         Err(HiRAGError::StorageError(format!("Context {} not found", id)).into())
     }
     
-    async fn delete_context(&self, id: Uuid) -> Result<()> {
+    async fn delete_context(&self, namespace: &Namespace, id: Uuid) -> Result<()> {
         debug!("Deleting context: {}", id);
         
-        // Try to delete from all collections
+        // Try to delete from all collections, leaving other namespaces' contexts alone
         for level in &[ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
             let collection = self.collection_name(*level);
-            let _ = self.vector_db.delete_points(&collection, vec![id]).await;
+            let owned = match self.vector_db.get_point(&collection, id).await {
+                Ok(Some(point)) => namespace.contains(&point.payload.agent_id),
+                _ => false,
+            };
+            
+            if owned {
+                let _ = self.vector_db.delete_points(&collection, vec![id]).await;
+                self.retriever.unindex_context(&collection, id);
+            }
         }
         
         // Remove from L1 cache (lock-free)
-        self.l1_cache.remove(&id);
-        self.l1_cache_size.store(self.l1_cache.len(), Ordering::Relaxed);
+        for cache in self.l1_cache.iter() {
+            cache.remove_if(&id, |_, c| namespace.contains(&c.agent_id));
+        }
+        self.l1_cache.retain(|_, cache| !cache.is_empty());
+        self.refresh_l1_size();
         
         info!("Context deleted: {}", id);
         Ok(())
     }
     
-    async fn clear_level(&self, level: ContextLevel) -> Result<()> {
-        debug!("Clearing level: {:?}", level);
+    async fn mark_used(&self, namespace: &Namespace, ids: &[Uuid]) -> Result<usize> {
+        InputValidator::validate_batch_size(ids.len())?;
+        
+        let now = Utc::now().timestamp();
+        let owned = namespace.scope(None);
+        let mut remaining = ids.to_vec();
+        let mut found = 0;
+        
+        for level in [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
+            if remaining.is_empty() {
+                break;
+            }
+            
+            let collection = self.collection_name(level);
+            let points = get_points(self.vector_db.as_ref(), &collection, remaining.clone(), owned.as_ref()).await?;
+            for point in &points {
+                found += 1;
+                if let Some(access) = &self.access {
+                    access.record_used(&collection, point.id);
+                    if level == ContextLevel::Immediate {
+                        for cache in self.l1_cache.iter() {
+                            if let Some(mut cached) = cache.get_mut(&point.id) {
+                                record_access(&mut cached.metadata, 0, 1, now);
+                            }
+                        }
+                    }
+                }
+            }
+            remaining.retain(|id| points.iter().all(|p| p.id != *id));
+        }
+        
+        debug!("Marked {} of {} contexts as used", found, ids.len());
+        Ok(found)
+    }
+    
+    async fn record_feedback(&self, namespace: &Namespace, query: &str, judgments: &[Judgment]) -> Result<usize> {
+        InputValidator::validate_text(query)?;
+        
+        let query_embedding = self.embedding_client.embed_query(query).await?;
+        let collections: Vec<_> = [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm]
+            .into_iter()
+            .map(|level| (level, self.collection_name(level)))
+            .collect();
+        
+        let records = feedback::snapshot_judgments(
+            &self.retriever,
+            &self.ranker,
+            &collections,
+            namespace,
+            query,
+            &query_embedding,
+            judgments,
+        ).await?;
+        self.feedback.append(&records).await?;
+        
+        debug!("Recorded feedback on {} of {} contexts", records.len(), judgments.len());
+        Ok(records.len())
+    }
+    
+    async fn clear_level(&self, namespace: &Namespace, level: ContextLevel) -> Result<()> {
+        debug!("Clearing level: {:?} in {:?}", level, namespace);
         
         let collection = self.collection_name(level);
         
-        // Delete and recreate collection
-        let _ = self.vector_db.delete_collection(&collection).await;
-        self.vector_db.create_collection(&collection).await?;
+        match namespace.condition() {
+            // Only remove this namespace's contexts
+            Some(condition) => {
+                self.retriever.delete_matching(&collection, Filter::new().must(condition)).await?;
+            }
+            // Delete and recreate collection
+            None => {
+                let _ = self.vector_db.delete_collection(&collection).await;
+                self.vector_db.create_collection(&collection).await?;
+                self.retriever.clear_index(&collection);
+            }
+        }
         
         // Clear L1 cache if immediate level
         if level == ContextLevel::Immediate {
-            self.l1_cache.clear();
-            self.l1_cache_size.store(0, Ordering::Relaxed);
+            self.l1_cache.retain(|_, cache| {
+                cache.retain(|_, c| !namespace.contains(&c.agent_id));
+                !cache.is_empty()
+            });
+            self.refresh_l1_size();
         }
         
         info!("Level cleared: {:?}", level);
         Ok(())
     }
-}
\ No newline at end of file
+    
+    async fn stats(&self, namespace: &Namespace) -> Result<HiRAGStats> {
+        let collections: Vec<_> = [ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm]
+            .into_iter()
+            .map(|level| (level, self.collection_name(level)))
+            .collect();
+        
+        HiRAGStats::collect(self.vector_db.as_ref(), &collections, namespace, self.metrics.as_deref()).await
+    }
+}
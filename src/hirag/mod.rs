@@ -8,12 +8,20 @@ pub mod ranker;
 pub mod models;
 pub mod token_estimator;
 pub mod background;
+pub mod sparse;
+pub mod rerank;
+pub mod access;
+pub mod feedback;
 
 pub use manager::HiRAGManager;
 pub use manager_v2::HiRAGManagerV2;
 pub use manager_enhanced::EnhancedHiRAGManager;
-pub use models::{Context, ContextRequest, ContextResponse, Priority};
-pub use ranker::ContextRanker;
+pub use models::{Context, ContextRequest, ContextResponse, FilteredCounts, HiRAGStats, Namespace, Priority, RerankOptions, RetrievalChannel, ScoreExplanation, SessionMode};
+pub use access::AccessTracker;
+pub use feedback::{fit_ranking_weights, FeedbackLog, FeedbackRecord, Judgment, WeightFit};
+pub use rerank::{HttpReranker, LexicalReranker, RerankStage, Reranker};
+pub use sparse::SparseIndex;
+pub use ranker::{ContextRanker, RankFeatures};
 pub use token_estimator::TokenEstimator;
 
 use async_trait::async_trait;
@@ -22,30 +30,54 @@ use crate::vector_db::ContextLevel;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-/// Trait for context management operations
+/// Trait for context management operations.
+///
+/// Every operation runs inside a [`Namespace`]: stored contexts are owned
+/// by it, and retrievals, updates, deletes, clears and stats only see
+/// contexts it contains. Contexts stored with a session id can be
+/// narrowed to or boosted for that session through [`SessionMode`].
 #[async_trait]
 pub trait ContextManager: Send + Sync {
     /// Store new context
     async fn store_context(
         &self,
+        namespace: &Namespace,
         text: &str,
         level: ContextLevel,
         metadata: HashMap<String, serde_json::Value>,
+        session_id: Option<&str>,
     ) -> Result<Uuid>;
     
     /// Retrieve relevant contexts
-    async fn retrieve_context(&self, request: ContextRequest) -> Result<ContextResponse>;
+    async fn retrieve_context(&self, namespace: &Namespace, request: ContextRequest) -> Result<ContextResponse>;
     
     /// Update context metadata
     async fn update_context(
         &self,
+        namespace: &Namespace,
         id: Uuid,
         metadata: HashMap<String, serde_json::Value>,
     ) -> Result<()>;
     
     /// Delete context
-    async fn delete_context(&self, id: Uuid) -> Result<()>;
+    async fn delete_context(&self, namespace: &Namespace, id: Uuid) -> Result<()>;
+    
+    /// Record that the caller actually used the given contexts.
+    ///
+    /// Returns how many of `ids` were found in the namespace; the feedback
+    /// is written back asynchronously with the retrieval hits. At most 100
+    /// IDs are accepted per call.
+    async fn mark_used(&self, namespace: &Namespace, ids: &[Uuid]) -> Result<usize>;
+    
+    /// Store helpful/unhelpful judgments of contexts returned for `query`.
+    ///
+    /// Returns how many judged contexts were found in the namespace; their
+    /// ranking features are logged for fitting `RankingWeights` offline.
+    async fn record_feedback(&self, namespace: &Namespace, query: &str, judgments: &[Judgment]) -> Result<usize>;
     
     /// Clear contexts by level
-    async fn clear_level(&self, level: ContextLevel) -> Result<()>;
+    async fn clear_level(&self, namespace: &Namespace, level: ContextLevel) -> Result<()>;
+    
+    /// Get storage statistics of the namespace's contexts and the retrieval statistics
+    async fn stats(&self, namespace: &Namespace) -> Result<HiRAGStats>;
 }
\ No newline at end of file
@@ -3,7 +3,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
-use crate::vector_db::{ContextLevel, Filter};
+use crate::config::{validation, RankingWeights, RecencyDecay, RerankerKind};
+use crate::error::Result;
+use crate::observability::MetricsCollector;
+use crate::vector_db::{Condition, ContextLevel, Filter, VectorStore, RESERVED_PAYLOAD_KEYS};
 
 /// Context item with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +31,123 @@ pub struct Context {
     
     /// Additional metadata
     pub metadata: HashMap<String, serde_json::Value>,
+    
+    /// Namespace (agent or tenant) owning this context
+    #[serde(default)]
+    pub agent_id: String,
+    
+    /// Session the context was stored in, if any
+    #[serde(default)]
+    pub session_id: Option<String>,
+    
+    /// Retrieval channels that surfaced this context
+    #[serde(default)]
+    pub channels: Vec<RetrievalChannel>,
+    
+    /// How the context was scored, when the request asked to explain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
+}
+
+/// Breakdown of a context's score, returned when `ContextRequest.explain` is set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoreExplanation {
+    /// Vector (or fused hybrid) score before ranking
+    pub vector_score: f32,
+    
+    /// Similarity component, already weighted
+    pub similarity: f32,
+    
+    /// Recency component, already weighted
+    pub recency: f32,
+    
+    /// Level component, already weighted
+    pub level: f32,
+    
+    /// Access frequency component, already weighted
+    pub frequency: f32,
+    
+    /// Multiplier applied for matching the request's session
+    pub session_boost: Option<f32>,
+    
+    /// Reranker score that replaced the heuristic score
+    pub rerank_score: Option<f32>,
+    
+    /// Token budget of the level the context was retrieved from
+    pub level_budget: usize,
+    
+    /// Whether the context was left out for not fitting the token limit
+    pub cut_by_token_limit: bool,
+}
+
+/// Search channel contributing a retrieved context
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RetrievalChannel {
+    /// Dense vector similarity
+    Dense,
+    /// Sparse lexical (BM25) match
+    Sparse,
+}
+
+/// Memory namespace an operation is scoped to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Namespace {
+    /// Contexts owned by one agent or tenant
+    Agent(String),
+    /// Every namespace at once; only granted to admin callers
+    All,
+}
+
+impl Default for Namespace {
+    fn default() -> Self {
+        Namespace::Agent(Self::DEFAULT_AGENT.to_string())
+    }
+}
+
+impl Namespace {
+    /// Owner of contexts stored without an explicit identity
+    pub const DEFAULT_AGENT: &'static str = "default";
+    
+    pub fn agent(id: impl Into<String>) -> Self {
+        Namespace::Agent(id.into())
+    }
+    
+    /// Agent id recorded on newly stored contexts
+    pub fn owner(&self) -> &str {
+        match self {
+            Namespace::Agent(id) => id,
+            Namespace::All => Self::DEFAULT_AGENT,
+        }
+    }
+    
+    /// Whether a context owned by `agent_id` is visible in this namespace
+    pub fn contains(&self, agent_id: &str) -> bool {
+        match self {
+            Namespace::Agent(id) => id == agent_id,
+            Namespace::All => true,
+        }
+    }
+    
+    /// Condition restricting points to this namespace, `None` for `All`
+    pub fn condition(&self) -> Option<Condition> {
+        match self {
+            Namespace::Agent(id) => Some(Condition::Match {
+                key: "agent_id".to_string(),
+                value: serde_json::Value::from(id.clone()),
+            }),
+            Namespace::All => None,
+        }
+    }
+    
+    /// Add this namespace's condition to `filter`
+    pub fn scope(&self, filter: Option<Filter>) -> Option<Filter> {
+        match self.condition() {
+            Some(condition) => Some(filter.unwrap_or_default().must(condition)),
+            None => filter,
+        }
+    }
 }
 
 /// Request for context retrieval
@@ -52,6 +172,54 @@ pub struct ContextRequest {
     
     /// Session context
     pub session_id: Option<String>,
+    
+    /// How `session_id` narrows or reorders results
+    #[serde(default)]
+    pub session_mode: SessionMode,
+    
+    /// Attach a `ScoreExplanation` to each context and list the ones cut by the token limit
+    #[serde(default)]
+    pub explain: bool,
+    
+    /// Reranking of the top results; `None` uses the configured default
+    #[serde(default)]
+    pub rerank: Option<RerankOptions>,
+    
+    /// Ranking weights for this request instead of `hirag.ranking_weights`
+    #[serde(default)]
+    pub ranking_weights: Option<RankingWeights>,
+    
+    /// Recency decay for this request instead of `hirag.recency_decay`
+    #[serde(default)]
+    pub recency_decay: Option<RecencyDecay>,
+}
+
+/// Per-request reranking options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankOptions {
+    /// Reranker to apply, `none` to keep the heuristic ranking
+    pub reranker: RerankerKind,
+    
+    /// Number of top contexts to rerank, at most `reranking.top_n`
+    #[serde(default)]
+    pub top_n: Option<usize>,
+    
+    /// Reranking deadline, at most `reranking.timeout_ms`
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Use of the request's session when retrieving contexts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionMode {
+    /// Ignore the session
+    Global,
+    /// Search everything, but rank the session's contexts higher
+    #[default]
+    Boost,
+    /// Only return contexts stored in the session
+    Only,
 }
 
 /// Priority levels for context retrieval
@@ -94,6 +262,25 @@ pub struct ResponseMetadata {
     
     /// Total contexts searched
     pub total_searched: usize,
+    
+    /// Contexts cut by the token limit, only listed when explaining
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<Context>,
+    
+    /// Candidates left out of the response, by reason
+    #[serde(default)]
+    pub filtered: FilteredCounts,
+}
+
+/// Retrieved candidates dropped before the response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilteredCounts {
+    /// Scored below `relevance_threshold`
+    pub below_threshold: usize,
+    /// Did not fit the token budget
+    pub over_budget: usize,
+    /// Surfaced by more than one level
+    pub duplicates: usize,
 }
 
 /// Statistics about HiRAG system
@@ -114,7 +301,7 @@ pub struct HiRAGStats {
     /// Average relevance score
     pub avg_relevance_score: f32,
     
-    /// Total storage size (bytes)
+    /// Total storage size (bytes), estimated by some backends (see `CollectionStats::size_bytes`)
     pub storage_size_bytes: usize,
 }
 
@@ -134,7 +321,96 @@ impl Context {
             token_count,
             timestamp,
             metadata: HashMap::new(),
+            agent_id: Namespace::DEFAULT_AGENT.to_string(),
+            session_id: None,
+            channels: Vec::new(),
+            explanation: None,
+        }
+    }
+    
+    /// Start a score explanation from the current (vector) score
+    pub fn start_explanation(&mut self) {
+        self.explanation = Some(ScoreExplanation {
+            vector_score: self.relevance_score,
+            ..ScoreExplanation::default()
+        });
+    }
+}
+
+impl HiRAGStats {
+    /// Build stats from per-level collection sizes and rolling retrieval metrics.
+    ///
+    /// Only contexts in `namespace` are counted; a single namespace's share
+    /// of the storage size is estimated from its share of the points.
+    pub async fn collect(
+        vector_db: &dyn VectorStore,
+        collections: &[(ContextLevel, String)],
+        namespace: &Namespace,
+        metrics: Option<&MetricsCollector>,
+    ) -> Result<Self> {
+        let mut contexts_per_level = HashMap::new();
+        let mut total_contexts = 0;
+        let mut storage_size_bytes = 0;
+        
+        for (level, collection) in collections {
+            let stats = vector_db.collection_stats(collection).await?;
+            let (count, size_bytes) = match namespace.scope(None) {
+                Some(filter) => {
+                    let count = vector_db.count(collection, Some(filter)).await?;
+                    let size = stats.size_bytes.checked_mul(count).and_then(|s| s.checked_div(stats.points_count));
+                    (count, size.unwrap_or(0))
+                }
+                None => (stats.points_count, stats.size_bytes),
+            };
+            contexts_per_level.insert(*level, count as usize);
+            total_contexts += count as usize;
+            storage_size_bytes += size_bytes as usize;
+        }
+        
+        let (avg_retrieval_time_ms, cache_hit_rate, avg_relevance_score) = match metrics {
+            Some(metrics) => {
+                let retrieval = metrics.retrieval_metrics();
+                (
+                    retrieval.avg_retrieval_time_ms,
+                    metrics.get_metrics().cache_hit_rate,
+                    retrieval.avg_relevance_score,
+                )
+            }
+            None => (0.0, 0.0, 0.0),
+        };
+        
+        Ok(Self {
+            total_contexts,
+            contexts_per_level,
+            avg_retrieval_time_ms,
+            cache_hit_rate,
+            avg_relevance_score,
+            storage_size_bytes,
+        })
+    }
+}
+
+impl Context {
+    /// Check a cached context against a vector store filter.
+    ///
+    /// Only fields kept on `Context` (text, level, timestamp, agent_id,
+    /// session_id and metadata) are visible.
+    pub fn matches_filter(&self, filter: &Filter) -> bool {
+        let mut payload: serde_json::Map<String, serde_json::Value> = self
+            .metadata
+            .iter()
+            .filter(|(key, _)| !RESERVED_PAYLOAD_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        payload.insert("text".to_string(), serde_json::Value::from(self.text.clone()));
+        payload.insert("level".to_string(), serde_json::Value::from(self.level.as_str()));
+        payload.insert("timestamp".to_string(), serde_json::Value::from(self.timestamp));
+        payload.insert("agent_id".to_string(), serde_json::Value::from(self.agent_id.clone()));
+        if let Some(session_id) = &self.session_id {
+            payload.insert("session_id".to_string(), serde_json::Value::from(session_id.clone()));
         }
+        
+        filter.matches(&self.id, &serde_json::Value::Object(payload))
     }
 }
 
@@ -147,6 +423,11 @@ impl ContextRequest {
             filters: None,
             priority: Priority::Normal,
             session_id: None,
+            session_mode: SessionMode::default(),
+            explain: false,
+            rerank: None,
+            ranking_weights: None,
+            recency_decay: None,
         }
     }
     
@@ -159,6 +440,69 @@ impl ContextRequest {
         self.session_id = Some(session_id);
         self
     }
+    
+    pub fn with_session_mode(mut self, mode: SessionMode) -> Self {
+        self.session_mode = mode;
+        self
+    }
+    
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+    
+    pub fn with_rerank(mut self, options: RerankOptions) -> Self {
+        self.rerank = Some(options);
+        self
+    }
+    
+    pub fn with_ranking_weights(mut self, weights: RankingWeights) -> Self {
+        self.ranking_weights = Some(weights);
+        self
+    }
+    
+    pub fn with_recency_decay(mut self, decay: RecencyDecay) -> Self {
+        self.recency_decay = Some(decay);
+        self
+    }
+    
+    /// Check the ranking weight and decay overrides like their config counterparts
+    pub fn validate_ranking_overrides(&self) -> Result<()> {
+        if let Some(weights) = &self.ranking_weights {
+            validation::validate_ranking_weights(weights)?;
+        }
+        if let Some(decay) = &self.recency_decay {
+            validation::validate_recency_decay(decay)?;
+        }
+        Ok(())
+    }
+    
+    /// Session whose contexts are the only ones returned, for `SessionMode::Only`
+    pub fn session_only(&self) -> Option<&str> {
+        match self.session_mode {
+            SessionMode::Only => self.session_id.as_deref(),
+            _ => None,
+        }
+    }
+    
+    /// Session whose contexts are ranked higher, for `SessionMode::Boost`
+    pub fn session_boosted(&self) -> Option<&str> {
+        match self.session_mode {
+            SessionMode::Boost => self.session_id.as_deref(),
+            _ => None,
+        }
+    }
+    
+    /// Add the session condition to `filter` when retrieval is session-only
+    pub fn scope_session(&self, filter: Option<Filter>) -> Option<Filter> {
+        match self.session_only() {
+            Some(session_id) => Some(filter.unwrap_or_default().must(Condition::Match {
+                key: "session_id".to_string(),
+                value: serde_json::Value::from(session_id),
+            })),
+            None => filter,
+        }
+    }
 }
 /// Search query for API endpoints
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -188,3 +532,149 @@ pub struct ContextFilter {
     /// Filter by creation date (after)
     pub created_after: Option<chrono::DateTime<chrono::Utc>>,
 }
+
+impl ContextFilter {
+    /// Translate into vector store conditions, all of which must hold.
+    ///
+    /// `created_after` applies to the context `timestamp`; `expires_before`
+    /// applies to an `expires_at` metadata field (unix seconds or RFC 3339).
+    pub fn to_conditions(&self) -> Vec<Condition> {
+        let mut conditions = Vec::new();
+        
+        if let Some(level) = self.level {
+            conditions.push(Condition::Match {
+                key: "level".to_string(),
+                value: serde_json::Value::from(level.as_str()),
+            });
+        }
+        
+        if let Some(tags) = &self.tags {
+            conditions.push(Condition::MatchAny {
+                key: "tags".to_string(),
+                any: tags.iter().cloned().map(serde_json::Value::from).collect(),
+            });
+        }
+        
+        if let Some(expires_before) = self.expires_before {
+            conditions.push(Condition::DatetimeRange {
+                key: "expires_at".to_string(),
+                gte: None,
+                lte: Some(expires_before),
+            });
+        }
+        
+        if let Some(created_after) = self.created_after {
+            conditions.push(Condition::DatetimeRange {
+                key: "timestamp".to_string(),
+                gte: Some(created_after),
+                lte: None,
+            });
+        }
+        
+        conditions
+    }
+    
+    /// Translate into a vector store filter
+    pub fn to_filter(&self) -> Filter {
+        Filter {
+            must: self.to_conditions(),
+            ..Filter::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_context_filter_matches_cached_context() {
+        let mut context = Context::new(Uuid::new_v4(), "text".to_string(), ContextLevel::Immediate, 1_700_000_000, 1);
+        context.metadata.insert("tags".to_string(), serde_json::json!(["deploy", "rust"]));
+        
+        let created = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&chrono::Utc);
+        let mut filter = ContextFilter {
+            level: Some(ContextLevel::Immediate),
+            tags: Some(vec!["rust".to_string(), "go".to_string()]),
+            expires_before: None,
+            created_after: Some(created("2023-01-01T00:00:00Z")),
+        };
+        assert_eq!(filter.to_conditions().len(), 3);
+        assert!(context.matches_filter(&filter.to_filter()));
+        
+        filter.tags = Some(vec!["go".to_string()]);
+        assert!(!context.matches_filter(&filter.to_filter()));
+        
+        filter.tags = None;
+        filter.created_after = Some(created("2024-01-01T00:00:00Z"));
+        assert!(!context.matches_filter(&filter.to_filter()));
+    }
+    
+    #[test]
+    fn test_namespace_scopes_cached_contexts() {
+        let mut context = Context::new(Uuid::new_v4(), "text".to_string(), ContextLevel::Immediate, 0, 1);
+        context.agent_id = "tenant-a".to_string();
+        context.metadata.insert("agent_id".to_string(), serde_json::json!("tenant-b"));
+        
+        let own = Namespace::agent("tenant-a").scope(None).unwrap();
+        let other = Namespace::agent("tenant-b").scope(Some(Filter::new())).unwrap();
+        assert!(context.matches_filter(&own));
+        assert!(!context.matches_filter(&other));
+        
+        assert!(Namespace::All.scope(None).is_none());
+        assert!(Namespace::All.contains("tenant-b"));
+        assert_eq!(Namespace::All.owner(), Namespace::DEFAULT_AGENT);
+    }
+    
+    #[test]
+    fn test_session_only_scopes_cached_contexts() {
+        let mut context = Context::new(Uuid::new_v4(), "text".to_string(), ContextLevel::Immediate, 0, 1);
+        let request = ContextRequest::new("q".to_string(), 100).with_session("s1".to_string());
+        assert!(request.scope_session(None).is_none());
+        assert_eq!(request.session_boosted(), Some("s1"));
+        
+        let request = request.with_session_mode(SessionMode::Only);
+        let filter = request.scope_session(None).unwrap();
+        assert!(!context.matches_filter(&filter));
+        
+        context.session_id = Some("s1".to_string());
+        assert!(context.matches_filter(&filter));
+        
+        context.session_id = Some("s2".to_string());
+        assert!(!context.matches_filter(&filter));
+    }
+    
+    #[tokio::test]
+    async fn test_stats_count_only_the_namespace() {
+        use crate::vector_db::{InMemoryVectorStore, Payload, VectorPoint};
+        
+        let mut config = crate::config::Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = InMemoryVectorStore::new(config);
+        store.create_collection("c").await.unwrap();
+        
+        let points = ["tenant-a", "tenant-b", "tenant-b"]
+            .into_iter()
+            .map(|agent| VectorPoint {
+                id: Uuid::new_v4(),
+                vector: vec![1.0, 0.0],
+                payload: Payload {
+                    text: "text".to_string(),
+                    level: ContextLevel::LongTerm,
+                    timestamp: 0,
+                    agent_id: agent.to_string(),
+                    session_id: None,
+                    metadata: HashMap::new(),
+                },
+            })
+            .collect();
+        store.insert_points("c", points).await.unwrap();
+        
+        let collections = [(ContextLevel::LongTerm, "c".to_string())];
+        let own = HiRAGStats::collect(&store, &collections, &Namespace::agent("tenant-a"), None).await.unwrap();
+        let all = HiRAGStats::collect(&store, &collections, &Namespace::All, None).await.unwrap();
+        assert_eq!(own.total_contexts, 1);
+        assert_eq!(all.total_contexts, 3);
+        assert_eq!(own.storage_size_bytes, all.storage_size_bytes / 3);
+    }
+}
@@ -1,17 +1,59 @@
 //! Context ranking and scoring
 
-use super::models::Context;
-use crate::config::RankingWeights;
+use super::access::{ACCESS_COUNT_KEY, USED_COUNT_KEY};
+use super::models::{Context, ScoreExplanation};
+use crate::config::{RankingWeights, RecencyDecay};
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Retrievals a single "used" report counts as in the frequency score
+const USED_ACCESS_WEIGHT: f32 = 5.0;
+
+/// Unweighted ranking signals of a context, each in 0.0 - 1.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RankFeatures {
+    pub similarity: f32,
+    pub recency: f32,
+    pub level: f32,
+    pub frequency: f32,
+}
+
+impl RankFeatures {
+    /// Composite score under `weights`, as computed by `ContextRanker`
+    pub fn score(&self, weights: &RankingWeights) -> f32 {
+        self.similarity * weights.similarity_weight
+            + self.recency * weights.recency_weight
+            + self.level * weights.level_weight
+            + self.frequency * weights.frequency_weight
+    }
+}
 
 /// Context ranker for scoring and ordering
+#[derive(Debug, Clone)]
 pub struct ContextRanker {
     weights: RankingWeights,
+    recency_decay: RecencyDecay,
 }
 
 impl ContextRanker {
     pub fn new(weights: RankingWeights) -> Self {
-        Self { weights }
+        Self {
+            weights,
+            recency_decay: RecencyDecay::default(),
+        }
+    }
+    
+    pub fn with_recency_decay(mut self, recency_decay: RecencyDecay) -> Self {
+        self.recency_decay = recency_decay;
+        self
+    }
+    
+    /// Copy of this ranker with a request's weight and decay overrides applied
+    pub fn with_overrides(&self, weights: Option<&RankingWeights>, recency_decay: Option<RecencyDecay>) -> Self {
+        Self {
+            weights: weights.unwrap_or(&self.weights).clone(),
+            recency_decay: recency_decay.unwrap_or(self.recency_decay),
+        }
     }
     
     /// Rank contexts based on multiple factors
@@ -19,7 +61,15 @@ impl ContextRanker {
         let current_time = Utc::now().timestamp();
         
         for context in &mut contexts {
-            context.relevance_score = self.calculate_score(context, current_time);
+            let components = self.explain_score(context, current_time);
+            context.relevance_score = components.similarity + components.recency + components.level + components.frequency;
+            
+            if let Some(explanation) = &mut context.explanation {
+                explanation.similarity = components.similarity;
+                explanation.recency = components.recency;
+                explanation.level = components.level;
+                explanation.frequency = components.frequency;
+            }
         }
         
         // Sort by relevance score (descending)
@@ -30,28 +80,63 @@ impl ContextRanker {
         contexts
     }
     
+    /// Multiply the scores of `session_id`'s contexts by `boost` and re-sort
+    pub fn boost_session(&self, mut contexts: Vec<Context>, session_id: &str, boost: f32) -> Vec<Context> {
+        for context in &mut contexts {
+            if context.session_id.as_deref() == Some(session_id) {
+                context.relevance_score *= boost;
+                if let Some(explanation) = &mut context.explanation {
+                    explanation.session_boost = Some(boost);
+                }
+            }
+        }
+        
+        contexts.sort_by(|a, b| {
+            b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        
+        contexts
+    }
+    
     /// Calculate composite score for a context
     pub fn calculate_score(&self, context: &Context, current_time: i64) -> f32 {
-        let similarity_score = context.relevance_score; // Already set from vector search
-        let recency_score = self.calculate_recency_score(context.timestamp, current_time);
-        let level_score = self.calculate_level_score(context.level);
-        let frequency_score = self.calculate_frequency_score(context);
+        let components = self.explain_score(context, current_time);
+        components.similarity + components.recency + components.level + components.frequency
+    }
+    
+    /// Weighted score components of a context, which sum to `calculate_score`
+    pub fn explain_score(&self, context: &Context, current_time: i64) -> ScoreExplanation {
+        let features = self.features(context, current_time);
         
-        similarity_score * self.weights.similarity_weight
-            + recency_score * self.weights.recency_weight
-            + level_score * self.weights.level_weight
-            + frequency_score * self.weights.frequency_weight
+        ScoreExplanation {
+            vector_score: features.similarity,
+            similarity: features.similarity * self.weights.similarity_weight,
+            recency: features.recency * self.weights.recency_weight,
+            level: features.level * self.weights.level_weight,
+            frequency: features.frequency * self.weights.frequency_weight,
+            ..ScoreExplanation::default()
+        }
+    }
+    
+    /// Unweighted score components of a context
+    pub fn features(&self, context: &Context, current_time: i64) -> RankFeatures {
+        RankFeatures {
+            similarity: context.relevance_score, // Already set from vector search
+            recency: self.calculate_recency_score(context.timestamp, current_time),
+            level: self.calculate_level_score(context.level),
+            frequency: self.calculate_frequency_score(context),
+        }
     }
     
     /// Calculate recency score (more recent = higher score)
     fn calculate_recency_score(&self, timestamp: i64, current_time: i64) -> f32 {
         let age_seconds = (current_time - timestamp).max(0) as f32;
-        let age_hours = age_seconds / 3600.0;
         
-        // Exponential decay: score = e^(-age_hours / 24)
-        // After 24 hours, score is ~0.37
-        // After 48 hours, score is ~0.14
-        (-age_hours / 24.0).exp()
+        match self.recency_decay {
+            RecencyDecay::Exponential { half_life_secs } => 0.5_f32.powf(age_seconds / half_life_secs),
+            RecencyDecay::Linear { window_secs } => (1.0 - age_seconds / window_secs).max(0.0),
+            RecencyDecay::Step { cutoff_secs } => if age_seconds <= cutoff_secs { 1.0 } else { 0.0 },
+        }
     }
     
     /// Calculate level score (L1 > L2 > L3)
@@ -65,12 +150,14 @@ impl ContextRanker {
         }
     }
     
-    /// Calculate frequency score based on access count in metadata
+    /// Calculate frequency score based on access and used counts in metadata
     fn calculate_frequency_score(&self, context: &Context) -> f32 {
-        let access_count = context.metadata
-            .get("access_count")
+        let count = |key: &str| context.metadata
+            .get(key)
             .and_then(|v| v.as_u64())
             .unwrap_or(0) as f32;
+        // A reported use says more than merely being retrieved
+        let access_count = (count(ACCESS_COUNT_KEY) + USED_ACCESS_WEIGHT * count(USED_COUNT_KEY)).min(100.0);
         
         // Logarithmic scaling: score = log(1 + access_count) / log(101)
         // Max score of 1.0 at 100 accesses
@@ -105,6 +192,26 @@ mod tests {
         assert!(score > 0.9 && score <= 1.0);
     }
     
+    #[test]
+    fn test_recency_decay_variants() {
+        let ranker = ContextRanker::new(RankingWeights::default());
+        let day = 86400;
+        
+        // Default keeps the original e^(-age_hours / 24) curve
+        assert!((ranker.calculate_recency_score(0, day) - (-1.0_f32).exp()).abs() < 1e-4);
+        
+        let ranker = ranker.with_recency_decay(RecencyDecay::Exponential { half_life_secs: 600.0 });
+        assert!((ranker.calculate_recency_score(0, 600) - 0.5).abs() < 1e-6);
+        
+        let ranker = ranker.with_overrides(None, Some(RecencyDecay::Linear { window_secs: 100.0 }));
+        assert!((ranker.calculate_recency_score(0, 25) - 0.75).abs() < 1e-6);
+        assert_eq!(ranker.calculate_recency_score(0, 200), 0.0);
+        
+        let ranker = ranker.with_overrides(None, Some(RecencyDecay::Step { cutoff_secs: 60.0 }));
+        assert_eq!(ranker.calculate_recency_score(0, 60), 1.0);
+        assert_eq!(ranker.calculate_recency_score(0, 61), 0.0);
+    }
+    
     #[test]
     fn test_level_score() {
         let weights = RankingWeights {
@@ -119,4 +226,40 @@ mod tests {
         assert_eq!(ranker.calculate_level_score(ContextLevel::ShortTerm), 0.7);
         assert_eq!(ranker.calculate_level_score(ContextLevel::LongTerm), 0.5);
     }
+    
+    #[test]
+    fn test_rank_contexts_explains_components() {
+        let ranker = ContextRanker::new(RankingWeights::default());
+        
+        let now = Utc::now().timestamp();
+        let mut context = Context::new(uuid::Uuid::new_v4(), "a".to_string(), ContextLevel::LongTerm, now, 1);
+        context.relevance_score = 0.8;
+        context.start_explanation();
+        
+        let ranked = ranker.rank_contexts(vec![context]);
+        let explanation = ranked[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.vector_score, 0.8);
+        assert!((explanation.similarity - 0.4).abs() < 1e-6);
+        assert!((explanation.level - 0.1).abs() < 1e-6);
+        assert_eq!(explanation.frequency, 0.0);
+        
+        let sum = explanation.similarity + explanation.recency + explanation.level + explanation.frequency;
+        assert!((ranked[0].relevance_score - sum).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_boost_session() {
+        let ranker = ContextRanker::new(RankingWeights::default());
+        
+        let mut other = Context::new(uuid::Uuid::new_v4(), "a".to_string(), ContextLevel::ShortTerm, 0, 1);
+        other.relevance_score = 0.6;
+        let mut own = Context::new(uuid::Uuid::new_v4(), "b".to_string(), ContextLevel::ShortTerm, 0, 1);
+        own.relevance_score = 0.5;
+        own.session_id = Some("s1".to_string());
+        
+        let ranked = ranker.boost_session(vec![other, own], "s1", 1.5);
+        assert_eq!(ranked[0].session_id.as_deref(), Some("s1"));
+        assert!((ranked[0].relevance_score - 0.75).abs() < 1e-6);
+        assert!((ranked[1].relevance_score - 0.6).abs() < 1e-6);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,350 @@
+//! Reranking stage applied to the top contexts after `ContextRanker`
+
+use super::models::{Context, RerankOptions};
+use super::sparse::tokenize;
+use crate::config::{RerankApi, RerankConfig, RerankerKind};
+use crate::error::{HiRAGError, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Scores contexts against a query
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Relevance of each context to `query` in 0.0 - 1.0, in input order
+    async fn rerank(&self, query: &str, contexts: &[Context]) -> Result<Vec<f32>>;
+}
+
+/// Fraction of the query's distinct terms that occur in the context
+#[derive(Debug, Default)]
+pub struct LexicalReranker;
+
+#[async_trait]
+impl Reranker for LexicalReranker {
+    async fn rerank(&self, query: &str, contexts: &[Context]) -> Result<Vec<f32>> {
+        let query_terms: HashSet<String> = tokenize(query).into_iter().collect();
+        if query_terms.is_empty() {
+            return Ok(vec![0.0; contexts.len()]);
+        }
+
+        Ok(contexts
+            .iter()
+            .map(|context| {
+                let terms: HashSet<String> = tokenize(&context.text).into_iter().collect();
+                query_terms.intersection(&terms).count() as f32 / query_terms.len() as f32
+            })
+            .collect())
+    }
+}
+
+/// Cross-encoder or LLM reranker behind a rerank HTTP endpoint
+pub struct HttpReranker {
+    client: Client,
+    url: String,
+    api: RerankApi,
+    model: Option<String>,
+    api_key: Option<Secret<String>>,
+}
+
+/// One scored document of a rerank response
+#[derive(Debug, Deserialize)]
+struct RerankHit {
+    index: usize,
+    #[serde(alias = "relevance_score")]
+    score: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankResults {
+    results: Vec<RerankHit>,
+}
+
+impl HttpReranker {
+    pub fn new(url: String, api: RerankApi) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            api,
+            model: None,
+            api_key: None,
+        }
+    }
+
+    /// Build from config; `None` without a `reranking.url`
+    pub fn from_config(config: &RerankConfig) -> Option<Self> {
+        let url = config.url.clone()?;
+        Some(Self {
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            ..Self::new(url, config.api)
+        })
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: Secret<String>) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+}
+
+#[async_trait]
+impl Reranker for HttpReranker {
+    async fn rerank(&self, query: &str, contexts: &[Context]) -> Result<Vec<f32>> {
+        let documents: Vec<&str> = contexts.iter().map(|c| c.text.as_str()).collect();
+        let body = match self.api {
+            RerankApi::OpenAi => serde_json::json!({
+                "model": self.model,
+                "query": query,
+                "documents": documents,
+                "top_n": documents.len(),
+            }),
+            RerankApi::Tei => serde_json::json!({
+                "query": query,
+                "texts": documents,
+            }),
+        };
+
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key.expose_secret());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HiRAGError::RankingError(format!("Rerank request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HiRAGError::RankingError(format!("Rerank API error {}: {}", status, error_text)).into());
+        }
+
+        let hits = match self.api {
+            RerankApi::OpenAi => response.json::<RerankResults>().await.map(|r| r.results),
+            RerankApi::Tei => response.json::<Vec<RerankHit>>().await,
+        }
+        .map_err(|e| HiRAGError::RankingError(format!("Failed to parse rerank response: {}", e)))?;
+
+        // Documents the endpoint left out score 0.0
+        let mut scores = vec![0.0; contexts.len()];
+        for hit in hits {
+            if let Some(score) = scores.get_mut(hit.index) {
+                *score = hit.score;
+            }
+        }
+        Ok(scores)
+    }
+}
+
+/// Runs the reranker picked by a request over its top ranked contexts.
+///
+/// The top `top_n` contexts are reordered by reranker score, which replaces
+/// their `relevance_score`; the rest keep their heuristic order behind them.
+/// Errors and timeouts leave the heuristic ranking untouched.
+#[derive(Clone)]
+pub struct RerankStage {
+    config: RerankConfig,
+    rerankers: HashMap<RerankerKind, Arc<dyn Reranker>>,
+}
+
+impl RerankStage {
+    pub fn new(config: RerankConfig) -> Self {
+        let mut rerankers: HashMap<RerankerKind, Arc<dyn Reranker>> = HashMap::new();
+        rerankers.insert(RerankerKind::Lexical, Arc::new(LexicalReranker));
+        if let Some(reranker) = HttpReranker::from_config(&config) {
+            rerankers.insert(RerankerKind::CrossEncoder, Arc::new(reranker));
+        }
+
+        Self { config, rerankers }
+    }
+
+    /// Register or replace the reranker used for `kind`
+    pub fn with_reranker(mut self, kind: RerankerKind, reranker: Arc<dyn Reranker>) -> Self {
+        self.rerankers.insert(kind, reranker);
+        self
+    }
+
+    /// Rerank `contexts` (already ranked best first) for `query`
+    pub async fn apply(&self, query: &str, mut contexts: Vec<Context>, options: Option<&RerankOptions>) -> Vec<Context> {
+        let kind = options.map(|o| o.reranker).unwrap_or(self.config.default_reranker);
+        if kind == RerankerKind::None || contexts.is_empty() {
+            return contexts;
+        }
+
+        let Some(reranker) = self.rerankers.get(&kind) else {
+            warn!("Reranker {:?} is not configured, keeping heuristic ranking", kind);
+            return contexts;
+        };
+
+        // Requests may lower the configured limits but never raise them
+        let top_n = options
+            .and_then(|o| o.top_n)
+            .map_or(self.config.top_n, |n| n.min(self.config.top_n))
+            .min(contexts.len());
+        let timeout_ms = options
+            .and_then(|o| o.timeout_ms)
+            .map_or(self.config.timeout_ms, |ms| ms.min(self.config.timeout_ms));
+        let timeout = Duration::from_millis(timeout_ms);
+
+        let scores = match tokio::time::timeout(timeout, reranker.rerank(query, &contexts[..top_n])).await {
+            Ok(Ok(scores)) if scores.len() == top_n => scores,
+            Ok(Ok(scores)) => {
+                warn!(
+                    "Reranker {:?} returned {} scores for {} contexts, keeping heuristic ranking",
+                    kind,
+                    scores.len(),
+                    top_n
+                );
+                return contexts;
+            }
+            Ok(Err(e)) => {
+                warn!("Reranker {:?} failed, keeping heuristic ranking: {}", kind, e);
+                return contexts;
+            }
+            Err(_) => {
+                warn!("Reranker {:?} timed out after {:?}, keeping heuristic ranking", kind, timeout);
+                return contexts;
+            }
+        };
+
+        for (context, score) in contexts.iter_mut().zip(scores) {
+            context.relevance_score = score;
+            if let Some(explanation) = &mut context.explanation {
+                explanation.rerank_score = Some(score);
+            }
+        }
+        // Stable sort keeps the heuristic order between equal scores
+        contexts[..top_n].sort_by(|a, b| {
+            b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        debug!("Reranked top {} contexts with {:?}", top_n, kind);
+        contexts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::ContextLevel;
+    use uuid::Uuid;
+
+    fn contexts(texts: &[&str]) -> Vec<Context> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let mut context = Context::new(Uuid::new_v4(), text.to_string(), ContextLevel::ShortTerm, 0, 1);
+                context.relevance_score = 1.0 - i as f32 * 0.1;
+                context
+            })
+            .collect()
+    }
+
+    fn options(reranker: RerankerKind) -> RerankOptions {
+        RerankOptions {
+            reranker,
+            top_n: None,
+            timeout_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lexical_reranker_reorders_top_n() {
+        let stage = RerankStage::new(RerankConfig::default());
+        let input = contexts(&["deployment notes", "rust borrow checker errors", "rust release"]);
+
+        let output = stage.apply("rust borrow errors", input, Some(&options(RerankerKind::Lexical))).await;
+        assert_eq!(output[0].text, "rust borrow checker errors");
+        assert_eq!(output[0].relevance_score, 1.0);
+        assert_eq!(output[2].text, "deployment notes");
+    }
+
+    #[tokio::test]
+    async fn test_http_reranker_parses_both_dialects() {
+        let mut server = mockito::Server::new_async().await;
+        let openai = server
+            .mock("POST", "/v1/rerank")
+            .with_body(r#"{"results": [{"index": 1, "relevance_score": 0.9}, {"index": 0, "relevance_score": 0.2}]}"#)
+            .create_async()
+            .await;
+        let tei = server
+            .mock("POST", "/rerank")
+            .with_body(r#"[{"index": 0, "score": 0.7}, {"index": 1, "score": 0.1}]"#)
+            .create_async()
+            .await;
+
+        let input = contexts(&["a", "b"]);
+        let reranker = HttpReranker::new(format!("{}/v1/rerank", server.url()), RerankApi::OpenAi);
+        assert_eq!(reranker.rerank("q", &input).await.unwrap(), vec![0.2, 0.9]);
+
+        let reranker = HttpReranker::new(format!("{}/rerank", server.url()), RerankApi::Tei);
+        assert_eq!(reranker.rerank("q", &input).await.unwrap(), vec![0.7, 0.1]);
+
+        openai.assert_async().await;
+        tei.assert_async().await;
+    }
+
+    struct SlowReranker;
+
+    #[async_trait]
+    impl Reranker for SlowReranker {
+        async fn rerank(&self, _query: &str, contexts: &[Context]) -> Result<Vec<f32>> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(vec![1.0; contexts.len()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_falls_back_to_heuristic_order() {
+        let stage = RerankStage::new(RerankConfig::default())
+            .with_reranker(RerankerKind::CrossEncoder, Arc::new(SlowReranker));
+        let input = contexts(&["a", "b"]);
+        let ids: Vec<_> = input.iter().map(|c| c.id).collect();
+
+        let mut options = options(RerankerKind::CrossEncoder);
+        options.timeout_ms = Some(10);
+        let output = stage.apply("q", input, Some(&options)).await;
+
+        assert_eq!(output.iter().map(|c| c.id).collect::<Vec<_>>(), ids);
+        assert_eq!(output[1].relevance_score, 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_request_cannot_raise_configured_limits() {
+        let config = RerankConfig {
+            top_n: 1,
+            timeout_ms: 10,
+            ..RerankConfig::default()
+        };
+        let stage = RerankStage::new(config).with_reranker(RerankerKind::CrossEncoder, Arc::new(SlowReranker));
+        let input = contexts(&["a", "b"]);
+
+        let mut slow = options(RerankerKind::CrossEncoder);
+        slow.timeout_ms = Some(60_000);
+        let started = std::time::Instant::now();
+        stage.apply("q", input, Some(&slow)).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let input = contexts(&["deployment notes", "rust errors", "rust release"]);
+        let mut lexical = options(RerankerKind::Lexical);
+        lexical.top_n = Some(usize::MAX);
+        let output = RerankStage::new(RerankConfig { top_n: 1, ..RerankConfig::default() })
+            .apply("rust", input, Some(&lexical))
+            .await;
+        // Only the first context was reranked, so the better lexical match stays second
+        assert_eq!(output[0].text, "deployment notes");
+        assert_eq!(output[0].relevance_score, 0.0);
+        assert_eq!(output[1].text, "rust errors");
+    }
+}
@@ -1,12 +1,43 @@
 //! Context retrieval logic for different levels
 
 use super::models::*;
+use super::sparse::SparseIndex;
 use super::token_estimator::TokenEstimator;
-use crate::config::RetrievalStrategy;
+use crate::config::{Distance, FusionMethod, RetrievalStrategy};
 use crate::error::Result;
-use crate::vector_db::{SearchParams, VectorStore};
+use crate::vector_db::{get_points, Condition, ContextLevel, Filter, Payload, SearchParams, SearchResult, VectorStore};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Candidates of one level that passed the relevance threshold
+#[derive(Debug, Default)]
+pub struct LevelCandidates {
+    /// Remaining candidates, best first
+    pub contexts: Vec<Context>,
+    /// Candidates dropped for scoring below the threshold
+    pub below_threshold: usize,
+}
+
+/// Level candidates fitted into a token budget
+#[derive(Debug, Default)]
+pub struct PackedContexts {
+    /// Selected contexts
+    pub contexts: Vec<Context>,
+    /// Candidates that did not fit the budget
+    pub over_budget: Vec<Context>,
+    /// Candidates dropped as repeats of an earlier level's
+    pub duplicates: usize,
+}
+
+/// A search hit before it is turned into a [`Context`]
+struct Candidate {
+    id: Uuid,
+    payload: Payload,
+    score: f32,
+    channels: Vec<RetrievalChannel>,
+}
 
 /// Context retriever for hierarchical retrieval
 #[derive(Clone)]
@@ -14,6 +45,9 @@ pub struct ContextRetriever {
     vector_db: Arc<dyn VectorStore>,
     token_estimator: TokenEstimator,
     strategy: RetrievalStrategy,
+    sparse_index: Option<Arc<SparseIndex>>,
+    relevance_threshold: f32,
+    distance: Distance,
 }
 
 impl ContextRetriever {
@@ -22,63 +56,443 @@ impl ContextRetriever {
         token_estimator: TokenEstimator,
         strategy: RetrievalStrategy,
     ) -> Self {
+        let sparse_index = strategy.hybrid_enabled.then(|| Arc::new(SparseIndex::new()));
+        
         Self {
             vector_db,
             token_estimator,
             strategy,
+            sparse_index,
+            relevance_threshold: 0.0,
+            distance: Distance::default(),
+        }
+    }
+    
+    /// Drop dense hits less similar than `threshold` (beyond the per-level minimum)
+    pub fn with_relevance_threshold(mut self, threshold: f32) -> Self {
+        self.relevance_threshold = threshold;
+        self
+    }
+    
+    /// Distance metric of the vector store, used to turn dense scores into similarities
+    pub fn with_distance(mut self, distance: Distance) -> Self {
+        self.distance = distance;
+        self
+    }
+    
+    /// The BM25 index, when hybrid retrieval is enabled
+    pub fn sparse_index(&self) -> Option<Arc<SparseIndex>> {
+        self.sparse_index.clone()
+    }
+    
+    /// Add a stored context to the sparse index (no-op unless hybrid retrieval is enabled)
+    pub fn index_context(&self, collection: &str, id: Uuid, text: &str) {
+        if let Some(index) = &self.sparse_index {
+            index.insert(collection, id, text);
+        }
+    }
+    
+    /// Remove a context from the sparse index
+    pub fn unindex_context(&self, collection: &str, id: Uuid) {
+        if let Some(index) = &self.sparse_index {
+            index.remove(collection, id);
+        }
+    }
+    
+    /// Drop a whole collection from the sparse index
+    pub fn clear_index(&self, collection: &str) {
+        if let Some(index) = &self.sparse_index {
+            index.clear(collection);
         }
     }
     
-    /// Retrieve contexts from a specific level
-    pub async fn retrieve_from_level(
+    /// Rebuild the sparse index of a collection from the points already stored
+    pub async fn rebuild_index(&self, collection: &str) -> Result<()> {
+        let Some(index) = &self.sparse_index else {
+            return Ok(());
+        };
+        
+        index.clear(collection);
+        let mut cursor = None;
+        loop {
+            let page = self.vector_db.scroll(collection, None, 256, cursor).await?;
+            for point in &page.points {
+                index.insert(collection, point.id, &point.payload.text);
+            }
+            
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        
+        info!("Sparse index for {} rebuilt with {} contexts", collection, index.len(collection));
+        Ok(())
+    }
+    
+    /// Delete every context of a collection matching `filter` and drop it
+    /// from the sparse index. Returns the number of contexts deleted.
+    pub async fn delete_matching(&self, collection: &str, filter: Filter) -> Result<usize> {
+        let mut deleted = 0;
+        let mut cursor = None;
+        loop {
+            let page = self.vector_db.scroll(collection, Some(filter.clone()), 256, cursor).await?;
+            let ids: Vec<Uuid> = page.points.iter().map(|p| p.id).collect();
+            
+            if !ids.is_empty() {
+                self.vector_db.delete_points(collection, ids.clone()).await?;
+                for id in ids {
+                    self.unindex_context(collection, id);
+                    deleted += 1;
+                }
+            }
+            
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        
+        debug!("Deleted {} matching contexts from {}", deleted, collection);
+        Ok(deleted)
+    }
+    
+    /// Search one level and apply the relevance threshold.
+    ///
+    /// Dense scores are turned into similarities and thresholded before
+    /// fusion, so the threshold means the same for every distance metric and
+    /// never drops lexical hits. It is applied here rather than passed to the
+    /// store so the best `min_contexts_per_level` dense hits survive even when
+    /// they score below it, and so the dropped candidates can be counted.
+    pub async fn search_level(
         &self,
         collection: &str,
+        query: &str,
         query_vector: Vec<f32>,
-        max_tokens: usize,
-        filters: Option<crate::vector_db::Filter>,
+        filters: Option<Filter>,
+    ) -> Result<LevelCandidates> {
+        debug!("Searching level: {}", collection);
+        
+        // Search with generous limit, token budgets are applied by `pack_levels`
+        self.retrieve(collection, query, query_vector, filters, self.strategy.search_limit, true).await
+    }
+    
+    /// Score the stored contexts among `ids` for a query as `search_level`
+    /// would, without the relevance threshold. Missing IDs are left out.
+    pub async fn score_contexts(
+        &self,
+        collection: &str,
+        query: &str,
+        query_vector: Vec<f32>,
+        ids: Vec<Uuid>,
+        filters: Option<Filter>,
     ) -> Result<Vec<Context>> {
-        debug!("Retrieving from level: {} with max_tokens: {}", collection, max_tokens);
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
         
-        // Search with generous limit, we'll filter by tokens later
+        let limit = ids.len();
+        let filter = filters.unwrap_or_default().must(Condition::HasId { ids });
+        let candidates = self.retrieve(collection, query, query_vector, Some(filter), limit, false).await?;
+        Ok(candidates.contexts)
+    }
+    
+    /// Dense search (thresholded if `threshold` is set) fused with sparse hits
+    async fn retrieve(
+        &self,
+        collection: &str,
+        query: &str,
+        query_vector: Vec<f32>,
+        filters: Option<Filter>,
+        limit: usize,
+        threshold: bool,
+    ) -> Result<LevelCandidates> {
         let search_params = SearchParams {
             vector: query_vector,
-            limit: 100,
+            limit,
             score_threshold: None,
-            filter: filters,
+            filter: filters.clone(),
             with_payload: true,
             with_vector: false,
         };
         
-        let results = self.vector_db.search(collection, search_params).await?;
+        let mut results = self.vector_db.search(collection, search_params).await?;
+        for result in &mut results {
+            result.score = self.similarity(result.score);
+        }
+        let dropped = if threshold { self.apply_threshold(&mut results) } else { Vec::new() };
+        
+        let candidates: Vec<Candidate> = match &self.sparse_index {
+            Some(index) => {
+                let sparse_hits = self.sparse_search(index, collection, query, filters.as_ref()).await?;
+                self.fuse(results, sparse_hits)
+            }
+            None => results
+                .into_iter()
+                .filter_map(|result| {
+                    result.payload.map(|payload| Candidate {
+                        id: result.id,
+                        payload,
+                        score: result.score,
+                        channels: vec![RetrievalChannel::Dense],
+                    })
+                })
+                .collect(),
+        };
         
-        // Convert to Context objects and filter by token budget
-        let mut contexts = Vec::new();
-        let mut total_tokens = 0;
+        // Dense hits below the threshold may still have come back through the sparse channel
+        let kept: HashSet<Uuid> = candidates.iter().map(|c| c.id).collect();
+        let below_threshold = dropped.iter().filter(|id| !kept.contains(id)).count();
         
-        for result in results {
-            if let Some(payload) = result.payload {
-                let token_count = self.token_estimator.estimate(&payload.text);
+        let contexts = candidates
+            .into_iter()
+            .map(|candidate| {
+                let payload = candidate.payload;
+                Context {
+                    id: candidate.id,
+                    token_count: self.token_estimator.estimate(&payload.text),
+                    text: payload.text,
+                    level: payload.level,
+                    relevance_score: candidate.score,
+                    timestamp: payload.timestamp,
+                    metadata: payload.metadata,
+                    agent_id: payload.agent_id,
+                    session_id: payload.session_id,
+                    channels: candidate.channels,
+                    explanation: None,
+                }
+            })
+            .collect();
+        
+        let candidates = LevelCandidates { contexts, below_threshold };
+        debug!(
+            "Level {} kept {} candidates, {} below threshold",
+            collection,
+            candidates.contexts.len(),
+            candidates.below_threshold
+        );
+        Ok(candidates)
+    }
+    
+    /// A dense score as a similarity, higher is better. Euclidean distances
+    /// map to `1 / (1 + d)`; cosine and dot scores are already similarities.
+    fn similarity(&self, score: f32) -> f32 {
+        match self.distance {
+            Distance::Cosine | Distance::Dot => score,
+            Distance::Euclidean => 1.0 / (1.0 + score.max(0.0)),
+        }
+    }
+    
+    /// Remove dense hits less similar than the relevance threshold, always
+    /// keeping the best `min_contexts_per_level`, and return the removed IDs.
+    /// `results` must be sorted best first and hold similarities.
+    pub fn apply_threshold(&self, results: &mut Vec<SearchResult>) -> Vec<Uuid> {
+        let keep = results
+            .iter()
+            .enumerate()
+            .take_while(|(i, r)| *i < self.strategy.min_contexts_per_level || r.score >= self.relevance_threshold)
+            .count();
+        
+        results.drain(keep..).map(|r| r.id).collect()
+    }
+    
+    /// Fit per-level candidates (each sorted best first) into `max_tokens`.
+    ///
+    /// Every level first gets its `min_contexts_per_level` best candidates,
+    /// then fills its share from `calculate_dynamic_allocations`, so levels
+    /// without candidates give their share away. Tokens a level leaves unused
+    /// go to the best remaining candidates of any level. A context surfaced by
+    /// several levels is only kept the first time. Explained contexts record
+    /// their level budget and whether they were cut.
+    pub fn pack_levels(
+        &self,
+        max_tokens: usize,
+        levels: Vec<(ContextLevel, Vec<Context>)>,
+    ) -> PackedContexts {
+        let available = |level: ContextLevel| {
+            levels.iter().filter(|(l, _)| *l == level).map(|(_, c)| c.len()).sum::<usize>()
+        };
+        let (l1_tokens, l2_tokens, l3_tokens) = self.calculate_dynamic_allocations(
+            max_tokens,
+            available(ContextLevel::Immediate),
+            available(ContextLevel::ShortTerm),
+            available(ContextLevel::LongTerm),
+        );
+        
+        let mut used = 0;
+        let mut selected = Vec::new();
+        let mut remaining = Vec::new();
+        let mut over_budget = Vec::new();
+        let mut duplicates = 0;
+        let mut seen = HashSet::new();
+        
+        for (level, contexts) in levels {
+            let mut budget = match level {
+                ContextLevel::Immediate => l1_tokens,
+                ContextLevel::ShortTerm => l2_tokens,
+                ContextLevel::LongTerm => l3_tokens,
+            };
+            let level_budget = budget;
+            let mut taken = 0;
+            
+            for mut context in contexts {
+                if !seen.insert(context.id) {
+                    duplicates += 1;
+                    continue;
+                }
                 
-                if total_tokens + token_count <= max_tokens {
-                    contexts.push(Context {
-                        id: result.id,
-                        text: payload.text,
-                        level: payload.level,
-                        relevance_score: result.score,
-                        token_count,
-                        timestamp: payload.timestamp,
-                        metadata: payload.metadata,
-                    });
-                    
-                    total_tokens += token_count;
+                if let Some(explanation) = &mut context.explanation {
+                    explanation.level_budget = level_budget;
+                }
+                
+                let fits_request = used + context.token_count <= max_tokens;
+                let guaranteed = taken < self.strategy.min_contexts_per_level;
+                
+                if fits_request && (guaranteed || context.token_count <= budget) {
+                    used += context.token_count;
+                    budget = budget.saturating_sub(context.token_count);
+                    taken += 1;
+                    selected.push(context);
                 } else {
-                    break;
+                    remaining.push(context);
                 }
             }
         }
         
-        debug!("Retrieved {} contexts with {} tokens", contexts.len(), total_tokens);
-        Ok(contexts)
+        // Hand budget left unused by some levels to the best leftovers
+        remaining.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
+        for mut context in remaining {
+            if used + context.token_count <= max_tokens {
+                used += context.token_count;
+                selected.push(context);
+            } else {
+                if let Some(explanation) = &mut context.explanation {
+                    explanation.cut_by_token_limit = true;
+                }
+                over_budget.push(context);
+            }
+        }
+        
+        debug!(
+            "Packed {} contexts with {} tokens, {} over budget, {} duplicates",
+            selected.len(),
+            used,
+            over_budget.len(),
+            duplicates
+        );
+        PackedContexts {
+            contexts: selected,
+            over_budget,
+            duplicates,
+        }
+    }
+    
+    /// Up to `search_limit` BM25 hits that pass `filter`, best first, with their payloads.
+    ///
+    /// The index holds every namespace's contexts, so ranked hits are read in
+    /// pages until enough of them pass the filter; other tenants' documents
+    /// never crowd out the caller's.
+    async fn sparse_search(
+        &self,
+        index: &SparseIndex,
+        collection: &str,
+        query: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<(Uuid, f32, Payload)>> {
+        let limit = self.strategy.search_limit;
+        let ranked = index.search(collection, query, usize::MAX);
+        
+        let mut hits = Vec::new();
+        for page in ranked.chunks(limit.max(1)) {
+            let ids = page.iter().map(|(id, _)| *id).collect();
+            let mut payloads: HashMap<Uuid, Payload> = get_points(self.vector_db.as_ref(), collection, ids, filter)
+                .await?
+                .into_iter()
+                .map(|point| (point.id, point.payload))
+                .collect();
+            hits.extend(
+                page.iter()
+                    .filter_map(|(id, score)| payloads.remove(id).map(|payload| (*id, *score, payload))),
+            );
+            if hits.len() >= limit {
+                break;
+            }
+        }
+        
+        hits.truncate(limit);
+        Ok(hits)
+    }
+    
+    /// Merge dense and sparse hits into one list ordered by fused score.
+    ///
+    /// Fused scores are scaled to 0.0 - 1.0 so `ContextRanker` can keep
+    /// treating them as similarities. With weighted fusion, dot product
+    /// scores are divided by the best one first since they are unbounded.
+    fn fuse(&self, dense: Vec<SearchResult>, sparse: Vec<(Uuid, f32, Payload)>) -> Vec<Candidate> {
+        let dense_weight = self.strategy.dense_weight;
+        let sparse_weight = self.strategy.sparse_weight;
+        let total_weight = (dense_weight + sparse_weight).max(f32::EPSILON);
+        let k = self.strategy.rrf_k;
+        let max_dense = dense.first().map(|r| r.score).unwrap_or(0.0).max(f32::EPSILON);
+        let max_sparse = sparse.first().map(|(_, score, _)| *score).unwrap_or(0.0).max(f32::EPSILON);
+        
+        let mut fused: HashMap<Uuid, Candidate> = HashMap::new();
+        
+        for (rank, result) in dense.into_iter().enumerate() {
+            let Some(payload) = result.payload else {
+                continue;
+            };
+            
+            let score = match self.strategy.fusion {
+                FusionMethod::Rrf => dense_weight * (k + 1.0) / (k + rank as f32 + 1.0),
+                FusionMethod::Weighted => {
+                    let similarity = match self.distance {
+                        Distance::Dot => result.score / max_dense,
+                        Distance::Cosine | Distance::Euclidean => result.score,
+                    };
+                    dense_weight * similarity.clamp(0.0, 1.0)
+                }
+            };
+            fused.insert(result.id, Candidate {
+                id: result.id,
+                payload,
+                score,
+                channels: vec![RetrievalChannel::Dense],
+            });
+        }
+        
+        for (rank, (id, bm25, payload)) in sparse.into_iter().enumerate() {
+            let score = match self.strategy.fusion {
+                FusionMethod::Rrf => sparse_weight * (k + 1.0) / (k + rank as f32 + 1.0),
+                FusionMethod::Weighted => sparse_weight * bm25 / max_sparse,
+            };
+            
+            match fused.get_mut(&id) {
+                Some(candidate) => {
+                    candidate.score += score;
+                    candidate.channels.push(RetrievalChannel::Sparse);
+                }
+                None => {
+                    fused.insert(id, Candidate {
+                        id,
+                        payload,
+                        score,
+                        channels: vec![RetrievalChannel::Sparse],
+                    });
+                }
+            }
+        }
+        
+        let mut candidates: Vec<_> = fused
+            .into_values()
+            .map(|mut candidate| {
+                candidate.score /= total_weight;
+                candidate
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        
+        candidates
     }
     
     /// Calculate token allocation for each level
@@ -142,4 +556,269 @@ impl ContextRetriever {
         
         (l1_tokens, l2_tokens, l3_tokens)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, TokenEstimator as EstimatorConfig};
+    use crate::vector_db::{InMemoryVectorStore, VectorPoint};
+
+    fn point(vector: Vec<f32>, text: &str) -> VectorPoint {
+        VectorPoint {
+            id: Uuid::new_v4(),
+            vector,
+            payload: Payload {
+                text: text.to_string(),
+                level: ContextLevel::ShortTerm,
+                timestamp: 0,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata: HashMap::new(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_surfaces_exact_identifier() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let near = point(vec![1.0, 0.0], "general notes on compiler errors");
+        let exact = point(vec![0.0, 1.0], "fixed E0308 in retriever");
+        store.insert_points("c", vec![near.clone(), exact.clone()]).await.unwrap();
+
+        let strategy = RetrievalStrategy {
+            hybrid_enabled: true,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever = ContextRetriever::new(store.clone(), estimator, strategy);
+        retriever.rebuild_index("c").await.unwrap();
+
+        let contexts = retriever
+            .search_level("c", "E0308", vec![1.0, 0.0], None)
+            .await
+            .unwrap()
+            .contexts;
+
+        assert_eq!(contexts.len(), 2);
+        let hit = contexts.iter().find(|c| c.id == exact.id).unwrap();
+        assert_eq!(hit.channels, vec![RetrievalChannel::Dense, RetrievalChannel::Sparse]);
+        assert_eq!(contexts[0].id, exact.id);
+        assert!(contexts[0].relevance_score <= 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_lexical_only_hits_respect_filter() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let mut points = vec![
+            point(vec![1.0, 0.0], "alpha notes"),
+            point(vec![1.0, 0.1], "beta notes"),
+            point(vec![0.0, 1.0], "fixed E0308 in retriever"),
+            point(vec![0.0, 1.0], "E0308 in another session"),
+        ];
+        for p in &mut points[..3] {
+            p.payload.session_id = Some("s1".to_string());
+        }
+        store.insert_points("c", points.clone()).await.unwrap();
+
+        let strategy = RetrievalStrategy {
+            hybrid_enabled: true,
+            search_limit: 2,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever = ContextRetriever::new(store.clone(), estimator, strategy);
+        retriever.rebuild_index("c").await.unwrap();
+
+        let filter = Filter::new().must(Condition::Match {
+            key: "session_id".to_string(),
+            value: serde_json::json!("s1"),
+        });
+        let contexts = retriever
+            .search_level("c", "E0308", vec![1.0, 0.0], Some(filter))
+            .await
+            .unwrap()
+            .contexts;
+
+        assert_eq!(contexts.len(), 3);
+        let lexical = contexts.iter().find(|c| c.id == points[2].id).unwrap();
+        assert_eq!(lexical.channels, vec![RetrievalChannel::Sparse]);
+        assert!(contexts.iter().all(|c| c.id != points[3].id));
+    }
+
+    #[tokio::test]
+    async fn test_sparse_hits_are_not_crowded_out_by_filtered_documents() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        // Other sessions' shorter documents outrank the caller's on BM25
+        let mut points: Vec<_> = (0..4).map(|_| point(vec![1.0, 0.0], "E0308")).collect();
+        let mut own = point(vec![0.0, 1.0], "fixed E0308 in retriever");
+        own.payload.session_id = Some("s1".to_string());
+        points.push(own.clone());
+        store.insert_points("c", points).await.unwrap();
+
+        let strategy = RetrievalStrategy {
+            hybrid_enabled: true,
+            search_limit: 2,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever = ContextRetriever::new(store.clone(), estimator, strategy);
+        retriever.rebuild_index("c").await.unwrap();
+
+        let filter = Filter::new().must(Condition::Match {
+            key: "session_id".to_string(),
+            value: serde_json::json!("s1"),
+        });
+        let contexts = retriever
+            .search_level("c", "E0308", vec![1.0, 0.0], Some(filter))
+            .await
+            .unwrap()
+            .contexts;
+
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].id, own.id);
+        assert!(contexts[0].channels.contains(&RetrievalChannel::Sparse));
+    }
+
+    #[test]
+    fn test_weighted_fusion_normalizes_dot_scores() {
+        let store = Arc::new(InMemoryVectorStore::new(Config::default_config().vector_db));
+        let strategy = RetrievalStrategy {
+            fusion: FusionMethod::Weighted,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever = ContextRetriever::new(store, estimator, strategy).with_distance(Distance::Dot);
+
+        let dense = [12.0, 3.0]
+            .into_iter()
+            .map(|score| SearchResult {
+                score,
+                payload: Some(point(vec![1.0, 0.0], "text").payload),
+                ..hit(score)
+            })
+            .collect();
+        let candidates = retriever.fuse(dense, Vec::new());
+
+        assert!(candidates.iter().all(|c| (0.0..=1.0).contains(&c.score)));
+        assert!(candidates[0].score > candidates[1].score);
+    }
+
+    fn context(level: ContextLevel, score: f32, tokens: usize) -> Context {
+        let mut context = Context::new(Uuid::new_v4(), "text".to_string(), level, 0, tokens);
+        context.relevance_score = score;
+        context
+    }
+
+    fn retriever(min_contexts_per_level: usize) -> ContextRetriever {
+        let store = Arc::new(InMemoryVectorStore::new(Config::default_config().vector_db));
+        let strategy = RetrievalStrategy {
+            min_contexts_per_level,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        ContextRetriever::new(store, estimator, strategy).with_relevance_threshold(0.7)
+    }
+
+    fn hit(score: f32) -> SearchResult {
+        SearchResult { id: Uuid::new_v4(), score, payload: None, vector: None }
+    }
+
+    #[test]
+    fn test_threshold_keeps_minimum_per_level() {
+        let retriever = retriever(1);
+
+        let mut low = vec![hit(0.5), hit(0.4)];
+        let dropped = retriever.apply_threshold(&mut low);
+        assert_eq!(low.len(), 1);
+        assert_eq!(dropped.len(), 1);
+
+        let mut high = vec![hit(0.9), hit(0.8)];
+        assert!(retriever.apply_threshold(&mut high).is_empty());
+        assert_eq!(high.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_threshold_uses_dense_similarity() {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 2;
+        config.distance = Distance::Euclidean;
+        let store = Arc::new(InMemoryVectorStore::new(config));
+        store.create_collection("c").await.unwrap();
+
+        let near = point(vec![1.0, 0.0], "general notes on compiler errors");
+        let far = point(vec![0.0, 1.0], "unrelated notes");
+        let exact = point(vec![-1.0, 0.0], "fixed E0308 in retriever");
+        store.insert_points("c", vec![near.clone(), far.clone(), exact.clone()]).await.unwrap();
+
+        let strategy = RetrievalStrategy {
+            hybrid_enabled: true,
+            min_contexts_per_level: 0,
+            ..RetrievalStrategy::default()
+        };
+        let estimator = TokenEstimator::new(EstimatorConfig::CharacterBased { chars_per_token: 4.0 });
+        let retriever = ContextRetriever::new(store.clone(), estimator, strategy)
+            .with_relevance_threshold(0.7)
+            .with_distance(Distance::Euclidean);
+        retriever.rebuild_index("c").await.unwrap();
+
+        let candidates = retriever.search_level("c", "E0308", vec![1.0, 0.0], None).await.unwrap();
+
+        // The closest point passes as a similarity of 1.0 instead of being cut as a distance of 0.0
+        assert_eq!(candidates.contexts.len(), 2);
+        assert!(candidates.contexts.iter().any(|c| c.id == near.id));
+        // A strong BM25-only hit survives even though its vector is the farthest away
+        let lexical = candidates.contexts.iter().find(|c| c.id == exact.id).unwrap();
+        assert_eq!(lexical.channels, vec![RetrievalChannel::Sparse]);
+        assert_eq!(candidates.below_threshold, 1);
+    }
+
+    #[test]
+    fn test_pack_levels_redistributes_unused_budget() {
+        let retriever = retriever(1);
+
+        // L2 gets the whole budget when the other levels are empty
+        let l2: Vec<_> = (0..10).map(|_| context(ContextLevel::ShortTerm, 0.9, 10)).collect();
+        let packed = retriever.pack_levels(100, vec![(ContextLevel::ShortTerm, l2)]);
+        assert_eq!(packed.contexts.len(), 10);
+        assert!(packed.over_budget.is_empty());
+
+        // A large L3 context is still guaranteed a slot, and L2 takes what L1 leaves
+        let mut l1 = vec![context(ContextLevel::Immediate, 1.0, 5)];
+        let mut l2: Vec<_> = (0..10).map(|_| context(ContextLevel::ShortTerm, 0.9, 10)).collect();
+        l1.iter_mut().chain(l2.iter_mut()).for_each(Context::start_explanation);
+        let l3 = vec![context(ContextLevel::LongTerm, 0.8, 40)];
+        let duplicate = l2[0].clone();
+        let packed = retriever.pack_levels(
+            100,
+            vec![
+                (ContextLevel::Immediate, l1),
+                (ContextLevel::ShortTerm, l2),
+                (ContextLevel::LongTerm, l3),
+                (ContextLevel::LongTerm, vec![duplicate]),
+            ],
+        );
+        let selected = &packed.contexts;
+        assert!(selected.iter().any(|c| c.level == ContextLevel::LongTerm));
+        assert_eq!(selected.iter().filter(|c| c.level == ContextLevel::ShortTerm).count(), 5);
+        assert_eq!(packed.over_budget.len(), 5);
+        assert_eq!(packed.duplicates, 1);
+        assert!(selected.iter().map(|c| c.token_count).sum::<usize>() <= 100);
+        
+        let explained = selected.iter().find(|c| c.level == ContextLevel::Immediate).unwrap();
+        assert_eq!(explained.explanation.as_ref().unwrap().level_budget, 30);
+        assert!(packed.over_budget[0].explanation.as_ref().unwrap().cut_by_token_limit);
+    }
+}
@@ -0,0 +1,198 @@
+//! In-process BM25 index over context text for lexical retrieval
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// BM25 term frequency saturation
+const K1: f32 = 1.2;
+
+/// BM25 document length normalization
+const B: f32 = 0.75;
+
+/// Inverted index of a single collection
+#[derive(Debug, Default)]
+struct CollectionIndex {
+    /// Term -> (document -> term frequency)
+    postings: HashMap<String, HashMap<Uuid, u32>>,
+    /// Document -> (terms, length in tokens)
+    docs: HashMap<Uuid, (Vec<String>, usize)>,
+    total_len: usize,
+}
+
+impl CollectionIndex {
+    fn insert(&mut self, id: Uuid, text: &str) {
+        self.remove(id);
+
+        let tokens = tokenize(text);
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *frequencies.entry(token.clone()).or_insert(0) += 1;
+        }
+
+        let terms = frequencies.keys().cloned().collect();
+        for (term, tf) in frequencies {
+            self.postings.entry(term).or_default().insert(id, tf);
+        }
+
+        self.total_len += tokens.len();
+        self.docs.insert(id, (terms, tokens.len()));
+    }
+
+    fn remove(&mut self, id: Uuid) {
+        let Some((terms, len)) = self.docs.remove(&id) else {
+            return;
+        };
+
+        for term in terms {
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        self.total_len -= len;
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<(Uuid, f32)> {
+        if self.docs.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = self.docs.len() as f32;
+        let avg_len = self.total_len as f32 / doc_count;
+
+        let mut query_terms = tokenize(query);
+        query_terms.sort_unstable();
+        query_terms.dedup();
+
+        let mut scores: HashMap<Uuid, f32> = HashMap::new();
+        for term in &query_terms {
+            let Some(posting) = self.postings.get(term) else {
+                continue;
+            };
+
+            let df = posting.len() as f32;
+            let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+            for (id, tf) in posting {
+                let len = self.docs[id].1 as f32;
+                let tf = *tf as f32;
+                let norm = tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg_len.max(1.0)));
+                *scores.entry(*id).or_insert(0.0) += idf * norm;
+            }
+        }
+
+        let mut ranked: Vec<_> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+/// BM25 inverted index keyed by collection name
+#[derive(Debug, Default)]
+pub struct SparseIndex {
+    collections: RwLock<HashMap<String, CollectionIndex>>,
+}
+
+impl SparseIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index (or re-index) the text of a context
+    pub fn insert(&self, collection: &str, id: Uuid, text: &str) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        collections.entry(collection.to_string()).or_default().insert(id, text);
+    }
+
+    /// Remove a context from the index
+    pub fn remove(&self, collection: &str, id: Uuid) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = collections.get_mut(collection) {
+            index.remove(id);
+        }
+    }
+
+    /// Drop every context of a collection
+    pub fn clear(&self, collection: &str) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        collections.remove(collection);
+    }
+
+    /// Number of indexed contexts in a collection
+    pub fn len(&self, collection: &str) -> usize {
+        let collections = self.collections.read().unwrap_or_else(|e| e.into_inner());
+        collections.get(collection).map(|c| c.docs.len()).unwrap_or(0)
+    }
+
+    /// Return up to `limit` `(id, bm25 score)` pairs, best first
+    pub fn search(&self, collection: &str, query: &str, limit: usize) -> Vec<(Uuid, f32)> {
+        let collections = self.collections.read().unwrap_or_else(|e| e.into_inner());
+        collections
+            .get(collection)
+            .map(|index| index.search(query, limit))
+            .unwrap_or_default()
+    }
+}
+
+/// Split text into lowercase terms.
+///
+/// Identifiers such as `snake_case_names` and `E0308` stay whole so exact
+/// lookups match; everything other than alphanumerics and `_` separates terms.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_keeps_identifiers() {
+        assert_eq!(
+            tokenize("error[E0308]: call parse_point_id()"),
+            vec!["error", "e0308", "call", "parse_point_id"]
+        );
+    }
+
+    #[test]
+    fn test_exact_identifier_ranks_first() {
+        let index = SparseIndex::new();
+        let hit = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        index.insert("c", hit, "build failed with E0308 in retriever");
+        index.insert("c", other, "build failed with a linker error");
+        index.insert("c", Uuid::new_v4(), "unrelated note about deployment");
+
+        let results = index.search("c", "E0308", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, hit);
+
+        let results = index.search("c", "build failed", 10);
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_and_clear() {
+        let index = SparseIndex::new();
+        let id = Uuid::new_v4();
+        index.insert("c", id, "alpha beta");
+        index.insert("c", id, "gamma");
+        assert!(index.search("c", "alpha", 10).is_empty());
+        assert_eq!(index.search("c", "gamma", 10)[0].0, id);
+
+        index.remove("c", id);
+        assert_eq!(index.len("c"), 0);
+        assert!(index.search("c", "gamma", 10).is_empty());
+
+        index.insert("c", id, "gamma");
+        index.clear("c");
+        assert_eq!(index.len("c"), 0);
+    }
+}
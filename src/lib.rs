@@ -38,9 +38,11 @@
 //!     
 //!     // Store and retrieve context
 //!     let id = manager.store_context(
+//!         &Namespace::default(),
 //!         "User prefers dark mode",
 //!         ContextLevel::LongTerm,
 //!         HashMap::new(),
+//!         None,
 //!     ).await?;
 //!     
 //!     Ok(())
@@ -74,7 +76,7 @@ pub mod prelude {
     pub use crate::embedding::{EmbeddingClient, EmbeddingProvider};
     pub use crate::error::{ContextError, Result};
     pub use crate::facts::{FactStore, Fact, FactQuery, FactsState};
-    pub use crate::hirag::{ContextManager, HiRAGManager, ContextRequest, ContextResponse};
+    pub use crate::hirag::{ContextManager, HiRAGManager, ContextRequest, ContextResponse, Namespace};
     pub use crate::middleware::{RateLimiter, RateLimitConfig, AuthMiddleware, AuthConfig, InputValidator};
     pub use crate::observability::{MetricsCollector, HealthChecker};
     pub use crate::protocol::{Message, MessageHandler, Codec};
@@ -1,11 +1,12 @@
 //! Metrics collection for observability
 
 use prometheus::{
-    Counter, CounterVec, Histogram, HistogramVec, Opts, Registry,
+    exponential_buckets, Counter, CounterVec, Histogram, HistogramOpts, HistogramVec, Opts, Registry,
     register_counter_vec_with_registry, register_histogram_vec_with_registry,
     register_counter_with_registry, register_histogram_with_registry,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use once_cell::sync::Lazy;
 
 /// Global metrics registry
@@ -49,6 +50,15 @@ pub struct Metrics {
     pub deepseek_cache_hits: Counter,
     pub deepseek_cache_misses: Counter,
     pub deepseek_circuit_open: CounterVec,
+
+    // Vector store and embedding provider metrics
+    pub vector_db_operation_duration: HistogramVec,
+    pub vector_db_operation_errors: CounterVec,
+    pub vector_db_payload_bytes: HistogramVec,
+    pub embedding_operation_duration: HistogramVec,
+    pub embedding_operation_errors: CounterVec,
+    pub embedding_input_bytes: HistogramVec,
+    pub embedding_cache_lookups: CounterVec,
 }
 
 impl Metrics {
@@ -184,6 +194,53 @@ impl Metrics {
             registry
         )?;
         
+        // Vector store and embedding provider metrics
+        let vector_db_operation_duration = register_histogram_vec_with_registry!(
+            "vector_db_operation_duration_seconds",
+            "Vector store operation duration in seconds",
+            &["collection", "operation"],
+            registry
+        )?;
+
+        let vector_db_operation_errors = register_counter_vec_with_registry!(
+            Opts::new("vector_db_operation_errors_total", "Total failed vector store operations"),
+            &["collection", "operation"],
+            registry
+        )?;
+
+        let vector_db_payload_bytes = register_histogram_vec_with_registry!(
+            HistogramOpts::new("vector_db_payload_bytes", "Approximate bytes of points written or returned")
+                .buckets(exponential_buckets(256.0, 4.0, 10)?),
+            &["collection", "operation"],
+            registry
+        )?;
+
+        let embedding_operation_duration = register_histogram_vec_with_registry!(
+            "embedding_operation_duration_seconds",
+            "Embedding operation duration in seconds",
+            &["operation"],
+            registry
+        )?;
+
+        let embedding_operation_errors = register_counter_vec_with_registry!(
+            Opts::new("embedding_operation_errors_total", "Total failed embedding operations"),
+            &["operation"],
+            registry
+        )?;
+
+        let embedding_input_bytes = register_histogram_vec_with_registry!(
+            HistogramOpts::new("embedding_input_bytes", "Bytes of text sent for embedding")
+                .buckets(exponential_buckets(64.0, 4.0, 10)?),
+            &["operation"],
+            registry
+        )?;
+        
+        let embedding_cache_lookups = register_counter_vec_with_registry!(
+            Opts::new("embedding_cache_lookups_total", "Embedding cache lookups by tier and result"),
+            &["tier", "result"],
+            registry
+        )?;
+        
         Ok(Self {
             registry,
             vision_search_requests,
@@ -207,6 +264,13 @@ impl Metrics {
             deepseek_cache_hits,
             deepseek_cache_misses,
             deepseek_circuit_open,
+            vector_db_operation_duration,
+            vector_db_operation_errors,
+            vector_db_payload_bytes,
+            embedding_operation_duration,
+            embedding_operation_errors,
+            embedding_input_bytes,
+            embedding_cache_lookups,
         })
     }
     
@@ -271,6 +335,40 @@ impl Metrics {
         }
     }
     
+    /// Record a vector store operation, with the size of the points it moved if any
+    pub fn record_vector_db_operation(
+        &self,
+        collection: &str,
+        operation: &str,
+        duration: Duration,
+        success: bool,
+        payload_bytes: Option<usize>,
+    ) {
+        let labels = [collection, operation];
+        self.vector_db_operation_duration.with_label_values(&labels).observe(duration.as_secs_f64());
+        if !success {
+            self.vector_db_operation_errors.with_label_values(&labels).inc();
+        }
+        if let Some(bytes) = payload_bytes {
+            self.vector_db_payload_bytes.with_label_values(&labels).observe(bytes as f64);
+        }
+    }
+    
+    /// Record an embedding operation and the size of its input text
+    pub fn record_embedding_operation(&self, operation: &str, duration: Duration, success: bool, input_bytes: usize) {
+        self.embedding_operation_duration.with_label_values(&[operation]).observe(duration.as_secs_f64());
+        if !success {
+            self.embedding_operation_errors.with_label_values(&[operation]).inc();
+        }
+        self.embedding_input_bytes.with_label_values(&[operation]).observe(input_bytes as f64);
+    }
+    
+    /// Record an embedding cache lookup in `tier` ("memory" or "disk")
+    pub fn record_embedding_cache(&self, tier: &str, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.embedding_cache_lookups.with_label_values(&[tier, result]).inc();
+    }
+    
     /// Export metrics in Prometheus text format
     pub fn export_prometheus(&self) -> String {
         use prometheus::Encoder;
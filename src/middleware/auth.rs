@@ -1,6 +1,6 @@
 //! Authentication middleware
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
@@ -14,6 +14,34 @@ pub struct AuthConfig {
     pub enabled: bool,
     /// Token prefix (e.g., "Bearer")
     pub token_prefix: String,
+    /// Tenant owning each token; unmapped tokens belong to the default tenant
+    pub tenants: HashMap<String, String>,
+    /// Tokens allowed to act on any namespace
+    pub admin_tokens: HashSet<String>,
+}
+
+/// Caller identity resolved from a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Namespace the caller's contexts live in
+    pub tenant_id: String,
+    /// Whether the caller may address other namespaces
+    pub admin: bool,
+}
+
+impl AuthConfig {
+    /// Resolve a valid token (without prefix) to its identity
+    pub fn identify(&self, token: &str) -> Option<Identity> {
+        let admin = self.admin_tokens.contains(token);
+        if !admin && !self.valid_tokens.contains(token) {
+            return None;
+        }
+
+        Some(Identity {
+            tenant_id: self.tenants.get(token).cloned().unwrap_or_else(|| "default".to_string()),
+            admin,
+        })
+    }
 }
 
 impl Default for AuthConfig {
@@ -22,6 +50,8 @@ impl Default for AuthConfig {
             valid_tokens: HashSet::new(),
             enabled: true,
             token_prefix: "Bearer".to_string(),
+            tenants: HashMap::new(),
+            admin_tokens: HashSet::new(),
         }
     }
 }
@@ -54,7 +84,7 @@ impl AuthMiddleware {
             token
         };
 
-        if config.valid_tokens.contains(token) {
+        if config.identify(token).is_some() {
             debug!("Authentication successful");
             Ok(())
         } else {
@@ -85,22 +115,31 @@ impl AuthMiddleware {
     
     /// Validate token (synchronous version for middleware)
     pub fn validate_token(&self, token: &str) -> bool {
-        // Use try_read to avoid blocking
         if let Ok(config) = self.config.try_read() {
             if !config.enabled {
                 return true;
             }
-            
-            let token = if token.starts_with(&format!("{} ", config.token_prefix)) {
-                token.trim_start_matches(&format!("{} ", config.token_prefix))
-            } else {
-                token
-            };
-            
-            config.valid_tokens.contains(token)
-        } else {
-            false
         }
+        self.identify(token).is_some()
+    }
+
+    /// Resolve a token to the caller's identity (synchronous version for middleware).
+    ///
+    /// With authentication disabled every caller is an admin of the default tenant.
+    pub fn identify(&self, token: &str) -> Option<Identity> {
+        // Use try_read to avoid blocking
+        let config = self.config.try_read().ok()?;
+        if !config.enabled {
+            return Some(Identity {
+                tenant_id: "default".to_string(),
+                admin: true,
+            });
+        }
+
+        let token = token
+            .strip_prefix(&format!("{} ", config.token_prefix))
+            .unwrap_or(token);
+        config.identify(token)
     }
 
     /// Get number of valid tokens
@@ -177,4 +216,24 @@ mod tests {
         auth.remove_token("new-token").await;
         assert!(!auth.has_token("new-token").await);
     }
+
+    #[test]
+    fn test_identify_tenant_and_admin() {
+        let mut config = AuthConfig::default();
+        config.valid_tokens.insert("plain".to_string());
+        config.valid_tokens.insert("acme-token".to_string());
+        config.tenants.insert("acme-token".to_string(), "acme".to_string());
+        config.admin_tokens.insert("root".to_string());
+
+        let auth = AuthMiddleware::new(config);
+        assert_eq!(auth.identify("plain").unwrap().tenant_id, "default");
+
+        let acme = auth.identify("Bearer acme-token").unwrap();
+        assert_eq!(acme.tenant_id, "acme");
+        assert!(!acme.admin);
+
+        assert!(auth.identify("root").unwrap().admin);
+        assert!(auth.validate_token("root"));
+        assert!(auth.identify("unknown").is_none());
+    }
 }
\ No newline at end of file
@@ -6,6 +6,6 @@ pub mod validator;
 pub mod body_limit;
 
 pub use rate_limiter::{RateLimiter, RateLimitConfig, RateLimitError};
-pub use auth::{AuthMiddleware, AuthConfig, AuthError};
+pub use auth::{AuthMiddleware, AuthConfig, AuthError, Identity};
 pub use validator::{InputValidator, ValidationError};
 pub use body_limit::{BodyLimiter, BodyLimitConfig};
\ No newline at end of file
@@ -1,5 +1,6 @@
 //! Input validation middleware
 
+use crate::vector_db::{Filter, RESERVED_PAYLOAD_KEYS};
 use tracing::{debug, warn};
 
 /// Maximum text length (8KB)
@@ -111,8 +112,21 @@ impl InputValidator {
         Ok(())
     }
 
-    /// Validate metadata key
+    /// Validate metadata key. Keys naming a core payload field, such as
+    /// `agent_id`, are rejected so metadata cannot overwrite them.
     pub fn validate_metadata_key(key: &str) -> Result<(), ValidationError> {
+        Self::validate_key_syntax(key)?;
+
+        if RESERVED_PAYLOAD_KEYS.contains(&key) {
+            warn!("Validation failed: reserved metadata key ({})", key);
+            return Err(ValidationError::ReservedMetadataKey(key.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Check a metadata key's length and charset
+    fn validate_key_syntax(key: &str) -> Result<(), ValidationError> {
         if key.is_empty() {
             return Err(ValidationError::EmptyMetadataKey);
         }
@@ -161,6 +175,33 @@ impl InputValidator {
         
         Ok(())
     }
+    
+    /// Validate a filter key: a metadata key or a dotted path of them (`source.kind`, `turns[].role`)
+    pub fn validate_filter_key(key: &str) -> Result<(), ValidationError> {
+        if key.is_empty() {
+            return Err(ValidationError::EmptyMetadataKey);
+        }
+        
+        for segment in key.split('.') {
+            Self::validate_key_syntax(segment.strip_suffix("[]").unwrap_or(segment))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Validate the keys and values of every condition in a filter
+    pub fn validate_filter(filter: &Filter) -> Result<(), ValidationError> {
+        for condition in filter.conditions() {
+            if let Some(key) = condition.key() {
+                Self::validate_filter_key(key)?;
+            }
+            for value in condition.values() {
+                Self::validate_metadata_value(value)?;
+            }
+        }
+        
+        Ok(())
+    }
 }
 
 /// Validation errors
@@ -201,6 +242,9 @@ pub enum ValidationError {
 
     #[error("Invalid metadata key (must contain only alphanumeric, underscore, or hyphen)")]
     InvalidMetadataKey,
+
+    #[error("Metadata key is reserved: {0}")]
+    ReservedMetadataKey(String),
     
     #[error("Invalid metadata value")]
     InvalidMetadataValue,
@@ -274,5 +318,29 @@ mod tests {
         assert!(InputValidator::validate_metadata_key("").is_err());
         assert!(InputValidator::validate_metadata_key("invalid key").is_err());
         assert!(InputValidator::validate_metadata_key("invalid@key").is_err());
+        assert!(InputValidator::validate_metadata_key("agent_id").is_err());
+        assert!(InputValidator::validate_metadata_key("text").is_err());
+    }
+
+    #[test]
+    fn test_validate_filter() {
+        use crate::vector_db::Condition;
+        
+        assert!(InputValidator::validate_filter_key("source.kind").is_ok());
+        assert!(InputValidator::validate_filter_key("turns[].role").is_ok());
+        assert!(InputValidator::validate_filter_key("a..b").is_err());
+        assert!(InputValidator::validate_filter_key("bad key").is_err());
+        
+        let ok = Filter::new().must(Condition::MatchAny {
+            key: "tags".to_string(),
+            any: vec![serde_json::json!("a")],
+        });
+        assert!(InputValidator::validate_filter(&ok).is_ok());
+        
+        let bad = Filter::new().must_not(Condition::Match {
+            key: "tags".to_string(),
+            value: serde_json::json!("a\0b"),
+        });
+        assert!(InputValidator::validate_filter(&bad).is_err());
     }
 }
\ No newline at end of file
@@ -194,6 +194,13 @@ impl HealthChecker {
                 std::time::Duration::from_secs(5),
                 async { client.embedding_dimension() }
             ).await {
+                // Offline providers work but must not serve production traffic
+                Ok(dim) if dim > 0 && !client.is_production() => ComponentHealth {
+                    name: "embedding_service".to_string(),
+                    status: HealthStatus::Degraded,
+                    message: Some(format!("Non-production offline provider (dim: {})", dim)),
+                    response_time_ms: Some(start.elapsed().as_millis() as u64),
+                },
                 Ok(dim) if dim > 0 => ComponentHealth {
                     name: "embedding_service".to_string(),
                     status: HealthStatus::Healthy,
@@ -383,6 +390,17 @@ mod tests {
         assert_eq!(health.components.len(), 4); // embedding, vector_db, cache, circuit_breaker
     }
     
+    #[tokio::test]
+    async fn test_offline_embedding_provider_is_flagged() {
+        let checker = HealthChecker::new()
+            .with_embedding_client(Arc::new(crate::embedding::HashingEmbedder::new(8)));
+        let health = checker.check_health().await;
+        
+        let embedding = &health.components[0];
+        assert_eq!(embedding.status, HealthStatus::Degraded);
+        assert!(embedding.message.as_deref().unwrap().contains("Non-production"));
+    }
+    
     #[test]
     fn test_liveness() {
         let checker = HealthChecker::new();
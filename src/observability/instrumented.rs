@@ -0,0 +1,247 @@
+//! Metric-recording decorators for `VectorStore` and `EmbeddingProvider`
+//!
+//! Both wrappers are transparent: results and errors pass through unchanged.
+//! Latency, failures and payload sizes go to the Prometheus registry in
+//! [`crate::metrics::METRICS`], labelled by collection and operation, and
+//! latency is also fed to the [`MetricsCollector`] histograms.
+
+use super::MetricsCollector;
+use crate::config::PayloadFieldType;
+use crate::embedding::EmbeddingProvider;
+use crate::error::Result;
+use crate::metrics::METRICS;
+use crate::vector_db::models::{CollectionStats, Filter, ScrollPage, SearchParams, SearchResult, VectorPoint};
+use crate::vector_db::VectorStore;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Approximate in-memory size of a point: id, vector and text
+fn point_bytes(point: &VectorPoint) -> usize {
+    16 + point.vector.len() * std::mem::size_of::<f32>() + point.payload.text.len()
+}
+
+/// `VectorStore` decorator recording per-operation metrics
+pub struct InstrumentedVectorStore {
+    inner: Arc<dyn VectorStore>,
+    metrics: Arc<MetricsCollector>,
+}
+
+impl InstrumentedVectorStore {
+    pub fn new(inner: Arc<dyn VectorStore>, metrics: Arc<MetricsCollector>) -> Self {
+        Self { inner, metrics }
+    }
+
+    /// Time `op`, then record it with the payload size computed from its result
+    async fn observe<T, Fut>(
+        &self,
+        collection: &str,
+        operation: &str,
+        op: Fut,
+        payload_bytes: impl FnOnce(&T) -> Option<usize>,
+    ) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let start = Instant::now();
+        let result = op.await;
+        let duration = start.elapsed();
+
+        let bytes = result.as_ref().ok().and_then(payload_bytes);
+        self.metrics.record_vector_db_latency(duration);
+        METRICS.record_vector_db_operation(collection, operation, duration, result.is_ok(), bytes);
+
+        result
+    }
+}
+
+#[async_trait]
+impl VectorStore for InstrumentedVectorStore {
+    async fn create_collection(&self, name: &str) -> Result<()> {
+        self.observe(name, "create_collection", self.inner.create_collection(name), |_| None).await
+    }
+
+    async fn delete_collection(&self, name: &str) -> Result<()> {
+        self.observe(name, "delete_collection", self.inner.delete_collection(name), |_| None).await
+    }
+
+    async fn collection_exists(&self, name: &str) -> Result<bool> {
+        self.observe(name, "collection_exists", self.inner.collection_exists(name), |_| None).await
+    }
+
+    async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+        let bytes = points.iter().map(point_bytes).sum();
+        self.observe(collection, "insert_points", self.inner.insert_points(collection, points), |_| Some(bytes))
+            .await
+    }
+
+    async fn search(&self, collection: &str, params: SearchParams) -> Result<Vec<SearchResult>> {
+        self.observe(collection, "search", self.inner.search(collection, params), |results| {
+            Some(
+                results
+                    .iter()
+                    .map(|r| {
+                        16 + r.payload.as_ref().map(|p| p.text.len()).unwrap_or(0)
+                            + r.vector.as_ref().map(|v| v.len() * std::mem::size_of::<f32>()).unwrap_or(0)
+                    })
+                    .sum(),
+            )
+        })
+        .await
+    }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<Uuid>) -> Result<()> {
+        self.observe(collection, "delete_points", self.inner.delete_points(collection, ids), |_| None).await
+    }
+
+    async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>> {
+        self.observe(collection, "get_point", self.inner.get_point(collection, id), |point| {
+            point.as_ref().map(point_bytes)
+        })
+        .await
+    }
+
+    async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+        self.observe(collection, "set_metadata", self.inner.set_metadata(collection, updates), |_| None).await
+    }
+
+    async fn scroll(
+        &self,
+        collection: &str,
+        filter: Option<Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage> {
+        self.observe(
+            collection,
+            "scroll",
+            self.inner.scroll(collection, filter, page_size, cursor),
+            |page| Some(page.points.iter().map(point_bytes).sum()),
+        )
+        .await
+    }
+
+    async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64> {
+        self.observe(collection, "count", self.inner.count(collection, filter), |_| None).await
+    }
+
+    async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+        self.observe(collection, "collection_stats", self.inner.collection_stats(collection), |_| None).await
+    }
+
+    async fn create_payload_index(&self, collection: &str, field: &str, kind: PayloadFieldType) -> Result<()> {
+        self.observe(
+            collection,
+            "create_payload_index",
+            self.inner.create_payload_index(collection, field, kind),
+            |_| None,
+        )
+        .await
+    }
+}
+
+/// `EmbeddingProvider` decorator recording per-operation metrics
+pub struct InstrumentedEmbeddingProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+    metrics: Arc<MetricsCollector>,
+}
+
+impl InstrumentedEmbeddingProvider {
+    pub fn new(inner: Arc<dyn EmbeddingProvider>, metrics: Arc<MetricsCollector>) -> Self {
+        Self { inner, metrics }
+    }
+
+    async fn observe<T, Fut>(&self, operation: &str, input_bytes: usize, op: Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let start = Instant::now();
+        let result = op.await;
+        let duration = start.elapsed();
+
+        self.metrics.record_embedding_latency(duration);
+        METRICS.record_embedding_operation(operation, duration, result.is_ok(), input_bytes);
+
+        result
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for InstrumentedEmbeddingProvider {
+    async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+        self.observe("embed_single", text.len(), self.inner.embed_single(text)).await
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let bytes = texts.iter().map(|t| t.len()).sum();
+        self.observe("embed_batch", bytes, self.inner.embed_batch(texts)).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.observe("embed_query", text.len(), self.inner.embed_query(text)).await
+    }
+
+    fn embedding_dimension(&self) -> usize {
+        self.inner.embedding_dimension()
+    }
+
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    fn is_production(&self) -> bool {
+        self.inner.is_production()
+    }
+
+    fn embeds_queries_separately(&self) -> bool {
+        self.inner.embeds_queries_separately()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::InMemoryVectorStore;
+
+    fn sample_count(collection: &str, operation: &str) -> u64 {
+        METRICS
+            .vector_db_operation_duration
+            .with_label_values(&[collection, operation])
+            .get_sample_count()
+    }
+
+    fn error_count(collection: &str, operation: &str) -> f64 {
+        METRICS
+            .vector_db_operation_errors
+            .with_label_values(&[collection, operation])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn test_records_latency_and_errors_by_operation() {
+        let collection = format!("instrumented_{}", Uuid::new_v4().simple());
+        let metrics = Arc::new(MetricsCollector::new());
+        let store = InstrumentedVectorStore::new(
+            Arc::new(InMemoryVectorStore::new(Config::default_config().vector_db)),
+            metrics,
+        );
+
+        store.create_collection(&collection).await.unwrap();
+        store.count(&collection, None).await.unwrap();
+        store.count(&collection, None).await.unwrap();
+        assert!(store.create_collection(&collection).await.is_err());
+
+        assert_eq!(sample_count(&collection, "count"), 2);
+        assert_eq!(error_count(&collection, "count"), 0.0);
+        assert_eq!(sample_count(&collection, "create_collection"), 2);
+        assert_eq!(error_count(&collection, "create_collection"), 1.0);
+
+        let exported = METRICS.export_prometheus();
+        assert!(exported.contains("vector_db_operation_duration_seconds"));
+        assert!(exported.contains(&collection));
+    }
+}
@@ -1,7 +1,8 @@
 //! Metrics collection and reporting
 
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 /// System metrics
@@ -29,6 +30,22 @@ pub struct SystemMetrics {
     pub memory_usage_bytes: usize,
 }
 
+/// Rolling averages over the most recent retrievals
+#[derive(Debug, Clone, Default)]
+pub struct RetrievalMetrics {
+    /// Average retrieval time (ms)
+    pub avg_retrieval_time_ms: f64,
+    
+    /// Average relevance score of returned contexts
+    pub avg_relevance_score: f32,
+    
+    /// Number of retrievals in the window
+    pub samples: usize,
+}
+
+/// Number of recent retrievals kept for rolling averages
+const RETRIEVAL_WINDOW: usize = 1000;
+
 /// Latency histogram buckets (in milliseconds)
 const LATENCY_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0];
 
@@ -107,6 +124,9 @@ pub struct MetricsCollector {
     embedding_latency: Histogram,
     vector_db_latency: Histogram,
     
+    // Recent (retrieval time ms, avg relevance) samples
+    retrievals: Arc<Mutex<VecDeque<(f64, f32)>>>,
+    
     // GC metrics
     gc_runs: Arc<AtomicU64>,
     gc_deleted_total: Arc<AtomicU64>,
@@ -127,6 +147,7 @@ impl MetricsCollector {
             request_latency: Histogram::new(LATENCY_BUCKETS),
             embedding_latency: Histogram::new(LATENCY_BUCKETS),
             vector_db_latency: Histogram::new(LATENCY_BUCKETS),
+            retrievals: Arc::new(Mutex::new(VecDeque::with_capacity(RETRIEVAL_WINDOW))),
             gc_runs: Arc::new(AtomicU64::new(0)),
             gc_deleted_total: Arc::new(AtomicU64::new(0)),
             gc_errors: Arc::new(AtomicU64::new(0)),
@@ -151,6 +172,33 @@ impl MetricsCollector {
         self.vector_db_latency.observe(duration.as_millis() as f64);
     }
     
+    /// Record a completed context retrieval
+    pub fn record_retrieval(&self, duration: Duration, avg_relevance: f32) {
+        let mut retrievals = self.retrievals.lock().unwrap_or_else(|e| e.into_inner());
+        if retrievals.len() == RETRIEVAL_WINDOW {
+            retrievals.pop_front();
+        }
+        retrievals.push_back((duration.as_secs_f64() * 1000.0, avg_relevance));
+    }
+    
+    /// Get rolling retrieval averages over the last `RETRIEVAL_WINDOW` retrievals
+    pub fn retrieval_metrics(&self) -> RetrievalMetrics {
+        let retrievals = self.retrievals.lock().unwrap_or_else(|e| e.into_inner());
+        if retrievals.is_empty() {
+            return RetrievalMetrics::default();
+        }
+        
+        let samples = retrievals.len();
+        let total_ms: f64 = retrievals.iter().map(|(ms, _)| ms).sum();
+        let total_relevance: f32 = retrievals.iter().map(|(_, r)| r).sum();
+        
+        RetrievalMetrics {
+            avg_retrieval_time_ms: total_ms / samples as f64,
+            avg_relevance_score: total_relevance / samples as f32,
+            samples,
+        }
+    }
+    
     /// Record GC run
     pub fn record_gc_run(&self, deleted_count: usize, _duration: Duration) {
         self.gc_runs.fetch_add(1, Ordering::Relaxed);
@@ -345,6 +393,27 @@ mod tests {
         assert_eq!(metrics.cache_hit_rate, 0.5);
     }
     
+    #[test]
+    fn test_retrieval_window() {
+        let collector = MetricsCollector::new();
+        assert_eq!(collector.retrieval_metrics().samples, 0);
+        
+        collector.record_retrieval(Duration::from_millis(10), 0.5);
+        collector.record_retrieval(Duration::from_millis(30), 1.0);
+        
+        let retrieval = collector.retrieval_metrics();
+        assert_eq!(retrieval.samples, 2);
+        assert_eq!(retrieval.avg_retrieval_time_ms, 20.0);
+        assert_eq!(retrieval.avg_relevance_score, 0.75);
+        
+        for _ in 0..RETRIEVAL_WINDOW {
+            collector.record_retrieval(Duration::from_millis(5), 0.0);
+        }
+        let retrieval = collector.retrieval_metrics();
+        assert_eq!(retrieval.samples, RETRIEVAL_WINDOW);
+        assert_eq!(retrieval.avg_retrieval_time_ms, 5.0);
+    }
+    
     #[test]
     fn test_prometheus_export() {
         let collector = MetricsCollector::new();
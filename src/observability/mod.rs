@@ -2,11 +2,13 @@
 
 pub mod metrics;
 pub mod health;
+pub mod instrumented;
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-pub use metrics::{MetricsCollector, SystemMetrics};
+pub use metrics::{MetricsCollector, RetrievalMetrics, SystemMetrics};
 pub use health::{HealthChecker, SystemHealth, HealthStatus, ComponentHealth};
+pub use instrumented::{InstrumentedEmbeddingProvider, InstrumentedVectorStore};
 
 /// Initialize logging and tracing
 pub fn init_observability(log_level: &str, format: &str) {
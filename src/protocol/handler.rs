@@ -2,7 +2,8 @@
 
 use super::messages::*;
 use crate::error::Result;
-use crate::hirag::ContextManager;
+use crate::hirag::{ContextManager, Namespace};
+use crate::middleware::Identity;
 use async_trait::async_trait;
 use std::sync::Arc;
 use tracing::{debug, error};
@@ -10,8 +11,11 @@ use tracing::{debug, error};
 /// Trait for handling messages
 #[async_trait]
 pub trait MessageHandler: Send + Sync {
-    /// Handle incoming message
-    async fn handle_message(&self, message: Message) -> Result<Option<Message>>;
+    /// Handle a message from the caller authenticated as `identity`.
+    ///
+    /// The identity comes from the transport's authentication, never from the
+    /// message itself, since `sender` is chosen by the client.
+    async fn handle_message(&self, identity: &Identity, message: Message) -> Result<Option<Message>>;
 }
 
 /// Default message handler implementation
@@ -58,12 +62,15 @@ impl DefaultMessageHandler {
 
 #[async_trait]
 impl MessageHandler for DefaultMessageHandler {
-    async fn handle_message(&self, message: Message) -> Result<Option<Message>> {
+    async fn handle_message(&self, identity: &Identity, message: Message) -> Result<Option<Message>> {
         debug!("Handling message type: {:?}", message.message_type);
         
+        // Each tenant reads and writes its own namespace
+        let namespace = Namespace::agent(identity.tenant_id.clone());
+        
         match &message.payload {
             MessagePayload::ContextRequest(request) => {
-                match self.context_manager.retrieve_context(request.clone()).await {
+                match self.context_manager.retrieve_context(&namespace, request.clone()).await {
                     Ok(response) => {
                         let reply = self.create_response(
                             &message,
@@ -84,9 +91,11 @@ impl MessageHandler for DefaultMessageHandler {
             }
             MessagePayload::ContextStore(store_payload) => {
                 match self.context_manager.store_context(
+                    &namespace,
                     &store_payload.text,
                     store_payload.level,
                     store_payload.metadata.clone(),
+                    store_payload.session_id.as_deref(),
                 ).await {
                     Ok(_id) => {
                         let reply = self.create_response(
@@ -65,6 +65,8 @@ pub struct ContextStorePayload {
     pub text: String,
     pub level: ContextLevel,
     pub metadata: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Acknowledgment payload
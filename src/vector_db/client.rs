@@ -1,22 +1,42 @@
 //! Qdrant client implementation
 
         use super::VectorStore;
-        use super::models::{ContextLevel, Payload, VectorPoint, SearchParams, SearchResult, Filter as ModelFilter, Condition as ModelCondition};
-        use crate::config::{VectorDbConfig, Distance};
+        use super::models::{PAYLOAD_VERSION_KEY, RESERVED_PAYLOAD_KEYS, ContextLevel, Payload, VectorPoint, SearchParams, SearchResult, ScrollPage, CollectionStats, Filter as ModelFilter, Condition as ModelCondition};
+        use crate::config::{VectorDbConfig, Distance, PayloadFieldType};
         use crate::error::{VectorDbError, Result};
         use async_trait::async_trait;
-        use qdrant_client::Qdrant;
+        use qdrant_client::{Qdrant, QdrantError};
         use qdrant_client::qdrant::{
             CreateCollectionBuilder, VectorParamsBuilder, VectorsConfig, PointStruct,
             SearchPoints, WithPayloadSelector, PointId, Value, Filter as QdrantFilter, 
-            Condition as QdrantCondition, Range,
+            Condition as QdrantCondition, Range, DatetimeRange, RetrievedPoint, ScrollPointsBuilder, CountPointsBuilder,
+            CreateFieldIndexCollectionBuilder, FieldType, VectorsOutput, PointsUpdateOperation, UpdateBatchPointsBuilder,
         };
+        use qdrant_client::qdrant::points_selector::PointsSelectorOneOf;
+        use qdrant_client::qdrant::points_update_operation::{Operation, SetPayload};
+        use qdrant_client::qdrant::r#match::MatchValue;
+        use qdrant_client::qdrant::vector_output::Vector as VectorOutputVector;
         use qdrant_client::qdrant::vectors_config::Config;
         use qdrant_client::qdrant::with_payload_selector::SelectorOptions;
         use std::collections::HashMap;
+        use tonic::Code;
         use tracing::{debug, info};
         use uuid::Uuid;
 
+        /// Current payload encoding: metadata stored as native Qdrant values
+        const PAYLOAD_VERSION: i64 = 2;
+        
+        /// Points rewritten per page during payload migration
+        const MIGRATION_PAGE_SIZE: u32 = 256;
+        
+        /// Payload fields HiRAG filters on, indexed in every collection
+        const DEFAULT_INDEXED_FIELDS: &[(&str, PayloadFieldType)] = &[
+            ("agent_id", PayloadFieldType::Keyword),
+            ("session_id", PayloadFieldType::Keyword),
+            ("level", PayloadFieldType::Keyword),
+            ("timestamp", PayloadFieldType::Integer),
+        ];
+        
         /// Client for Qdrant vector database
         pub struct VectorDbClient {
             config: VectorDbConfig,
@@ -42,23 +62,109 @@
                 for level in &[ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
                     let collection_name = self.collection_name(*level);
                     
-                    // Check if collection exists
-                    let exists = self.client
-                        .collection_info(collection_name.clone())
-                        .await
-                        .is_ok();
-                    
-                    if !exists {
+                    if !self.collection_exists(&collection_name).await? {
                         info!("Creating collection: {}", collection_name);
                         self.create_collection(&collection_name).await?;
                     } else {
                         debug!("Collection already exists: {}", collection_name);
+                        self.reconcile_payload_indexes(&collection_name).await?;
+                        self.migrate_legacy_payloads(&collection_name).await?;
                     }
                 }
                 
                 Ok(())
             }
             
+            /// Create any default or configured payload index missing from an
+            /// existing collection. Returns the number of indexes created.
+            pub async fn reconcile_payload_indexes(&self, collection: &str) -> Result<usize> {
+                let response = self.client
+                    .collection_info(collection)
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::QdrantError))?;
+                
+                let existing = response.result
+                    .map(|info| info.payload_schema)
+                    .unwrap_or_default();
+                
+                let mut created = 0;
+                for (field, kind) in self.indexed_fields() {
+                    if !existing.contains_key(&field) {
+                        self.create_payload_index(collection, &field, kind).await?;
+                        created += 1;
+                    }
+                }
+                
+                if created > 0 {
+                    info!("Created {} payload indexes in {}", created, collection);
+                }
+                
+                Ok(created)
+            }
+            
+            /// Default HiRAG indexes followed by the ones declared in config
+            fn indexed_fields(&self) -> Vec<(String, PayloadFieldType)> {
+                let mut fields: Vec<(String, PayloadFieldType)> = DEFAULT_INDEXED_FIELDS
+                    .iter()
+                    .map(|(field, kind)| (field.to_string(), *kind))
+                    .collect();
+                
+                for indexed in &self.config.indexed_fields {
+                    if !fields.iter().any(|(field, _)| *field == indexed.field) {
+                        fields.push((indexed.field.clone(), indexed.kind));
+                    }
+                }
+                
+                fields
+            }
+            
+            /// Rewrite points stored with stringified metadata to native payload values.
+            ///
+            /// Only points missing the payload version marker are touched, so this
+            /// is cheap to run on every start. Returns the number of points migrated.
+            pub async fn migrate_legacy_payloads(&self, collection: &str) -> Result<usize> {
+                let mut migrated = 0;
+                let mut offset: Option<PointId> = None;
+                
+                loop {
+                    let mut scroll_points = ScrollPointsBuilder::new(collection)
+                        .filter(QdrantFilter::must([QdrantCondition::is_empty(PAYLOAD_VERSION_KEY)]))
+                        .limit(MIGRATION_PAGE_SIZE)
+                        .with_payload(true)
+                        .with_vectors(true);
+                    
+                    if let Some(offset) = offset.take() {
+                        scroll_points = scroll_points.offset(offset);
+                    }
+                    
+                    let response = self.client
+                        .scroll(scroll_points)
+                        .await
+                        .map_err(|e| qdrant_error(e, VectorDbError::SearchError))?;
+                    
+                    let points = response.result
+                        .into_iter()
+                        .map(|point| self.parse_retrieved_point(point))
+                        .collect::<Result<Vec<_>>>()?;
+                    
+                    if !points.is_empty() {
+                        migrated += points.len();
+                        self.insert_points(collection, points).await?;
+                    }
+                    
+                    match response.next_page_offset {
+                        Some(next) => offset = Some(next),
+                        None => break,
+                    }
+                }
+                
+                if migrated > 0 {
+                    info!("Migrated {} legacy payloads in {}", migrated, collection);
+                }
+                
+                Ok(migrated)
+            }
+            
             /// Get collection name for a context level
             pub fn collection_name(&self, level: ContextLevel) -> String {
                 format!("{}_{}", self.config.collection_prefix, level.as_str().to_lowercase())
@@ -77,6 +183,14 @@
             fn to_qdrant_payload(&self, payload: &Payload) -> HashMap<String, Value> {
                 let mut map = HashMap::new();
                 
+                // Metadata goes first so core fields always win; reserved keys are rejected on
+                // store, this only guards points written by older versions or other clients
+                for (key, value) in &payload.metadata {
+                    if !RESERVED_PAYLOAD_KEYS.contains(&key.as_str()) {
+                        map.insert(key.clone(), Value::from(value.clone()));
+                    }
+                }
+                
                 map.insert("text".to_string(), Value::from(payload.text.clone()));
                 map.insert("level".to_string(), Value::from(payload.level.as_str().to_string()));
                 map.insert("timestamp".to_string(), Value::from(payload.timestamp));
@@ -86,12 +200,7 @@
                     map.insert("session_id".to_string(), Value::from(session_id.clone()));
                 }
                 
-                // Add additional metadata
-                for (key, value) in &payload.metadata {
-                    if let Ok(v) = serde_json::to_string(value) {
-                        map.insert(key.clone(), Value::from(v));
-                    }
-                }
+                map.insert(PAYLOAD_VERSION_KEY.to_string(), Value::from(PAYLOAD_VERSION));
                 
                 map
             }
@@ -144,29 +253,22 @@
                         _ => None,
                     });
                 
+                // Points written before PAYLOAD_VERSION stored metadata as JSON strings
+                let legacy = !payload.contains_key(PAYLOAD_VERSION_KEY);
+                
                 let mut metadata = HashMap::new();
                 for (key, value) in payload {
-                    if !["text", "level", "timestamp", "agent_id", "session_id"].contains(&key.as_str()) {
-                        if let Some(kind) = value.kind.as_ref() {
-                            match kind {
-                                qdrant_client::qdrant::value::Kind::StringValue(s) => {
-                                    if let Ok(json_value) = serde_json::from_str(s) {
-                                        metadata.insert(key, json_value);
-                                    }
-                                }
-                                qdrant_client::qdrant::value::Kind::IntegerValue(i) => {
-                                    metadata.insert(key, serde_json::Value::Number((*i).into()));
-                                }
-                                qdrant_client::qdrant::value::Kind::DoubleValue(d) => {
-                                    metadata.insert(key, serde_json::Value::Number(serde_json::Number::from_f64(*d).unwrap_or_else(|| serde_json::Number::from(0))));
-                                }
-                                qdrant_client::qdrant::value::Kind::BoolValue(b) => {
-                                    metadata.insert(key, serde_json::Value::Bool(*b));
-                                }
-                                _ => {}
-                            }
-                        }
+                    if RESERVED_PAYLOAD_KEYS.contains(&key.as_str()) {
+                        continue;
                     }
+                    
+                    let json_value = match value.kind {
+                        Some(qdrant_client::qdrant::value::Kind::StringValue(s)) if legacy => {
+                            serde_json::from_str(&s).unwrap_or(serde_json::Value::String(s))
+                        }
+                        _ => value.into_json(),
+                    };
+                    metadata.insert(key, json_value);
                 }
                 
                 Ok(Payload {
@@ -179,6 +281,37 @@
                 })
             }
             
+            /// Convert a Qdrant point ID to a UUID
+            fn parse_point_id(&self, point_id: Option<PointId>) -> Result<Uuid> {
+                let id_str = match point_id.and_then(|id| id.point_id_options) {
+                    Some(qdrant_client::qdrant::point_id::PointIdOptions::Num(num)) => num.to_string(),
+                    Some(qdrant_client::qdrant::point_id::PointIdOptions::Uuid(uuid)) => uuid,
+                    None => return Err(VectorDbError::InvalidIdFormat("Missing point ID".to_string()).into()),
+                };
+                
+                Uuid::parse_str(&id_str)
+                    .map_err(|e| VectorDbError::InvalidIdFormat(format!("{}: {}", id_str, e)).into())
+            }
+            
+            /// Convert a retrieved Qdrant point (with payload and vector) to a VectorPoint
+            fn parse_retrieved_point(&self, point: RetrievedPoint) -> Result<VectorPoint> {
+                let id = self.parse_point_id(point.id)?;
+                let payload = self.parse_qdrant_payload(point.payload)?;
+                
+                let vector = self.dense_vector(point.vectors)
+                    .ok_or_else(|| VectorDbError::SearchError("Missing vector".to_string()))?;
+                
+                Ok(VectorPoint { id, vector, payload })
+            }
+            
+            /// Extract the default dense vector from a Qdrant vectors output
+            fn dense_vector(&self, vectors: Option<VectorsOutput>) -> Option<Vec<f32>> {
+                match vectors?.get_vector()? {
+                    VectorOutputVector::Dense(dense) => Some(dense.data),
+                    _ => None,
+                }
+            }
+            
             /// Convert Filter to Qdrant Filter
             fn to_qdrant_filter(&self, filter: &ModelFilter) -> QdrantFilter {
                 let mut must_conditions = Vec::new();
@@ -217,13 +350,66 @@
             /// Convert Condition to Qdrant Condition
             fn to_qdrant_condition(&self, condition: &ModelCondition) -> Option<QdrantCondition> {
                 match condition {
-                    ModelCondition::Match { key, value } => {
-                        if let Some(s) = value.as_str() {
-                            Some(QdrantCondition::matches(key.clone(), s.to_string()))
+                    ModelCondition::Match { key, value } => match value {
+                        serde_json::Value::String(s) => Some(QdrantCondition::matches(key.clone(), s.clone())),
+                        serde_json::Value::Bool(b) => Some(QdrantCondition::matches(key.clone(), *b)),
+                        serde_json::Value::Number(n) => match n.as_i64() {
+                            Some(i) => Some(QdrantCondition::matches(key.clone(), i)),
+                            // Qdrant only matches integers exactly; pin floats with a closed range
+                            None => n.as_f64().map(|f| QdrantCondition::range(key.clone(), Range {
+                                gte: Some(f),
+                                lte: Some(f),
+                                ..Default::default()
+                            })),
+                        },
+                        _ => None,
+                    },
+                    ModelCondition::MatchAny { key, any } => {
+                        if let Some(keywords) = any.iter().map(|v| v.as_str().map(str::to_string)).collect::<Option<Vec<_>>>() {
+                            Some(QdrantCondition::matches(key.clone(), keywords))
+                        } else if let Some(integers) = any.iter().map(|v| v.as_i64()).collect::<Option<Vec<_>>>() {
+                            Some(QdrantCondition::matches(key.clone(), integers))
                         } else {
-                            value.as_i64().map(|i| QdrantCondition::matches(key.clone(), i))
+                            // Mixed value types: any single match will do
+                            let conditions = any.iter().filter_map(|value| self.to_qdrant_condition(&ModelCondition::Match {
+                                key: key.clone(),
+                                value: value.clone(),
+                            }));
+                            Some(QdrantCondition::from(QdrantFilter::should(conditions)))
                         }
                     }
+                    ModelCondition::MatchExcept { key, except } => {
+                        if let Some(keywords) = except.iter().map(|v| v.as_str().map(str::to_string)).collect::<Option<Vec<_>>>() {
+                            Some(QdrantCondition::matches(key.clone(), !MatchValue::from(keywords)))
+                        } else if let Some(integers) = except.iter().map(|v| v.as_i64()).collect::<Option<Vec<_>>>() {
+                            Some(QdrantCondition::matches(key.clone(), !MatchValue::from(integers)))
+                        } else {
+                            let conditions = except.iter().filter_map(|value| self.to_qdrant_condition(&ModelCondition::Match {
+                                key: key.clone(),
+                                value: value.clone(),
+                            }));
+                            Some(QdrantCondition::from(QdrantFilter::must_not(conditions)))
+                        }
+                    }
+                    ModelCondition::DatetimeRange { key, gte, lte } => {
+                        // Unix-second numbers and RFC 3339 strings are both accepted
+                        let seconds = QdrantCondition::range(key.clone(), Range {
+                            gte: gte.map(|d| d.timestamp() as f64),
+                            lte: lte.map(|d| d.timestamp() as f64),
+                            ..Default::default()
+                        });
+                        let datetime = QdrantCondition::datetime_range(key.clone(), DatetimeRange {
+                            gte: gte.map(to_prost_timestamp),
+                            lte: lte.map(to_prost_timestamp),
+                            ..Default::default()
+                        });
+                        Some(QdrantCondition::from(QdrantFilter::should([seconds, datetime])))
+                    }
+                    ModelCondition::MatchText { key, text } => {
+                        Some(QdrantCondition::matches_text(key.clone(), text.clone()))
+                    }
+                    ModelCondition::IsEmpty { key } => Some(QdrantCondition::is_empty(key.clone())),
+                    ModelCondition::IsNull { key } => Some(QdrantCondition::is_null(key.clone())),
                     ModelCondition::Range { key, gte, lte } => {
                         let mut range_builder = Range::default();
                         if let Some(gte_val) = gte {
@@ -246,6 +432,43 @@
             }
         }
         
+        /// Report an existing collection as `CollectionExists` so it is not retried as a backend failure
+        fn create_collection_error(name: &str, error: QdrantError) -> VectorDbError {
+            match error {
+                QdrantError::ResponseError { status } if status.message().contains("already exists") => {
+                    VectorDbError::CollectionExists(name.to_string())
+                }
+                error => qdrant_error(error, VectorDbError::QdrantError),
+            }
+        }
+        
+        /// Report unreachable, overloaded or timed-out requests as
+        /// `ConnectionError`, the only Qdrant failures worth retrying; any other
+        /// error was a rejected request and becomes `rejected`
+        fn qdrant_error(error: QdrantError, rejected: fn(String) -> VectorDbError) -> VectorDbError {
+            let transient = match &error {
+                QdrantError::ResponseError { status } => matches!(
+                    status.code(),
+                    Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted | Code::Aborted
+                ),
+                QdrantError::ResourceExhaustedError { .. } | QdrantError::Io(_) => true,
+                _ => false,
+            };
+            
+            if transient {
+                VectorDbError::ConnectionError(error.to_string())
+            } else {
+                rejected(error.to_string())
+            }
+        }
+        
+        fn to_prost_timestamp(datetime: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+            prost_types::Timestamp {
+                seconds: datetime.timestamp(),
+                nanos: datetime.timestamp_subsec_nanos() as i32,
+            }
+        }
+        
         #[async_trait]
         impl VectorStore for VectorDbClient {
             async fn create_collection(&self, name: &str) -> Result<()> {
@@ -264,7 +487,11 @@
                             })
                     )
                     .await
-                    .map_err(|e| VectorDbError::ConnectionError(e.to_string()))?;
+                    .map_err(|e| create_collection_error(name, e))?;
+                
+                for (field, kind) in self.indexed_fields() {
+                    self.create_payload_index(name, &field, kind).await?;
+                }
                 
                 info!("Collection created: {}", name);
                 Ok(())
@@ -276,12 +503,19 @@
                 self.client
                     .delete_collection(name)
                     .await
-                    .map_err(|e| VectorDbError::ConnectionError(e.to_string()))?;
+                    .map_err(|e| qdrant_error(e, VectorDbError::QdrantError))?;
                 
                 info!("Collection deleted: {}", name);
                 Ok(())
             }
             
+            async fn collection_exists(&self, name: &str) -> Result<bool> {
+                self.client
+                    .collection_exists(name)
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::QdrantError).into())
+            }
+            
             async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
                 if points.is_empty() {
                     return Ok(());
@@ -308,7 +542,7 @@
                 self.client
                     .upsert_points(upsert_points)
                     .await
-                    .map_err(|e| VectorDbError::InsertError(e.to_string()))?;
+                    .map_err(|e| qdrant_error(e, VectorDbError::InsertError))?;
                 
                 debug!("Points inserted successfully");
                 Ok(())
@@ -336,7 +570,7 @@
                 let results = self.client
                     .search_points(search_points)
                     .await
-                    .map_err(|e| VectorDbError::SearchError(e.to_string()))?;
+                    .map_err(|e| qdrant_error(e, VectorDbError::SearchError))?;
                 
                 let search_results: Result<Vec<SearchResult>> = results
                     .result
@@ -364,13 +598,7 @@
                         };
                         
                         let vector = if params.with_vector {
-                            point.vectors.and_then(|v| {
-                                if let Some(qdrant_client::qdrant::vectors_output::VectorsOptions::Vector(vec)) = v.vectors_options {
-                                    Some(vec.data)
-                                } else {
-                                    None
-                                }
-                            })
+                            self.dense_vector(point.vectors)
                         } else {
                             None
                         };
@@ -407,12 +635,47 @@
                 self.client
                     .delete_points(delete_points)
                     .await
-                    .map_err(|e| VectorDbError::DeleteError(e.to_string()))?;
+                    .map_err(|e| qdrant_error(e, VectorDbError::DeleteError))?;
                 
                 debug!("Points deleted successfully");
                 Ok(())
             }
             
+            async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+                if updates.is_empty() {
+                    return Ok(());
+                }
+                
+                debug!("Updating metadata of {} points in collection: {}", updates.len(), collection);
+                
+                let operations: Vec<PointsUpdateOperation> = updates.into_iter()
+                    .map(|(id, metadata)| {
+                        let payload = metadata.into_iter()
+                            .filter(|(key, _)| !RESERVED_PAYLOAD_KEYS.contains(&key.as_str()))
+                            .map(|(key, value)| (key, Value::from(value)))
+                            .collect();
+                        
+                        // A filter selects nothing once the point is deleted, where an ID list would fail
+                        let selector = QdrantFilter::must([QdrantCondition::has_id([PointId::from(id.to_string())])]);
+                        PointsUpdateOperation {
+                            operation: Some(Operation::SetPayload(SetPayload {
+                                payload,
+                                points_selector: Some(PointsSelectorOneOf::from(selector).into()),
+                                shard_key_selector: None,
+                                key: None,
+                            })),
+                        }
+                    })
+                    .collect();
+                
+                self.client
+                    .update_points_batch(UpdateBatchPointsBuilder::new(collection, operations).wait(true))
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::InsertError))?;
+                
+                Ok(())
+            }
+            
             async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>> {
                 debug!("Getting point {} from collection: {}", id, collection);
                 
@@ -426,26 +689,264 @@
                 let points = self.client
                     .get_points(get_points)
                     .await
-                    .map_err(|e| VectorDbError::SearchError(e.to_string()))?;
+                    .map_err(|e| qdrant_error(e, VectorDbError::SearchError))?;
                 
-                if let Some(point) = points.result.first() {
-                    let payload = self.parse_qdrant_payload(point.payload.clone())?;
-                    
-                    let vector = point.vectors.as_ref().and_then(|v| {
-                        if let Some(qdrant_client::qdrant::vectors_output::VectorsOptions::Vector(vec)) = &v.vectors_options {
-                            Some(vec.data.clone())
-                        } else {
-                            None
-                        }
-                    }).ok_or_else(|| VectorDbError::SearchError("Missing vector".to_string()))?;
-                    
-                    Ok(Some(VectorPoint {
-                        id,
-                        vector,
-                        payload,
-                    }))
+                if let Some(point) = points.result.into_iter().next() {
+                    Ok(Some(self.parse_retrieved_point(point)?))
                 } else {
                     Ok(None)
                 }
             }
-        }
\ No newline at end of file
+            
+            async fn scroll(
+                &self,
+                collection: &str,
+                filter: Option<ModelFilter>,
+                page_size: usize,
+                cursor: Option<Uuid>,
+            ) -> Result<ScrollPage> {
+                debug!("Scrolling collection: {} with page size: {}", collection, page_size);
+                
+                let mut scroll_points = ScrollPointsBuilder::new(collection)
+                    .limit(page_size.max(1) as u32)
+                    .with_payload(true)
+                    .with_vectors(true);
+                
+                if let Some(filter) = filter {
+                    scroll_points = scroll_points.filter(self.to_qdrant_filter(&filter));
+                }
+                
+                if let Some(cursor) = cursor {
+                    scroll_points = scroll_points.offset(PointId::from(cursor.to_string()));
+                }
+                
+                let response = self.client
+                    .scroll(scroll_points)
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::SearchError))?;
+                
+                let points = response.result
+                    .into_iter()
+                    .map(|point| self.parse_retrieved_point(point))
+                    .collect::<Result<Vec<_>>>()?;
+                
+                let next_cursor = match response.next_page_offset {
+                    Some(offset) => Some(self.parse_point_id(Some(offset))?),
+                    None => None,
+                };
+                
+                Ok(ScrollPage { points, next_cursor })
+            }
+            
+            async fn count(&self, collection: &str, filter: Option<ModelFilter>) -> Result<u64> {
+                let mut count_points = CountPointsBuilder::new(collection).exact(true);
+                
+                if let Some(filter) = filter {
+                    count_points = count_points.filter(self.to_qdrant_filter(&filter));
+                }
+                
+                let response = self.client
+                    .count(count_points)
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::SearchError))?;
+                
+                Ok(response.result.map(|r| r.count).unwrap_or(0))
+            }
+            
+            async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+                let response = self.client
+                    .collection_info(collection)
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::QdrantError))?;
+                
+                let points_count = response.result.and_then(|info| info.points_count).unwrap_or(0);
+                
+                // Qdrant does not expose disk usage, so estimate from the raw vector size;
+                // payloads, indexes and HNSW links are not counted
+                let size_bytes = points_count * (self.config.vector_size * std::mem::size_of::<f32>()) as u64;
+                
+                Ok(CollectionStats { points_count, size_bytes })
+            }
+            
+            async fn create_payload_index(&self, collection: &str, field: &str, kind: PayloadFieldType) -> Result<()> {
+                let field_type = match kind {
+                    PayloadFieldType::Keyword => FieldType::Keyword,
+                    PayloadFieldType::Integer => FieldType::Integer,
+                    PayloadFieldType::Float => FieldType::Float,
+                    PayloadFieldType::Bool => FieldType::Bool,
+                    PayloadFieldType::Datetime => FieldType::Datetime,
+                    PayloadFieldType::Text => FieldType::Text,
+                };
+                
+                self.client
+                    .create_field_index(
+                        CreateFieldIndexCollectionBuilder::new(collection, field, field_type).wait(true)
+                    )
+                    .await
+                    .map_err(|e| qdrant_error(e, VectorDbError::QdrantError))?;
+                
+                debug!("Payload index created: {}.{} ({:?})", collection, field, kind);
+                Ok(())
+            }
+        }
+        
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::config::Config;
+            use qdrant_client::qdrant::condition::ConditionOneOf;
+            use qdrant_client::qdrant::FieldCondition;
+            
+            async fn client() -> VectorDbClient {
+                VectorDbClient::new(Config::default_config().vector_db).await.unwrap()
+            }
+            
+            #[tokio::test]
+            async fn test_metadata_round_trips_natively() {
+                let client = client().await;
+                let metadata: HashMap<String, serde_json::Value> = serde_json::from_value(serde_json::json!({
+                    "priority": 3,
+                    "score": 0.75,
+                    "archived": false,
+                    "tags": ["rust", "db"],
+                    "source": {"kind": "chat", "turn": 7},
+                    "note": "42",
+                })).unwrap();
+                
+                let payload = Payload {
+                    text: "text".to_string(),
+                    level: ContextLevel::LongTerm,
+                    timestamp: 1,
+                    agent_id: "agent".to_string(),
+                    session_id: None,
+                    metadata: metadata.clone(),
+                };
+                
+                let qdrant_payload = client.to_qdrant_payload(&payload);
+                assert!(matches!(
+                    qdrant_payload["priority"].kind,
+                    Some(qdrant_client::qdrant::value::Kind::IntegerValue(3))
+                ));
+                
+                let parsed = client.parse_qdrant_payload(qdrant_payload).unwrap();
+                assert_eq!(parsed.metadata, metadata);
+            }
+            
+            #[tokio::test]
+            async fn test_legacy_stringified_metadata_is_decoded() {
+                let client = client().await;
+                let mut legacy = HashMap::new();
+                legacy.insert("text".to_string(), Value::from("text"));
+                legacy.insert("level".to_string(), Value::from("ShortTerm"));
+                legacy.insert("timestamp".to_string(), Value::from(1i64));
+                legacy.insert("agent_id".to_string(), Value::from("agent"));
+                legacy.insert("priority".to_string(), Value::from("3"));
+                legacy.insert("label".to_string(), Value::from("\"urgent\""));
+                
+                let parsed = client.parse_qdrant_payload(legacy).unwrap();
+                assert_eq!(parsed.metadata["priority"], serde_json::json!(3));
+                assert_eq!(parsed.metadata["label"], serde_json::json!("urgent"));
+            }
+            
+            #[tokio::test]
+            async fn test_metadata_cannot_overwrite_core_fields() {
+                let client = client().await;
+                let payload = Payload {
+                    text: "text".to_string(),
+                    level: ContextLevel::LongTerm,
+                    timestamp: 1,
+                    agent_id: "tenant-a".to_string(),
+                    session_id: None,
+                    metadata: HashMap::from([
+                        ("agent_id".to_string(), serde_json::json!("tenant-b")),
+                        ("session_id".to_string(), serde_json::json!("s9")),
+                    ]),
+                };
+                
+                let qdrant_payload = client.to_qdrant_payload(&payload);
+                assert!(matches!(
+                    &qdrant_payload["agent_id"].kind,
+                    Some(qdrant_client::qdrant::value::Kind::StringValue(agent)) if agent == "tenant-a"
+                ));
+                assert!(!qdrant_payload.contains_key("session_id"));
+            }
+            
+            /// The field condition a converted condition wraps
+            fn field(condition: Option<QdrantCondition>) -> FieldCondition {
+                match condition.and_then(|c| c.condition_one_of) {
+                    Some(ConditionOneOf::Field(field)) => field,
+                    other => panic!("expected a field condition, got {:?}", other),
+                }
+            }
+            
+            fn match_value(condition: Option<QdrantCondition>) -> MatchValue {
+                field(condition).r#match.and_then(|m| m.match_value).unwrap()
+            }
+            
+            /// The nested filter a converted condition wraps
+            fn nested(condition: Option<QdrantCondition>) -> QdrantFilter {
+                match condition.and_then(|c| c.condition_one_of) {
+                    Some(ConditionOneOf::Filter(filter)) => filter,
+                    other => panic!("expected a nested filter, got {:?}", other),
+                }
+            }
+            
+            #[tokio::test]
+            async fn test_match_any_and_except_conversion() {
+                let client = client().await;
+                let key = || "tags".to_string();
+                let convert = |c: ModelCondition| client.to_qdrant_condition(&c);
+                
+                let keywords = convert(ModelCondition::MatchAny { key: key(), any: vec![serde_json::json!("a"), serde_json::json!("b")] });
+                assert!(matches!(match_value(keywords), MatchValue::Keywords(r) if r.strings == ["a", "b"]));
+                
+                let integers = convert(ModelCondition::MatchAny { key: key(), any: vec![serde_json::json!(1), serde_json::json!(2)] });
+                assert!(matches!(match_value(integers), MatchValue::Integers(r) if r.integers == [1, 2]));
+                
+                let mixed = convert(ModelCondition::MatchAny { key: key(), any: vec![serde_json::json!("a"), serde_json::json!(1)] });
+                let mixed = nested(mixed);
+                assert_eq!(mixed.should.len(), 2);
+                assert!(mixed.must.is_empty());
+                
+                let keywords = convert(ModelCondition::MatchExcept { key: key(), except: vec![serde_json::json!("a")] });
+                assert!(matches!(match_value(keywords), MatchValue::ExceptKeywords(r) if r.strings == ["a"]));
+                
+                let integers = convert(ModelCondition::MatchExcept { key: key(), except: vec![serde_json::json!(3)] });
+                assert!(matches!(match_value(integers), MatchValue::ExceptIntegers(r) if r.integers == [3]));
+                
+                let mixed = convert(ModelCondition::MatchExcept { key: key(), except: vec![serde_json::json!("a"), serde_json::json!(true)] });
+                assert_eq!(nested(mixed).must_not.len(), 2);
+            }
+            
+            #[tokio::test]
+            async fn test_text_empty_null_and_datetime_conversion() {
+                let client = client().await;
+                let convert = |c: ModelCondition| client.to_qdrant_condition(&c);
+                
+                let text = field(convert(ModelCondition::MatchText { key: "text".to_string(), text: "E0308".to_string() }));
+                assert_eq!(text.key, "text");
+                assert!(matches!(text.r#match.and_then(|m| m.match_value), Some(MatchValue::Text(t)) if t == "E0308"));
+                
+                let empty = convert(ModelCondition::IsEmpty { key: "links".to_string() });
+                assert!(matches!(empty.and_then(|c| c.condition_one_of), Some(ConditionOneOf::IsEmpty(c)) if c.key == "links"));
+                
+                let null = convert(ModelCondition::IsNull { key: "archived".to_string() });
+                assert!(matches!(null.and_then(|c| c.condition_one_of), Some(ConditionOneOf::IsNull(c)) if c.key == "archived"));
+                
+                // Nested keys are passed through in Qdrant's own syntax
+                let nested_key = field(convert(ModelCondition::Match {
+                    key: "source.turns[].role".to_string(),
+                    value: serde_json::json!("tool"),
+                }));
+                assert_eq!(nested_key.key, "source.turns[].role");
+                
+                let since = chrono::DateTime::parse_from_rfc3339("2024-01-15T10:00:00Z").unwrap().with_timezone(&chrono::Utc);
+                let datetime = nested(convert(ModelCondition::DatetimeRange { key: "created".to_string(), gte: Some(since), lte: None }));
+                assert_eq!(datetime.should.len(), 2);
+                let mut branches = datetime.should.into_iter().map(|c| field(Some(c)));
+                let seconds = branches.next().unwrap();
+                assert_eq!(seconds.range.and_then(|r| r.gte), Some(since.timestamp() as f64));
+                let rfc3339 = branches.next().unwrap();
+                assert_eq!(rfc3339.datetime_range.and_then(|r| r.gte).map(|t| t.seconds), Some(since.timestamp()));
+            }
+        }
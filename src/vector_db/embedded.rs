@@ -0,0 +1,948 @@
+//! Embedded single-node vector store persisted to a local directory
+//!
+//! Each collection lives in its own directory:
+//!
+//! ```text
+//! <data_dir>/<collection>/segment-00000001.seg
+//! <data_dir>/<collection>/segment-00000002.seg
+//! <data_dir>/<collection>/wal.log
+//! ```
+//!
+//! Mutations are appended to `wal.log` and fsynced before they are applied in
+//! memory. Once the WAL grows past `segment_max_bytes` it is sealed by renaming
+//! it into the next immutable segment. Compaction rewrites the live points of a
+//! collection into a single segment that starts with a `Reset` record, so any
+//! older segments left behind by a crash are harmless and removed on the next
+//! open. A torn final frame of the WAL (crash mid-append) is detected by its
+//! length and checksum and truncated away during recovery; a bad frame
+//! anywhere else means the files are corrupt, and opening the store fails
+//! rather than silently dropping acknowledged writes.
+//!
+//! `<data_dir>/.lock` is held with an exclusive lock while the store is open,
+//! so a second process fails to open the same directory instead of corrupting
+//! it. File I/O runs on the blocking thread pool.
+
+use super::VectorStore;
+use super::memory::MemoryCollection;
+use super::models::{CollectionStats, ContextLevel, Filter, ScrollPage, SearchParams, SearchResult, VectorPoint};
+use crate::config::{PayloadFieldType, VectorDbConfig};
+use crate::error::{Result, VectorDbError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+const LOCK_FILE: &str = ".lock";
+const WAL_FILE: &str = "wal.log";
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_SUFFIX: &str = ".seg";
+
+/// Compact a collection automatically once it has this many segments
+const MAX_SEGMENTS: usize = 8;
+
+/// Points per record when writing a compacted segment
+const COMPACTION_CHUNK: usize = 256;
+
+/// Frame header: payload length (u32 LE) followed by CRC-32 (u32 LE)
+const FRAME_HEADER_LEN: usize = 8;
+
+/// A single entry in the WAL or a segment
+#[derive(Debug, Serialize, Deserialize)]
+enum LogRecord {
+    Upsert(Vec<VectorPoint>),
+    Delete(Vec<Uuid>),
+    /// Discard all state replayed so far (first record of compacted segments)
+    Reset,
+    /// Merge metadata into existing points
+    SetMetadata(Vec<(Uuid, HashMap<String, serde_json::Value>)>),
+}
+
+/// On-disk and in-memory state of one collection
+struct EmbeddedCollection {
+    dir: PathBuf,
+    data: RwLock<MemoryCollection>,
+    /// Only locked on the blocking pool; serializes writes so the WAL and `data` apply records in the same order
+    log: Mutex<CollectionLog>,
+}
+
+/// Files of a collection
+struct CollectionLog {
+    wal: File,
+    wal_bytes: u64,
+    segments: Vec<u64>,
+    /// Set when a failed append could not be rolled back or a seal left no
+    /// usable WAL; the log then refuses writes until reopened
+    poisoned: bool,
+}
+
+/// Vector store persisting collections to append-only segment files
+pub struct EmbeddedVectorStore {
+    config: VectorDbConfig,
+    root: PathBuf,
+    collections: RwLock<HashMap<String, Arc<EmbeddedCollection>>>,
+    /// Exclusive lock on `data_dir`, released when the store is dropped
+    _lock: File,
+}
+
+impl EmbeddedVectorStore {
+    /// Open the store, recovering every collection found under `config.data_dir`.
+    ///
+    /// Fails immediately if another store holds the directory.
+    pub fn open(config: VectorDbConfig) -> Result<Self> {
+        let root = PathBuf::from(&config.data_dir);
+        info!("Opening embedded vector store at {}", root.display());
+
+        fs::create_dir_all(&root).map_err(storage_err)?;
+        let lock = lock_dir(&root)?;
+
+        let mut collections = HashMap::new();
+        for entry in fs::read_dir(&root).map_err(storage_err)? {
+            let entry = entry.map_err(storage_err)?;
+            if !entry.file_type().map_err(storage_err)?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let mut collection = EmbeddedCollection::open(entry.path())?;
+            debug!("Recovered collection {} with {} points", name, collection.data.get_mut().points.len());
+            collections.insert(name, Arc::new(collection));
+        }
+
+        info!("Embedded vector store opened with {} collections", collections.len());
+
+        Ok(Self {
+            config,
+            root,
+            collections: RwLock::new(collections),
+            _lock: lock,
+        })
+    }
+
+    /// Initialize collections for all context levels
+    pub async fn initialize_collections(&self) -> Result<()> {
+        for level in &[ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
+            let collection_name = self.collection_name(*level);
+            if !self.collections.read().await.contains_key(&collection_name) {
+                info!("Creating collection: {}", collection_name);
+                self.create_collection(&collection_name).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get collection name for a context level
+    pub fn collection_name(&self, level: ContextLevel) -> String {
+        format!("{}_{}", self.config.collection_prefix, level.as_str().to_lowercase())
+    }
+
+    /// Compact every collection into a single segment
+    pub async fn compact(&self) -> Result<()> {
+        let collections: Vec<_> = self
+            .collections
+            .read()
+            .await
+            .iter()
+            .map(|(name, collection)| (name.clone(), collection.clone()))
+            .collect();
+
+        for (name, collection) in collections {
+            blocking(move || collection.compact()).await?;
+            debug!("Compacted collection: {}", name);
+        }
+
+        Ok(())
+    }
+
+    /// Start periodic background compaction
+    pub fn start_compaction_task(self: Arc<Self>) {
+        let interval = Duration::from_secs(self.config.compaction_interval_secs);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately; skip it
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+
+                if let Err(e) = self.compact().await {
+                    error!("Embedded vector store compaction failed: {}", e);
+                }
+            }
+        });
+
+        info!("Embedded vector store compaction task started");
+    }
+
+    fn check_dimension(&self, actual: usize) -> Result<()> {
+        if actual != self.config.vector_size {
+            return Err(VectorDbError::InvalidDimension {
+                expected: self.config.vector_size,
+                actual,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Look up a collection without keeping the collection map locked
+    async fn collection(&self, name: &str) -> Result<Arc<EmbeddedCollection>> {
+        self.collections
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VectorDbError::CollectionNotFound(name.to_string()).into())
+    }
+
+    /// Append a record to a collection's WAL and apply it in memory
+    async fn apply(&self, collection: &str, record: LogRecord) -> Result<()> {
+        let target = self.collection(collection).await?;
+        let segment_max_bytes = self.config.segment_max_bytes;
+
+        blocking(move || target.write(record, segment_max_bytes)).await
+    }
+}
+
+impl EmbeddedCollection {
+    /// Create a new, empty collection directory
+    fn create(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir).map_err(storage_err)?;
+        sync_dir(dir.parent().unwrap_or(&dir))?;
+        Self::open(dir)
+    }
+
+    /// Recover a collection from its segments and WAL
+    fn open(dir: PathBuf) -> Result<Self> {
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(storage_err)? {
+            let path = entry.map_err(storage_err)?.path();
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+
+            if file_name.ends_with(".tmp") {
+                // Leftover from an interrupted compaction
+                warn!("Removing incomplete file {}", path.display());
+                fs::remove_file(&path).map_err(storage_err)?;
+            } else if let Some(seq) = parse_segment_seq(file_name) {
+                segments.push(seq);
+            }
+        }
+        segments.sort_unstable();
+
+        let mut data = MemoryCollection::default();
+        let mut obsolete = Vec::new();
+
+        for seq in &segments {
+            let path = segment_path(&dir, *seq);
+            let (records, valid_len, torn) = read_records(&path)?;
+            // Segments are fsynced before they are renamed into place, so they are never torn
+            if torn {
+                return Err(corrupt(&path, valid_len).into());
+            }
+
+            if matches!(records.first(), Some(LogRecord::Reset)) {
+                obsolete.extend(segments.iter().copied().filter(|s| s < seq));
+            }
+            for record in records {
+                data.replay(record);
+            }
+        }
+
+        // Segments superseded by a later compaction are no longer needed
+        for seq in &obsolete {
+            fs::remove_file(segment_path(&dir, *seq)).map_err(storage_err)?;
+        }
+        segments.retain(|s| !obsolete.contains(s));
+
+        let wal_path = dir.join(WAL_FILE);
+        let mut wal_bytes = 0;
+        if wal_path.exists() {
+            let (records, valid_len, torn) = read_records(&wal_path)?;
+            if torn {
+                warn!("Recovered from torn write in {}", wal_path.display());
+                truncate(&wal_path, valid_len)?;
+            }
+            for record in records {
+                data.replay(record);
+            }
+            wal_bytes = valid_len;
+        }
+
+        let wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&wal_path)
+            .map_err(storage_err)?;
+
+        Ok(Self {
+            dir,
+            data: RwLock::new(data),
+            log: Mutex::new(CollectionLog {
+                wal,
+                wal_bytes,
+                segments,
+                poisoned: false,
+            }),
+        })
+    }
+
+    fn lock_log(&self) -> Result<MutexGuard<'_, CollectionLog>> {
+        self.log
+            .lock()
+            .map_err(|_| VectorDbError::StorageError(format!("Log of {} is poisoned", self.dir.display())).into())
+    }
+
+    /// Persist a record, apply it in memory and seal or compact as needed. Blocking.
+    ///
+    /// The record is committed once appended, so a failed seal or compaction
+    /// is only logged; it is retried by the next write.
+    fn write(&self, record: LogRecord, segment_max_bytes: u64) -> Result<()> {
+        let mut log = self.lock_log()?;
+        log.append(&record)?;
+        self.data.blocking_write().replay(record);
+
+        if log.wal_bytes >= segment_max_bytes {
+            if let Err(e) = log.seal(&self.dir) {
+                error!("Failed to seal WAL of {}: {}", self.dir.display(), e);
+            } else if log.segments.len() > MAX_SEGMENTS {
+                if let Err(e) = self.compact_log(&mut log) {
+                    error!("Failed to compact {}: {}", self.dir.display(), e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bytes used on disk by the WAL and all segments. Blocking.
+    fn disk_bytes(&self) -> Result<u64> {
+        let log = self.lock_log()?;
+        let mut total = log.wal_bytes;
+        for seq in &log.segments {
+            total += fs::metadata(segment_path(&self.dir, *seq)).map_err(storage_err)?.len();
+        }
+        Ok(total)
+    }
+
+    /// Rewrite all live points into a single new segment. Blocking.
+    fn compact(&self) -> Result<()> {
+        let mut log = self.lock_log()?;
+        self.compact_log(&mut log)
+    }
+
+    fn compact_log(&self, log: &mut CollectionLog) -> Result<()> {
+        log.seal(&self.dir)?;
+        if log.segments.len() <= 1 {
+            return Ok(());
+        }
+
+        let seq = next_segment_seq(&self.dir, &log.segments)?;
+        let final_path = segment_path(&self.dir, seq);
+        let tmp_path = final_path.with_extension("seg.tmp");
+
+        // Writers are held off by the log lock, so this snapshot matches the sealed segments
+        let points: Vec<VectorPoint> = self.data.blocking_read().points.values().cloned().collect();
+        let written = write_segment(&tmp_path, &points)
+            .and_then(|_| fs::rename(&tmp_path, &final_path).map_err(|e| storage_err(e).into()));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        // The new segment starts with a Reset, so from here on it alone holds
+        // the collection; old segments left behind are removed on the next open
+        let old_segments = std::mem::replace(&mut log.segments, vec![seq]);
+        sync_dir(&self.dir)?;
+        for old in old_segments {
+            if let Err(e) = fs::remove_file(segment_path(&self.dir, old)) {
+                warn!("Failed to remove compacted segment {} of {}: {}", old, self.dir.display(), e);
+            }
+        }
+
+        info!(
+            "Compacted {} into segment {} ({} points)",
+            self.dir.display(),
+            seq,
+            points.len()
+        );
+        Ok(())
+    }
+}
+
+impl CollectionLog {
+    /// Append a record to the WAL and fsync it.
+    ///
+    /// A failed append is truncated away so the WAL never holds a record that
+    /// was not applied in memory; if that fails too the WAL is poisoned.
+    fn append(&mut self, record: &LogRecord) -> Result<()> {
+        if self.poisoned {
+            return Err(VectorDbError::StorageError(
+                "WAL is unusable after a failed write; reopen the store".to_string(),
+            )
+            .into());
+        }
+
+        let frame = encode_frame(record)?;
+        if let Err(e) = self.wal.write_all(&frame).and_then(|_| self.wal.sync_data()) {
+            if let Err(rollback) = self.wal.set_len(self.wal_bytes).and_then(|_| self.wal.sync_data()) {
+                error!("Could not roll back failed WAL write: {}", rollback);
+                self.poisoned = true;
+            }
+            return Err(storage_err(e).into());
+        }
+
+        self.wal_bytes += frame.len() as u64;
+        Ok(())
+    }
+
+    /// Turn the current WAL into an immutable segment and start a new WAL.
+    ///
+    /// Once the WAL is renamed the segment is recorded; if the new WAL cannot
+    /// be created the log is poisoned rather than appending to the segment.
+    fn seal(&mut self, dir: &Path) -> Result<()> {
+        if self.wal_bytes == 0 || self.poisoned {
+            return Ok(());
+        }
+
+        let seq = next_segment_seq(dir, &self.segments)?;
+        let wal_path = dir.join(WAL_FILE);
+        fs::rename(&wal_path, segment_path(dir, seq)).map_err(storage_err)?;
+
+        self.segments.push(seq);
+        self.wal_bytes = 0;
+        self.poisoned = true;
+
+        sync_dir(dir)?;
+        self.wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&wal_path)
+            .map_err(storage_err)?;
+        self.poisoned = false;
+
+        debug!("Sealed WAL into segment {} in {}", seq, dir.display());
+        Ok(())
+    }
+}
+
+impl MemoryCollection {
+    /// Apply a log record to the in-memory state
+    fn replay(&mut self, record: LogRecord) {
+        match record {
+            LogRecord::Upsert(points) => {
+                for point in points {
+                    self.points.insert(point.id, point);
+                }
+            }
+            LogRecord::Delete(ids) => {
+                for id in ids {
+                    self.points.remove(&id);
+                }
+            }
+            LogRecord::Reset => self.points.clear(),
+            LogRecord::SetMetadata(updates) => self.set_metadata(updates),
+        }
+    }
+}
+
+#[async_trait]
+impl VectorStore for EmbeddedVectorStore {
+    async fn create_collection(&self, name: &str) -> Result<()> {
+        validate_collection_name(name)?;
+
+        let mut collections = self.collections.write().await;
+        if collections.contains_key(name) {
+            return Err(VectorDbError::CollectionExists(name.to_string()).into());
+        }
+
+        let dir = self.root.join(name);
+        let collection = blocking(move || EmbeddedCollection::create(dir)).await?;
+        collections.insert(name.to_string(), Arc::new(collection));
+
+        info!("Collection created: {}", name);
+        Ok(())
+    }
+
+    async fn delete_collection(&self, name: &str) -> Result<()> {
+        let collection = self
+            .collections
+            .write()
+            .await
+            .remove(name)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(name.to_string()))?;
+
+        let root = self.root.clone();
+        blocking(move || {
+            // Wait for in-flight writes before removing their files
+            let _log = collection.lock_log()?;
+            fs::remove_dir_all(&collection.dir).map_err(storage_err)?;
+            sync_dir(&root)
+        })
+        .await?;
+
+        info!("Collection deleted: {}", name);
+        Ok(())
+    }
+
+    async fn collection_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.collections.read().await.contains_key(name))
+    }
+
+    async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        for point in &points {
+            self.check_dimension(point.vector.len())?;
+        }
+
+        debug!("Inserting {} points into collection: {}", points.len(), collection);
+        self.apply(collection, LogRecord::Upsert(points)).await
+    }
+
+    async fn search(&self, collection: &str, params: SearchParams) -> Result<Vec<SearchResult>> {
+        self.check_dimension(params.vector.len())?;
+
+        let target = self.collection(collection).await?;
+        let data = target.data.read().await;
+        data.search(self.config.distance, &params)
+    }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<Uuid>) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Deleting {} points from collection: {}", ids.len(), collection);
+        self.apply(collection, LogRecord::Delete(ids)).await
+    }
+
+    async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>> {
+        let target = self.collection(collection).await?;
+        let data = target.data.read().await;
+        Ok(data.points.get(&id).cloned())
+    }
+
+    async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Updating metadata of {} points in collection: {}", updates.len(), collection);
+        self.apply(collection, LogRecord::SetMetadata(updates)).await
+    }
+
+    async fn scroll(
+        &self,
+        collection: &str,
+        filter: Option<Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage> {
+        let target = self.collection(collection).await?;
+        let data = target.data.read().await;
+        data.scroll(filter.as_ref(), page_size, cursor)
+    }
+
+    async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64> {
+        let target = self.collection(collection).await?;
+        let data = target.data.read().await;
+        data.count(filter.as_ref())
+    }
+
+    async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+        let target = self.collection(collection).await?;
+        let points_count = target.data.read().await.points.len() as u64;
+        let size_bytes = blocking(move || target.disk_bytes()).await?;
+
+        Ok(CollectionStats {
+            points_count,
+            size_bytes,
+        })
+    }
+
+    async fn create_payload_index(&self, collection: &str, _field: &str, _kind: PayloadFieldType) -> Result<()> {
+        // Filters are evaluated by scanning, there is no index to build
+        self.collection(collection).await?;
+        Ok(())
+    }
+}
+
+/// Run blocking file I/O off the async runtime
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| VectorDbError::StorageError(e.to_string()))?
+}
+
+/// Take the exclusive lock on a store directory, failing if it is held
+fn lock_dir(root: &Path) -> Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(root.join(LOCK_FILE))
+        .map_err(storage_err)?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(VectorDbError::StorageError(format!(
+            "{} is already in use by another embedded vector store",
+            root.display()
+        ))
+        .into()),
+        Err(TryLockError::Error(e)) => Err(storage_err(e).into()),
+    }
+}
+
+fn storage_err(e: std::io::Error) -> VectorDbError {
+    VectorDbError::StorageError(e.to_string())
+}
+
+/// Collection names become directory names, so keep them to a safe charset
+fn validate_collection_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(VectorDbError::StorageError(format!("Invalid collection name: {}", name)).into());
+    }
+    Ok(())
+}
+
+fn segment_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{}{:08}{}", SEGMENT_PREFIX, seq, SEGMENT_SUFFIX))
+}
+
+fn parse_segment_seq(file_name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(SEGMENT_PREFIX)?
+        .strip_suffix(SEGMENT_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Make a rename or unlink inside `dir` durable
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir).and_then(|d| d.sync_all()).map_err(storage_err)?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Sequence number after the highest of `segments` and the segment files in
+/// `dir`, so a segment left behind by a failed compaction is never overwritten
+fn next_segment_seq(dir: &Path, segments: &[u64]) -> Result<u64> {
+    let mut highest = segments.iter().copied().max().unwrap_or(0);
+    for entry in fs::read_dir(dir).map_err(storage_err)? {
+        let name = entry.map_err(storage_err)?.file_name();
+        if let Some(seq) = parse_segment_seq(&name.to_string_lossy()) {
+            highest = highest.max(seq);
+        }
+    }
+    Ok(highest + 1)
+}
+
+/// Write a compacted segment holding `points` to `path` and fsync it
+fn write_segment(path: &Path, points: &[VectorPoint]) -> Result<()> {
+    let mut file = File::create(path).map_err(storage_err)?;
+    file.write_all(&encode_frame(&LogRecord::Reset)?).map_err(storage_err)?;
+    for chunk in points.chunks(COMPACTION_CHUNK) {
+        let frame = encode_frame(&LogRecord::Upsert(chunk.to_vec()))?;
+        file.write_all(&frame).map_err(storage_err)?;
+    }
+    file.sync_all().map_err(storage_err)?;
+    Ok(())
+}
+
+fn corrupt(path: &Path, offset: u64) -> VectorDbError {
+    VectorDbError::StorageError(format!(
+        "Corrupt frame at byte {} of {}; restore the collection from a backup",
+        offset,
+        path.display()
+    ))
+}
+
+fn truncate(path: &Path, len: u64) -> Result<()> {
+    let file = OpenOptions::new().write(true).open(path).map_err(storage_err)?;
+    file.set_len(len).map_err(storage_err)?;
+    file.sync_all().map_err(storage_err)?;
+    Ok(())
+}
+
+fn encode_frame(record: &LogRecord) -> Result<Vec<u8>> {
+    let body = rmp_serde::to_vec_named(record)
+        .map_err(|e| VectorDbError::SerializationError(e.to_string()))?;
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32(&body).to_le_bytes());
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+/// Read all complete frames from a log file.
+///
+/// Returns the decoded records, the byte length of the valid prefix and
+/// whether a torn final frame follows it. A frame that fails its checksum
+/// with more data after it is corruption, not a torn write, and an error.
+fn read_records(path: &Path) -> Result<(Vec<LogRecord>, u64, bool)> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .map_err(storage_err)?;
+
+    let mut records = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+        if rest.len() < FRAME_HEADER_LEN {
+            break;
+        }
+
+        let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let checksum = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]);
+        let Some(body) = rest.get(FRAME_HEADER_LEN..FRAME_HEADER_LEN + len) else {
+            break;
+        };
+        if crc32(body) != checksum {
+            // Only the last frame can have been cut short by a crash
+            if FRAME_HEADER_LEN + len < rest.len() {
+                return Err(corrupt(path, offset as u64).into());
+            }
+            break;
+        }
+
+        let record = rmp_serde::from_slice(body)
+            .map_err(|e| VectorDbError::SerializationError(e.to_string()))?;
+        records.push(record);
+        offset += FRAME_HEADER_LEN + len;
+    }
+
+    Ok((records, offset as u64, offset < bytes.len()))
+}
+
+/// CRC-32 (IEEE) checksum
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, VectorDbBackend};
+    use crate::vector_db::models::Payload;
+
+    fn config(dir: &Path) -> VectorDbConfig {
+        let mut config = Config::default_config().vector_db;
+        config.backend = VectorDbBackend::Embedded;
+        config.data_dir = dir.to_string_lossy().to_string();
+        config.vector_size = 2;
+        config
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("embedded-store-{}", Uuid::new_v4()))
+    }
+
+    fn point(x: f32) -> VectorPoint {
+        VectorPoint {
+            id: Uuid::new_v4(),
+            vector: vec![x, 1.0],
+            payload: Payload {
+                text: format!("point {}", x),
+                level: ContextLevel::LongTerm,
+                timestamp: 1,
+                agent_id: "default".to_string(),
+                session_id: Some("s1".to_string()),
+                metadata: HashMap::from([("score".to_string(), serde_json::json!(x))]),
+            },
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[tokio::test]
+    async fn test_survives_restart() {
+        let dir = temp_dir();
+        let p1 = point(1.0);
+        let p2 = point(2.0);
+
+        {
+            let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+            store.create_collection("c").await.unwrap();
+            store.insert_points("c", vec![p1.clone(), p2.clone()]).await.unwrap();
+            store.delete_points("c", vec![p1.id]).await.unwrap();
+            let hits = HashMap::from([("hits".to_string(), serde_json::json!(3))]);
+            store.set_metadata("c", vec![(p1.id, hits.clone()), (p2.id, hits)]).await.unwrap();
+        }
+
+        let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+        assert!(store.get_point("c", p1.id).await.unwrap().is_none());
+        let restored = store.get_point("c", p2.id).await.unwrap().unwrap();
+        assert_eq!(restored.payload.text, "point 2");
+        assert_eq!(restored.payload.session_id.as_deref(), Some("s1"));
+        assert_eq!(restored.payload.metadata["score"], serde_json::json!(2.0));
+        assert_eq!(restored.payload.metadata["hits"], serde_json::json!(3));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_torn_write() {
+        let dir = temp_dir();
+        let p1 = point(1.0);
+
+        {
+            let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+            store.create_collection("c").await.unwrap();
+            store.insert_points("c", vec![p1.clone()]).await.unwrap();
+        }
+
+        // Simulate a crash halfway through appending the next frame
+        let wal_path = dir.join("c").join(WAL_FILE);
+        let valid_len = fs::metadata(&wal_path).unwrap().len();
+        let frame = encode_frame(&LogRecord::Upsert(vec![point(2.0)])).unwrap();
+        let mut wal = OpenOptions::new().append(true).open(&wal_path).unwrap();
+        wal.write_all(&frame[..frame.len() / 2]).unwrap();
+        drop(wal);
+
+        let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+        assert!(store.get_point("c", p1.id).await.unwrap().is_some());
+        assert_eq!(fs::metadata(&wal_path).unwrap().len(), valid_len);
+
+        // The log stays usable after recovery
+        let p3 = point(3.0);
+        store.insert_points("c", vec![p3.clone()]).await.unwrap();
+        drop(store);
+        let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+        assert!(store.get_point("c", p3.id).await.unwrap().is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refuses_corruption_before_the_tail() {
+        let dir = temp_dir();
+        {
+            let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+            store.create_collection("c").await.unwrap();
+            store.insert_points("c", vec![point(1.0)]).await.unwrap();
+            store.delete_points("c", vec![Uuid::new_v4()]).await.unwrap();
+        }
+
+        // Flip a byte in the body of the first of two WAL frames
+        let wal_path = dir.join("c").join(WAL_FILE);
+        let mut bytes = fs::read(&wal_path).unwrap();
+        bytes[FRAME_HEADER_LEN] ^= 0xFF;
+        fs::write(&wal_path, &bytes).unwrap();
+        assert!(EmbeddedVectorStore::open(config(&dir)).is_err());
+
+        // A damaged sealed segment is never truncated, even at its end
+        bytes[FRAME_HEADER_LEN] ^= 0xFF;
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&wal_path, b"").unwrap();
+        fs::write(segment_path(&dir.join("c"), 1), &bytes).unwrap();
+        assert!(EmbeddedVectorStore::open(config(&dir)).is_err());
+        assert_eq!(fs::metadata(segment_path(&dir.join("c"), 1)).unwrap().len(), bytes.len() as u64);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_seal_and_compact() {
+        let dir = temp_dir();
+        let mut config = config(&dir);
+        config.segment_max_bytes = 1;
+
+        let store = EmbeddedVectorStore::open(config.clone()).unwrap();
+        store.create_collection("c").await.unwrap();
+
+        let points: Vec<_> = (0..5).map(|i| point(i as f32)).collect();
+        for p in &points {
+            store.insert_points("c", vec![p.clone()]).await.unwrap();
+        }
+        store.delete_points("c", vec![points[0].id]).await.unwrap();
+
+        let segment_count = || {
+            fs::read_dir(dir.join("c"))
+                .unwrap()
+                .filter(|e| {
+                    let name = e.as_ref().unwrap().file_name();
+                    parse_segment_seq(&name.to_string_lossy()).is_some()
+                })
+                .count()
+        };
+        assert_eq!(segment_count(), 6);
+
+        store.compact().await.unwrap();
+        assert_eq!(segment_count(), 1);
+
+        let stats = store.collection_stats("c").await.unwrap();
+        let on_disk: u64 = fs::read_dir(dir.join("c")).unwrap().map(|e| e.unwrap().metadata().unwrap().len()).sum();
+        assert_eq!(stats.points_count, 4);
+        assert_eq!(stats.size_bytes, on_disk);
+        drop(store);
+
+        let store = EmbeddedVectorStore::open(config).unwrap();
+        assert!(store.get_point("c", points[0].id).await.unwrap().is_none());
+        for p in &points[1..] {
+            assert!(store.get_point("c", p.id).await.unwrap().is_some());
+        }
+
+        let results = store.search("c", SearchParams::new(vec![4.0, 1.0], 1)).await.unwrap();
+        assert_eq!(results[0].id, points[4].id);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_collection_removes_files() {
+        let dir = temp_dir();
+        let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+
+        store.initialize_collections().await.unwrap();
+        assert!(dir.join("contexts_shortterm").exists());
+
+        store.delete_collection("contexts_shortterm").await.unwrap();
+        assert!(!dir.join("contexts_shortterm").exists());
+        assert!(store.create_collection("../escape").await.is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_data_dir_is_locked_while_open() {
+        let dir = temp_dir();
+        let store = EmbeddedVectorStore::open(config(&dir)).unwrap();
+        assert!(EmbeddedVectorStore::open(config(&dir)).is_err());
+
+        drop(store);
+        assert!(EmbeddedVectorStore::open(config(&dir)).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
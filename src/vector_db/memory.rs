@@ -0,0 +1,584 @@
+//! In-process vector store for offline use and tests
+
+use super::VectorStore;
+use super::models::{CollectionStats, ContextLevel, Filter, ScrollPage, SearchParams, SearchResult, VectorPoint, RESERVED_PAYLOAD_KEYS};
+use crate::config::{Distance, PayloadFieldType, VectorDbConfig};
+use crate::error::{Result, VectorDbError};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Points of a single collection held in memory
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryCollection {
+    pub(crate) points: HashMap<Uuid, VectorPoint>,
+}
+
+impl MemoryCollection {
+    /// Brute-force search over every point in the collection
+    pub(crate) fn search(&self, distance: Distance, params: &SearchParams) -> Result<Vec<SearchResult>> {
+        let mut scored = Vec::new();
+
+        for point in self.points.values() {
+            if !matches_filter(point, params.filter.as_ref())? {
+                continue;
+            }
+
+            let score = score(distance, &params.vector, &point.vector);
+            if let Some(threshold) = params.score_threshold {
+                if !passes_threshold(distance, score, threshold) {
+                    continue;
+                }
+            }
+
+            scored.push((score, point));
+        }
+
+        // Euclidean scores are distances (lower is better), as in Qdrant
+        scored.sort_by(|(a, pa), (b, pb)| {
+            let ord = match distance {
+                Distance::Euclidean => a.partial_cmp(b),
+                Distance::Cosine | Distance::Dot => b.partial_cmp(a),
+            };
+            ord.unwrap_or(std::cmp::Ordering::Equal).then_with(|| pa.id.cmp(&pb.id))
+        });
+
+        Ok(scored
+            .into_iter()
+            .take(params.limit)
+            .map(|(score, point)| SearchResult {
+                id: point.id,
+                score,
+                payload: params.with_payload.then(|| point.payload.clone()),
+                vector: params.with_vector.then(|| point.vector.clone()),
+            })
+            .collect())
+    }
+
+    /// Return up to `page_size` matching points with IDs at or after `cursor`
+    pub(crate) fn scroll(
+        &self,
+        filter: Option<&Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage> {
+        let page_size = page_size.max(1);
+        let mut ids: Vec<&Uuid> = self
+            .points
+            .keys()
+            .filter(|id| cursor.is_none_or(|c| **id >= c))
+            .collect();
+        ids.sort_unstable();
+
+        let mut points = Vec::with_capacity(page_size);
+        for id in ids {
+            let point = &self.points[id];
+            if !matches_filter(point, filter)? {
+                continue;
+            }
+
+            if points.len() == page_size {
+                return Ok(ScrollPage { points, next_cursor: Some(*id) });
+            }
+            points.push(point.clone());
+        }
+
+        Ok(ScrollPage { points, next_cursor: None })
+    }
+
+    /// Count points matching `filter`
+    pub(crate) fn count(&self, filter: Option<&Filter>) -> Result<u64> {
+        let mut count = 0;
+        for point in self.points.values() {
+            if matches_filter(point, filter)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Merge metadata into the points that exist, skipping reserved keys
+    pub(crate) fn set_metadata(&mut self, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) {
+        for (id, metadata) in updates {
+            let Some(point) = self.points.get_mut(&id) else { continue };
+            for (key, value) in metadata {
+                if !RESERVED_PAYLOAD_KEYS.contains(&key.as_str()) {
+                    point.payload.metadata.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Approximate memory held by the points' IDs, vectors and texts
+    pub(crate) fn size_bytes(&self) -> u64 {
+        self.points
+            .values()
+            .map(|p| {
+                (std::mem::size_of::<Uuid>()
+                    + p.vector.len() * std::mem::size_of::<f32>()
+                    + p.payload.text.len()) as u64
+            })
+            .sum()
+    }
+}
+
+fn matches_filter(point: &VectorPoint, filter: Option<&Filter>) -> Result<bool> {
+    let Some(filter) = filter else {
+        return Ok(true);
+    };
+
+    let payload = serde_json::to_value(&point.payload)
+        .map_err(|e| VectorDbError::SerializationError(e.to_string()))?;
+    Ok(filter.matches(&point.id, &payload))
+}
+
+/// Score a candidate vector against the query using the configured metric
+pub(crate) fn score(distance: Distance, query: &[f32], candidate: &[f32]) -> f32 {
+    match distance {
+        Distance::Dot => dot(query, candidate),
+        Distance::Cosine => {
+            let norm = (dot(query, query) * dot(candidate, candidate)).sqrt();
+            if norm > 0.0 {
+                dot(query, candidate) / norm
+            } else {
+                0.0
+            }
+        }
+        Distance::Euclidean => query
+            .iter()
+            .zip(candidate)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt(),
+    }
+}
+
+/// Check a score against a threshold (an upper bound for Euclidean distance)
+fn passes_threshold(distance: Distance, score: f32, threshold: f32) -> bool {
+    match distance {
+        Distance::Euclidean => score <= threshold,
+        Distance::Cosine | Distance::Dot => score >= threshold,
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Vector store that keeps all collections in process memory.
+///
+/// Nothing is persisted; intended for tests, local development and running
+/// the server without external services.
+pub struct InMemoryVectorStore {
+    config: VectorDbConfig,
+    collections: RwLock<HashMap<String, MemoryCollection>>,
+}
+
+impl InMemoryVectorStore {
+    /// Create a new, empty in-memory store
+    pub fn new(config: VectorDbConfig) -> Self {
+        info!(
+            "Initializing in-memory vector store (dim: {}, distance: {:?})",
+            config.vector_size, config.distance
+        );
+
+        Self {
+            config,
+            collections: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Initialize collections for all context levels
+    pub async fn initialize_collections(&self) -> Result<()> {
+        for level in &[ContextLevel::Immediate, ContextLevel::ShortTerm, ContextLevel::LongTerm] {
+            let collection_name = self.collection_name(*level);
+            let mut collections = self.collections.write().await;
+            collections.entry(collection_name).or_default();
+        }
+
+        Ok(())
+    }
+
+    /// Get collection name for a context level
+    pub fn collection_name(&self, level: ContextLevel) -> String {
+        format!("{}_{}", self.config.collection_prefix, level.as_str().to_lowercase())
+    }
+
+    /// Number of points currently stored in a collection
+    pub async fn len(&self, collection: &str) -> Result<usize> {
+        let collections = self.collections.read().await;
+        collections
+            .get(collection)
+            .map(|c| c.points.len())
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()).into())
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn create_collection(&self, name: &str) -> Result<()> {
+        let mut collections = self.collections.write().await;
+
+        if collections.contains_key(name) {
+            return Err(VectorDbError::CollectionExists(name.to_string()).into());
+        }
+
+        collections.insert(name.to_string(), MemoryCollection::default());
+        debug!("Collection created: {}", name);
+        Ok(())
+    }
+
+    async fn delete_collection(&self, name: &str) -> Result<()> {
+        let mut collections = self.collections.write().await;
+
+        if collections.remove(name).is_none() {
+            return Err(VectorDbError::CollectionNotFound(name.to_string()).into());
+        }
+
+        debug!("Collection deleted: {}", name);
+        Ok(())
+    }
+
+    async fn collection_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.collections.read().await.contains_key(name))
+    }
+
+    async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        for point in &points {
+            if point.vector.len() != self.config.vector_size {
+                return Err(VectorDbError::InvalidDimension {
+                    expected: self.config.vector_size,
+                    actual: point.vector.len(),
+                }
+                .into());
+            }
+        }
+
+        let mut collections = self.collections.write().await;
+        let target = collections
+            .get_mut(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        debug!("Inserting {} points into collection: {}", points.len(), collection);
+        for point in points {
+            target.points.insert(point.id, point);
+        }
+
+        Ok(())
+    }
+
+    async fn search(&self, collection: &str, params: SearchParams) -> Result<Vec<SearchResult>> {
+        let collections = self.collections.read().await;
+        let target = collections
+            .get(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        if params.vector.len() != self.config.vector_size {
+            return Err(VectorDbError::InvalidDimension {
+                expected: self.config.vector_size,
+                actual: params.vector.len(),
+            }
+            .into());
+        }
+
+        let results = target.search(self.config.distance, &params)?;
+        debug!("Found {} results in collection: {}", results.len(), collection);
+        Ok(results)
+    }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<Uuid>) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut collections = self.collections.write().await;
+        let target = collections
+            .get_mut(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        for id in &ids {
+            target.points.remove(id);
+        }
+
+        debug!("Deleted {} points from collection: {}", ids.len(), collection);
+        Ok(())
+    }
+
+    async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>> {
+        let collections = self.collections.read().await;
+        let target = collections
+            .get(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        Ok(target.points.get(&id).cloned())
+    }
+
+    async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+        let mut collections = self.collections.write().await;
+        let target = collections
+            .get_mut(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        debug!("Updating metadata of {} points in collection: {}", updates.len(), collection);
+        target.set_metadata(updates);
+        Ok(())
+    }
+
+    async fn scroll(
+        &self,
+        collection: &str,
+        filter: Option<Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage> {
+        let collections = self.collections.read().await;
+        let target = collections
+            .get(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        target.scroll(filter.as_ref(), page_size, cursor)
+    }
+
+    async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64> {
+        let collections = self.collections.read().await;
+        let target = collections
+            .get(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        target.count(filter.as_ref())
+    }
+
+    async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+        let collections = self.collections.read().await;
+        let target = collections
+            .get(collection)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(collection.to_string()))?;
+
+        Ok(CollectionStats {
+            points_count: target.points.len() as u64,
+            size_bytes: target.size_bytes(),
+        })
+    }
+
+    async fn create_payload_index(&self, collection: &str, _field: &str, _kind: PayloadFieldType) -> Result<()> {
+        // Filters are evaluated by scanning, there is no index to build
+        if !self.collections.read().await.contains_key(collection) {
+            return Err(VectorDbError::CollectionNotFound(collection.to_string()).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::models::{Condition, Filter, Payload};
+
+    fn store(distance: Distance) -> InMemoryVectorStore {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = 3;
+        config.distance = distance;
+        InMemoryVectorStore::new(config)
+    }
+
+    fn point(vector: Vec<f32>, timestamp: i64, tags: serde_json::Value) -> VectorPoint {
+        let mut metadata = HashMap::new();
+        metadata.insert("tags".to_string(), tags);
+        VectorPoint {
+            id: Uuid::new_v4(),
+            vector,
+            payload: Payload {
+                text: "text".to_string(),
+                level: ContextLevel::ShortTerm,
+                timestamp,
+                agent_id: "default".to_string(),
+                session_id: None,
+                metadata,
+            },
+        }
+    }
+
+    async fn seeded(distance: Distance) -> (InMemoryVectorStore, Vec<Uuid>) {
+        let store = store(distance);
+        store.create_collection("test").await.unwrap();
+        let points = vec![
+            point(vec![1.0, 0.0, 0.0], 100, serde_json::json!(["rust", "db"])),
+            point(vec![0.9, 0.1, 0.0], 200, serde_json::json!(["rust"])),
+            point(vec![0.0, 1.0, 0.0], 300, serde_json::json!(["python"])),
+        ];
+        let ids = points.iter().map(|p| p.id).collect();
+        store.insert_points("test", points).await.unwrap();
+        (store, ids)
+    }
+
+    #[tokio::test]
+    async fn test_cosine_ordering() {
+        let (store, ids) = seeded(Distance::Cosine).await;
+
+        let results = store.search("test", SearchParams::new(vec![1.0, 0.0, 0.0], 10)).await.unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].id, ids[0]);
+        assert_eq!(results[1].id, ids[1]);
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+        assert!(results[0].payload.is_some());
+        assert!(results[0].vector.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_euclidean_lower_is_better() {
+        let (store, ids) = seeded(Distance::Euclidean).await;
+
+        let params = SearchParams::new(vec![0.0, 1.0, 0.0], 10).with_score_threshold(0.5);
+        let results = store.search("test", params).await.unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, ids[2]);
+        assert_eq!(results[0].score, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_dot_score_threshold() {
+        let (store, _) = seeded(Distance::Dot).await;
+
+        let params = SearchParams::new(vec![1.0, 0.0, 0.0], 10).with_score_threshold(0.95);
+        let results = store.search("test", params).await.unwrap();
+
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_filter_semantics() {
+        let (store, ids) = seeded(Distance::Cosine).await;
+
+        let filter = Filter::new()
+            .must(Condition::Match { key: "tags".to_string(), value: serde_json::json!("rust") })
+            .must(Condition::Range { key: "timestamp".to_string(), gte: Some(150.0), lte: None });
+        let results = store
+            .search("test", SearchParams::new(vec![1.0, 0.0, 0.0], 10).with_filter(filter))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, ids[1]);
+
+        let filter = Filter::new()
+            .should(Condition::HasId { ids: vec![ids[0], ids[2]] })
+            .must_not(Condition::Match { key: "tags".to_string(), value: serde_json::json!("python") });
+        let results = store
+            .search("test", SearchParams::new(vec![1.0, 0.0, 0.0], 10).with_filter(filter))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, ids[0]);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cannot_change_namespace() {
+        let store = store(Distance::Cosine);
+        store.create_collection("test").await.unwrap();
+        let mut spoofed = point(vec![1.0, 0.0, 0.0], 100, serde_json::json!([]));
+        spoofed.payload.agent_id = "tenant-a".to_string();
+        spoofed.payload.metadata.insert("agent_id".to_string(), serde_json::json!("tenant-b"));
+        store.insert_points("test", vec![spoofed.clone()]).await.unwrap();
+
+        let owned_by = |agent: &str| {
+            Filter::new().must(Condition::Match { key: "agent_id".to_string(), value: serde_json::json!(agent) })
+        };
+        assert_eq!(store.count("test", Some(owned_by("tenant-a"))).await.unwrap(), 1);
+        assert_eq!(store.count("test", Some(owned_by("tenant-b"))).await.unwrap(), 0);
+
+        let serialized = serde_json::to_value(&spoofed.payload).unwrap();
+        assert_eq!(serialized["agent_id"], serde_json::json!("tenant-a"));
+    }
+
+    #[tokio::test]
+    async fn test_set_metadata_merges_without_creating() {
+        let (store, ids) = seeded(Distance::Cosine).await;
+        let missing = Uuid::new_v4();
+        let update = |key: &str| HashMap::from([(key.to_string(), serde_json::json!(1)), ("text".to_string(), serde_json::json!("x"))]);
+
+        store.set_metadata("test", vec![(ids[0], update("hits")), (missing, update("hits"))]).await.unwrap();
+
+        let point = store.get_point("test", ids[0]).await.unwrap().unwrap();
+        assert_eq!(point.payload.metadata["hits"], 1);
+        assert!(point.payload.metadata.contains_key("tags"));
+        assert!(!point.payload.metadata.contains_key("text"));
+        assert!(store.get_point("test", missing).await.unwrap().is_none());
+        assert_eq!(store.len("test").await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_vector_and_get_delete() {
+        let (store, ids) = seeded(Distance::Cosine).await;
+
+        let mut params = SearchParams::new(vec![1.0, 0.0, 0.0], 1);
+        params.with_payload = false;
+        params.with_vector = true;
+        let results = store.search("test", params).await.unwrap();
+        assert!(results[0].payload.is_none());
+        assert_eq!(results[0].vector, Some(vec![1.0, 0.0, 0.0]));
+
+        store.delete_points("test", vec![ids[0]]).await.unwrap();
+        assert!(store.get_point("test", ids[0]).await.unwrap().is_none());
+        assert!(store.get_point("test", ids[1]).await.unwrap().is_some());
+        assert_eq!(store.len("test").await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_scroll_and_count() {
+        let store = store(Distance::Cosine);
+        store.create_collection("test").await.unwrap();
+        let points: Vec<_> = (0..25)
+            .map(|i| point(vec![1.0, 0.0, 0.0], i, serde_json::json!([if i % 2 == 0 { "even" } else { "odd" }])))
+            .collect();
+        store.insert_points("test", points).await.unwrap();
+
+        let even = Filter::new().must(Condition::Match { key: "tags".to_string(), value: serde_json::json!("even") });
+        assert_eq!(store.count("test", None).await.unwrap(), 25);
+        assert_eq!(store.count("test", Some(even.clone())).await.unwrap(), 13);
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store.scroll("test", Some(even.clone()), 5, cursor).await.unwrap();
+            assert!(page.points.len() <= 5);
+            seen.extend(page.points.iter().map(|p| p.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(seen.len(), 13);
+        assert!(seen.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_collection_stats() {
+        let (store, _) = seeded(Distance::Cosine).await;
+
+        let stats = store.collection_stats("test").await.unwrap();
+        assert_eq!(stats.points_count, 3);
+        assert_eq!(stats.size_bytes, 3 * (16 + 3 * 4 + "text".len() as u64));
+    }
+
+    #[tokio::test]
+    async fn test_collection_errors() {
+        let store = store(Distance::Cosine);
+
+        assert!(store.search("missing", SearchParams::new(vec![1.0, 0.0, 0.0], 1)).await.is_err());
+        store.create_collection("test").await.unwrap();
+        assert!(store.create_collection("test").await.is_err());
+
+        let bad = point(vec![1.0, 0.0], 0, serde_json::json!([]));
+        assert!(store.insert_points("test", vec![bad]).await.is_err());
+    }
+}
@@ -2,16 +2,27 @@
 
 pub mod client;
 pub mod client_v2;
+pub mod embedded;
+pub mod memory;
 pub mod models;
+pub mod resilient;
 pub mod search;
+pub mod transfer;
 pub mod circuit_breaker;
 
 pub use client::VectorDbClient;
-pub use models::{VectorPoint, Payload, SearchParams, SearchResult, Filter, Condition, ContextLevel};
+pub use embedded::EmbeddedVectorStore;
+pub use memory::InMemoryVectorStore;
+pub use resilient::ResilientVectorStore;
+pub use models::{VectorPoint, Payload, SearchParams, SearchResult, ScrollPage, CollectionStats, Filter, Condition, ContextLevel, RESERVED_PAYLOAD_KEYS};
+pub use transfer::{export_collection, import_collection, ExportFormat, ExportOptions, ExportReader, ExportRecord, ImportOptions, ImportReport};
 pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
 
 use async_trait::async_trait;
+use crate::config::{PayloadFieldType, VectorDbBackend, VectorDbConfig};
 use crate::error::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Trait for vector storage operations
@@ -23,6 +34,9 @@ pub trait VectorStore: Send + Sync {
     /// Delete a collection
     async fn delete_collection(&self, name: &str) -> Result<()>;
     
+    /// Whether a collection exists
+    async fn collection_exists(&self, name: &str) -> Result<bool>;
+    
     /// Insert points into collection
     async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()>;
     
@@ -34,4 +48,87 @@ pub trait VectorStore: Send + Sync {
     
     /// Get point by ID
     async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>>;
-}
\ No newline at end of file
+    
+    /// Merge metadata into existing points, keyed by point ID.
+    ///
+    /// Vectors and core payload fields are left alone and reserved keys are
+    /// ignored. IDs that do not exist are skipped, never created.
+    async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()>;
+    
+    /// Page through points matching `filter` in ID order.
+    ///
+    /// Start with `cursor: None` and pass each page's `next_cursor` back in
+    /// until it is `None`.
+    async fn scroll(
+        &self,
+        collection: &str,
+        filter: Option<Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage>;
+    
+    /// Count points matching `filter`
+    async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64>;
+    
+    /// Get point count and storage size of a collection
+    async fn collection_stats(&self, collection: &str) -> Result<CollectionStats>;
+    
+    /// Index a payload field so filters on it avoid full scans.
+    ///
+    /// Creating an index that already exists is not an error. Backends that
+    /// filter by scanning treat this as a no-op.
+    async fn create_payload_index(&self, collection: &str, field: &str, kind: PayloadFieldType) -> Result<()>;
+}
+
+/// Create the vector store selected by `config.backend` and initialize the
+/// collections for all context levels
+pub async fn create_vector_store(config: VectorDbConfig) -> Result<Arc<dyn VectorStore>> {
+    match config.backend {
+        VectorDbBackend::Qdrant => {
+            let client = VectorDbClient::new(config).await?;
+            client.initialize_collections().await?;
+            Ok(Arc::new(client))
+        }
+        VectorDbBackend::Memory => {
+            let store = InMemoryVectorStore::new(config);
+            store.initialize_collections().await?;
+            Ok(Arc::new(store))
+        }
+        VectorDbBackend::Embedded => {
+            let store = Arc::new(EmbeddedVectorStore::open(config)?);
+            store.initialize_collections().await?;
+            store.clone().start_compaction_task();
+            Ok(store)
+        }
+    }
+}
+
+/// Fetch the points with the given IDs that match `filter`, in one scroll
+/// rather than a lookup per ID. Missing IDs are left out.
+pub async fn get_points(
+    store: &dyn VectorStore,
+    collection: &str,
+    ids: Vec<Uuid>,
+    filter: Option<&Filter>,
+) -> Result<Vec<VectorPoint>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let page_size = ids.len();
+    let filter = filter.cloned().unwrap_or_default().must(Condition::HasId { ids });
+    
+    let mut points = Vec::with_capacity(page_size);
+    let mut cursor = None;
+    loop {
+        let page = store.scroll(collection, Some(filter.clone()), page_size, cursor).await?;
+        points.extend(page.points);
+        
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    
+    Ok(points)
+}
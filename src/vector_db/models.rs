@@ -1,6 +1,8 @@
 //! Data models for vector database operations
 
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -35,8 +37,14 @@ pub struct VectorPoint {
     pub payload: Payload,
 }
 
+/// Payload key the Qdrant backend records its metadata encoding under
+pub(crate) const PAYLOAD_VERSION_KEY: &str = "_payload_version";
+
+/// Payload keys holding `Payload` fields or backend bookkeeping, never user metadata
+pub const RESERVED_PAYLOAD_KEYS: &[&str] = &["text", "level", "timestamp", "agent_id", "session_id", PAYLOAD_VERSION_KEY];
+
 /// Metadata payload for vector points
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Payload {
     /// Original text content
     pub text: String,
@@ -58,6 +66,30 @@ pub struct Payload {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+/// Serialized as one flat map like `#[serde(flatten)]` would, except that
+/// metadata under a reserved key is skipped so it can never shadow a core
+/// field, e.g. when filters are evaluated against the serialized payload.
+impl Serialize for Payload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let metadata: Vec<_> = self
+            .metadata
+            .iter()
+            .filter(|(key, _)| !RESERVED_PAYLOAD_KEYS.contains(&key.as_str()))
+            .collect();
+        
+        let mut map = serializer.serialize_map(Some(metadata.len() + 5))?;
+        for (key, value) in metadata {
+            map.serialize_entry(key, value)?;
+        }
+        map.serialize_entry("text", &self.text)?;
+        map.serialize_entry("level", &self.level)?;
+        map.serialize_entry("timestamp", &self.timestamp)?;
+        map.serialize_entry("agent_id", &self.agent_id)?;
+        map.serialize_entry("session_id", &self.session_id)?;
+        map.end()
+    }
+}
+
 /// Search parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchParams {
@@ -96,6 +128,29 @@ pub struct SearchResult {
     pub vector: Option<Vec<f32>>,
 }
 
+/// One page of points returned by a scroll
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollPage {
+    /// Points in this page, ordered by ID
+    pub points: Vec<VectorPoint>,
+    
+    /// Cursor for the next page, `None` once the scroll is exhausted
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Size of a collection as reported by the backend
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CollectionStats {
+    /// Number of points stored
+    pub points_count: u64,
+    
+    /// Storage used by the collection (bytes). Exact on disk for the embedded
+    /// backend; an estimate of vector and text memory for the memory backend, and
+    /// of raw vector size (points × dimension × 4) for Qdrant, which does not
+    /// report disk usage.
+    pub size_bytes: u64,
+}
+
 /// Filter for metadata-based search
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Filter {
@@ -113,11 +168,30 @@ pub struct Filter {
 }
 
 /// Individual filter condition
+///
+/// `key` may be a dotted path into nested metadata (`source.kind`); arrays
+/// along the path are searched element-wise.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Condition {
     Match { key: String, value: serde_json::Value },
+    /// Field equals any of the given values (or an array field contains one)
+    MatchAny { key: String, any: Vec<serde_json::Value> },
+    /// Field has a value outside the given list; missing fields also match
+    MatchExcept { key: String, except: Vec<serde_json::Value> },
     Range { key: String, gte: Option<f64>, lte: Option<f64> },
+    /// Range over unix-second numbers or RFC 3339 strings
+    DatetimeRange {
+        key: String,
+        gte: Option<DateTime<Utc>>,
+        lte: Option<DateTime<Utc>>,
+    },
+    /// String field contains `text`
+    MatchText { key: String, text: String },
+    /// Field is missing, null or an empty array
+    IsEmpty { key: String },
+    /// Field is present and null
+    IsNull { key: String },
     HasId { ids: Vec<Uuid> },
 }
 
@@ -173,4 +247,176 @@ impl Default for Filter {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+impl Filter {
+    /// Iterate every condition regardless of clause
+    pub fn conditions(&self) -> impl Iterator<Item = &Condition> {
+        self.must.iter().chain(&self.should).chain(&self.must_not)
+    }
+    
+    /// Check whether a point satisfies this filter.
+    ///
+    /// Mirrors Qdrant semantics: every `must` condition has to hold, at least
+    /// one `should` condition has to hold (when any are given), and no
+    /// `must_not` condition may hold. `payload` is the JSON form of [`Payload`].
+    pub fn matches(&self, id: &Uuid, payload: &serde_json::Value) -> bool {
+        self.must.iter().all(|c| c.matches(id, payload))
+            && (self.should.is_empty() || self.should.iter().any(|c| c.matches(id, payload)))
+            && !self.must_not.iter().any(|c| c.matches(id, payload))
+    }
+}
+
+impl Condition {
+    /// Payload key the condition applies to (`None` for `HasId`)
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Condition::Match { key, .. }
+            | Condition::MatchAny { key, .. }
+            | Condition::MatchExcept { key, .. }
+            | Condition::Range { key, .. }
+            | Condition::DatetimeRange { key, .. }
+            | Condition::MatchText { key, .. }
+            | Condition::IsEmpty { key }
+            | Condition::IsNull { key } => Some(key),
+            Condition::HasId { .. } => None,
+        }
+    }
+    
+    /// Values the condition compares against
+    pub fn values(&self) -> Vec<&serde_json::Value> {
+        match self {
+            Condition::Match { value, .. } => vec![value],
+            Condition::MatchAny { any, .. } => any.iter().collect(),
+            Condition::MatchExcept { except, .. } => except.iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    /// Check whether a single condition holds for a point
+    pub fn matches(&self, id: &Uuid, payload: &serde_json::Value) -> bool {
+        match self {
+            Condition::Match { key, value } => {
+                leaf_values(payload, key).any(|v| json_eq(v, value))
+            }
+            Condition::MatchAny { key, any } => {
+                leaf_values(payload, key).any(|v| any.iter().any(|a| json_eq(v, a)))
+            }
+            Condition::MatchExcept { key, except } => {
+                let mut values = leaf_values(payload, key).peekable();
+                values.peek().is_none() || values.any(|v| !except.iter().any(|e| json_eq(v, e)))
+            }
+            Condition::Range { key, gte, lte } => leaf_values(payload, key).any(|v| match v.as_f64() {
+                Some(n) => gte.is_none_or(|g| n >= g) && lte.is_none_or(|l| n <= l),
+                None => false,
+            }),
+            Condition::DatetimeRange { key, gte, lte } => leaf_values(payload, key).any(|v| {
+                let at = match v {
+                    serde_json::Value::Number(n) => n.as_i64().and_then(|secs| DateTime::from_timestamp(secs, 0)),
+                    serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s).ok().map(|d| d.with_timezone(&Utc)),
+                    _ => None,
+                };
+                at.is_some_and(|at| gte.is_none_or(|g| at >= g) && lte.is_none_or(|l| at <= l))
+            }),
+            Condition::MatchText { key, text } => {
+                leaf_values(payload, key).any(|v| v.as_str().is_some_and(|s| s.contains(text.as_str())))
+            }
+            Condition::IsEmpty { key } => resolve(payload, key).iter().all(|v| match v {
+                serde_json::Value::Null => true,
+                serde_json::Value::Array(items) => items.is_empty(),
+                _ => false,
+            }),
+            Condition::IsNull { key } => resolve(payload, key).iter().any(|v| v.is_null()),
+            Condition::HasId { ids } => ids.contains(id),
+        }
+    }
+}
+
+/// Resolve a dotted key to every value it reaches, descending into arrays
+/// along the way like Qdrant does. A `[]` suffix on a segment is accepted.
+fn resolve<'a>(payload: &'a serde_json::Value, key: &str) -> Vec<&'a serde_json::Value> {
+    let mut current = vec![payload];
+
+    for segment in key.split('.') {
+        let segment = segment.strip_suffix("[]").unwrap_or(segment);
+        let mut next = Vec::new();
+        for value in current {
+            match value {
+                serde_json::Value::Object(map) => next.extend(map.get(segment)),
+                serde_json::Value::Array(items) => {
+                    next.extend(items.iter().filter_map(|item| item.get(segment)))
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+
+    current
+}
+
+/// Resolved values of a key with arrays flattened, as compared by match conditions
+fn leaf_values<'a>(payload: &'a serde_json::Value, key: &str) -> impl Iterator<Item = &'a serde_json::Value> {
+    resolve(payload, key).into_iter().flat_map(field_values)
+}
+
+/// Iterate the values of a payload field, flattening arrays like Qdrant does
+fn field_values(field: &serde_json::Value) -> Box<dyn Iterator<Item = &serde_json::Value> + '_> {
+    match field {
+        serde_json::Value::Array(items) => Box::new(items.iter()),
+        other => Box::new(std::iter::once(other)),
+    }
+}
+
+/// Compare JSON values, treating integers and floats as the same number
+fn json_eq(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rich_conditions() {
+        let id = Uuid::new_v4();
+        let payload = serde_json::json!({
+            "text": "fixed E0308 in retriever",
+            "timestamp": 1_700_000_000,
+            "tags": ["rust", "db"],
+            "archived": null,
+            "links": [],
+            "source": {"kind": "chat", "turns": [{"role": "user"}, {"role": "tool"}]},
+            "created": "2024-01-15T10:00:00Z",
+        });
+        let key = |k: &str| k.to_string();
+        let holds = |c: Condition| Filter::new().must(c).matches(&id, &payload);
+
+        assert!(holds(Condition::MatchAny { key: key("tags"), any: vec![serde_json::json!("go"), serde_json::json!("db")] }));
+        assert!(!holds(Condition::MatchAny { key: key("tags"), any: vec![serde_json::json!("go")] }));
+        assert!(holds(Condition::MatchExcept { key: key("tags"), except: vec![serde_json::json!("rust")] }));
+        assert!(!holds(Condition::MatchExcept { key: key("source.kind"), except: vec![serde_json::json!("chat")] }));
+        assert!(holds(Condition::MatchExcept { key: key("missing"), except: vec![serde_json::json!("x")] }));
+        assert!(holds(Condition::IsNull { key: key("archived") }));
+        assert!(!holds(Condition::IsNull { key: key("missing") }));
+        assert!(holds(Condition::IsEmpty { key: key("archived") }));
+        assert!(holds(Condition::IsEmpty { key: key("links") }));
+        assert!(holds(Condition::IsEmpty { key: key("missing") }));
+        assert!(!holds(Condition::IsEmpty { key: key("tags") }));
+        assert!(holds(Condition::MatchText { key: key("text"), text: "E0308".to_string() }));
+        assert!(holds(Condition::Match { key: key("source.kind"), value: serde_json::json!("chat") }));
+        assert!(holds(Condition::Match { key: key("source.turns[].role"), value: serde_json::json!("tool") }));
+
+        let day = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&chrono::Utc);
+        assert!(holds(Condition::DatetimeRange {
+            key: key("created"),
+            gte: Some(day("2024-01-01T00:00:00Z")),
+            lte: Some(day("2024-02-01T00:00:00Z")),
+        }));
+        assert!(holds(Condition::DatetimeRange { key: key("timestamp"), gte: Some(day("2023-11-01T00:00:00Z")), lte: None }));
+        assert!(!holds(Condition::DatetimeRange { key: key("timestamp"), gte: Some(day("2024-01-01T00:00:00Z")), lte: None }));
+    }
+}
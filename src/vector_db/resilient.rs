@@ -0,0 +1,321 @@
+//! `VectorStore` decorator adding timeouts, retries and a circuit breaker
+
+use super::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use super::models::{CollectionStats, Filter, ScrollPage, SearchParams, SearchResult, VectorPoint};
+use super::VectorStore;
+use crate::config::{PayloadFieldType, VectorDbConfig};
+use crate::error::{ContextError, Result, VectorDbError};
+use async_trait::async_trait;
+use rand::Rng;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Upper bound for a single backoff delay
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Wraps any `VectorStore` so every backend shares the same failure semantics.
+///
+/// Each call is bounded by `timeout_secs`. Transient failures (connection,
+/// unavailable and timeout errors) count against the circuit breaker and, for
+/// idempotent operations, are retried with jittered exponential backoff.
+/// Errors caused by the request itself, such as a missing collection or a
+/// malformed filter, are returned as-is and do not trip the breaker.
+pub struct ResilientVectorStore {
+    inner: Arc<dyn VectorStore>,
+    breaker: Arc<CircuitBreaker>,
+    timeout: Duration,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl ResilientVectorStore {
+    pub fn new(inner: Arc<dyn VectorStore>, config: &VectorDbConfig) -> Self {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: config.circuit_failure_threshold,
+            timeout: Duration::from_secs(config.circuit_open_secs),
+            ..CircuitBreakerConfig::default()
+        });
+
+        Self {
+            inner,
+            breaker: Arc::new(breaker),
+            timeout: Duration::from_secs(config.timeout_secs),
+            max_retries: config.max_retries,
+            backoff: Duration::from_millis(config.retry_backoff_ms),
+        }
+    }
+
+    /// Share an existing circuit breaker instead of the one built from config
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// Circuit breaker guarding the wrapped store, for health checks and metrics
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        self.breaker.clone()
+    }
+
+    /// Run `op` under the timeout and breaker, retrying transient failures when `idempotent`
+    async fn call<T, F, Fut>(&self, name: &str, idempotent: bool, op: F) -> Result<T>
+    where
+        T: Send,
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T>> + Send,
+    {
+        let attempts = if idempotent { self.max_retries + 1 } else { 1 };
+        let mut attempt = 0;
+
+        loop {
+            if !self.breaker.allow_request().await {
+                return Err(VectorDbError::CircuitOpen.into());
+            }
+
+            let result = match tokio::time::timeout(self.timeout, op()).await {
+                Ok(result) => result,
+                Err(_) => Err(VectorDbError::Timeout(self.timeout.as_millis() as u64).into()),
+            };
+
+            match result {
+                Err(e) if is_transient(&e) => {
+                    self.breaker.record_failure().await;
+                    attempt += 1;
+                    if attempt >= attempts {
+                        return Err(e);
+                    }
+
+                    let delay = self.backoff_delay(attempt);
+                    warn!(
+                        "Vector store {} failed (attempt {}/{}), retrying in {:?}: {}",
+                        name, attempt, attempts, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => {
+                    self.breaker.record_success().await;
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Exponential backoff with full jitter: uniform in `[0, base * 2^(attempt - 1)]`
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .backoff
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(MAX_BACKOFF);
+        let millis = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+}
+
+/// Whether an error points at the backend rather than the request.
+///
+/// Backends report unreachable, unavailable or overloaded servers as
+/// `ConnectionError`; search, insert and other errors mean the backend
+/// rejected the request, and retrying it would fail the same way.
+fn is_transient(error: &ContextError) -> bool {
+    matches!(
+        error,
+        ContextError::VectorDb(VectorDbError::ConnectionError(_) | VectorDbError::Timeout(_))
+    )
+}
+
+#[async_trait]
+impl VectorStore for ResilientVectorStore {
+    async fn create_collection(&self, name: &str) -> Result<()> {
+        self.call("create_collection", false, || self.inner.create_collection(name)).await
+    }
+
+    async fn delete_collection(&self, name: &str) -> Result<()> {
+        self.call("delete_collection", false, || self.inner.delete_collection(name)).await
+    }
+
+    async fn collection_exists(&self, name: &str) -> Result<bool> {
+        self.call("collection_exists", true, || self.inner.collection_exists(name)).await
+    }
+
+    async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+        // Upserts by ID, so replaying a batch is safe
+        self.call("insert_points", true, || self.inner.insert_points(collection, points.clone())).await
+    }
+
+    async fn search(&self, collection: &str, params: SearchParams) -> Result<Vec<SearchResult>> {
+        self.call("search", true, || self.inner.search(collection, params.clone())).await
+    }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<Uuid>) -> Result<()> {
+        self.call("delete_points", true, || self.inner.delete_points(collection, ids.clone())).await
+    }
+
+    async fn get_point(&self, collection: &str, id: Uuid) -> Result<Option<VectorPoint>> {
+        self.call("get_point", true, || self.inner.get_point(collection, id)).await
+    }
+
+    async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+        // Sets absolute values, so replaying a batch is safe
+        self.call("set_metadata", true, || self.inner.set_metadata(collection, updates.clone())).await
+    }
+
+    async fn scroll(
+        &self,
+        collection: &str,
+        filter: Option<Filter>,
+        page_size: usize,
+        cursor: Option<Uuid>,
+    ) -> Result<ScrollPage> {
+        self.call("scroll", true, || self.inner.scroll(collection, filter.clone(), page_size, cursor)).await
+    }
+
+    async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64> {
+        self.call("count", true, || self.inner.count(collection, filter.clone())).await
+    }
+
+    async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+        self.call("collection_stats", true, || self.inner.collection_stats(collection)).await
+    }
+
+    async fn create_payload_index(&self, collection: &str, field: &str, kind: PayloadFieldType) -> Result<()> {
+        self.call("create_payload_index", true, || self.inner.create_payload_index(collection, field, kind)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::circuit_breaker::CircuitState;
+    use crate::vector_db::InMemoryVectorStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In-memory store whose `count` fails `failures` times and whose `get_point` hangs
+    struct FlakyStore {
+        inner: InMemoryVectorStore,
+        failures: AtomicUsize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl VectorStore for FlakyStore {
+        async fn create_collection(&self, name: &str) -> Result<()> {
+            self.inner.create_collection(name).await
+        }
+
+        async fn delete_collection(&self, name: &str) -> Result<()> {
+            self.inner.delete_collection(name).await
+        }
+
+        async fn collection_exists(&self, name: &str) -> Result<bool> {
+            self.inner.collection_exists(name).await
+        }
+
+        async fn insert_points(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+            self.inner.insert_points(collection, points).await
+        }
+
+        async fn search(&self, collection: &str, params: SearchParams) -> Result<Vec<SearchResult>> {
+            self.inner.search(collection, params).await
+        }
+
+        async fn delete_points(&self, collection: &str, ids: Vec<Uuid>) -> Result<()> {
+            self.inner.delete_points(collection, ids).await
+        }
+
+        async fn get_point(&self, _collection: &str, _id: Uuid) -> Result<Option<VectorPoint>> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(None)
+        }
+
+        async fn set_metadata(&self, collection: &str, updates: Vec<(Uuid, HashMap<String, serde_json::Value>)>) -> Result<()> {
+            self.inner.set_metadata(collection, updates).await
+        }
+
+        async fn scroll(
+            &self,
+            collection: &str,
+            filter: Option<Filter>,
+            page_size: usize,
+            cursor: Option<Uuid>,
+        ) -> Result<ScrollPage> {
+            self.inner.scroll(collection, filter, page_size, cursor).await
+        }
+
+        async fn count(&self, collection: &str, filter: Option<Filter>) -> Result<u64> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Err(VectorDbError::ConnectionError("connection reset".to_string()).into());
+            }
+            self.inner.count(collection, filter).await
+        }
+
+        async fn collection_stats(&self, collection: &str) -> Result<CollectionStats> {
+            self.inner.collection_stats(collection).await
+        }
+
+        async fn create_payload_index(&self, collection: &str, field: &str, kind: PayloadFieldType) -> Result<()> {
+            self.inner.create_payload_index(collection, field, kind).await
+        }
+    }
+
+    async fn resilient(failures: usize) -> (ResilientVectorStore, Arc<FlakyStore>) {
+        let mut config = Config::default_config().vector_db;
+        config.max_retries = 2;
+        config.retry_backoff_ms = 1;
+        config.circuit_failure_threshold = 3;
+
+        let flaky = Arc::new(FlakyStore {
+            inner: InMemoryVectorStore::new(config.clone()),
+            failures: AtomicUsize::new(failures),
+            calls: AtomicUsize::new(0),
+        });
+        flaky.create_collection("test").await.unwrap();
+
+        let mut store = ResilientVectorStore::new(flaky.clone(), &config);
+        store.timeout = Duration::from_millis(50);
+        (store, flaky)
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let (store, flaky) = resilient(2).await;
+
+        assert_eq!(store.count("test", None).await.unwrap(), 0);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(store.circuit_breaker().state().await, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_are_not_retried() {
+        let (store, _) = resilient(0).await;
+
+        let err = store.create_collection("test").await.unwrap_err();
+        assert!(matches!(err, ContextError::VectorDb(VectorDbError::CollectionExists(_))));
+        assert!(store.count("missing", None).await.is_err());
+        assert_eq!(store.circuit_breaker().stats().await.total_failures, 0);
+
+        // Backends report rejected requests, such as a malformed filter, with their operation's error
+        let rejected = ContextError::VectorDb(VectorDbError::SearchError("Wrong input: bad filter".to_string()));
+        assert!(!is_transient(&rejected));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_breaker_opens() {
+        let (store, flaky) = resilient(10).await;
+
+        let err = store.get_point("test", Uuid::new_v4()).await.unwrap_err();
+        assert!(matches!(err, ContextError::VectorDb(VectorDbError::Timeout(50))));
+        assert_eq!(store.circuit_breaker().state().await, CircuitState::Open);
+
+        // Open circuit fails fast without reaching the store
+        let err = store.count("test", None).await.unwrap_err();
+        assert!(matches!(err, ContextError::VectorDb(VectorDbError::CircuitOpen)));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 0);
+    }
+}
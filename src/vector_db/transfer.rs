@@ -0,0 +1,603 @@
+//! Streaming export and import of collections as JSONL or MessagePack
+//!
+//! An export is a header record followed by one record per point. JSONL
+//! writes one JSON document per line; MessagePack writes the same records as
+//! consecutive self-delimiting values. Import detects the format on its own.
+
+use super::models::{Payload, VectorPoint};
+use super::VectorStore;
+use crate::embedding::{EmbeddingProvider, EMBEDDING_MODEL_KEY};
+use crate::error::{ContextError, Result, VectorDbError};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Version of the export record layout
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Points fetched per scroll page while exporting
+const EXPORT_PAGE_SIZE: usize = 256;
+
+/// Export file encodings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON document per line
+    #[default]
+    Jsonl,
+    /// Consecutive MessagePack values
+    Msgpack,
+}
+
+impl ExportFormat {
+    /// Pick the format from a file extension (`.msgpack` or `.mpk`, otherwise JSONL)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("msgpack") | Some("mpk") => ExportFormat::Msgpack,
+            _ => ExportFormat::Jsonl,
+        }
+    }
+
+    /// Detect the format from the first byte of a file.
+    ///
+    /// Records are JSON objects or MessagePack maps, so anything starting
+    /// with `{` is JSONL.
+    fn detect(first_byte: u8) -> Self {
+        if first_byte == b'{' {
+            ExportFormat::Jsonl
+        } else {
+            ExportFormat::Msgpack
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ContextError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "jsonl" | "json" => Ok(ExportFormat::Jsonl),
+            "msgpack" | "messagepack" | "mpk" => Ok(ExportFormat::Msgpack),
+            other => Err(ContextError::Configuration(format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+/// First record of every export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub version: u32,
+    pub collection: String,
+    pub with_vectors: bool,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// One exported point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRecord {
+    pub id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
+    pub payload: Payload,
+}
+
+/// Export settings
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Include vectors; without them an import has to re-embed
+    pub with_vectors: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            with_vectors: true,
+        }
+    }
+}
+
+/// Import settings
+#[derive(Clone)]
+pub struct ImportOptions {
+    /// Points per `insert_points` call
+    pub batch_size: usize,
+    /// Vector dimension of the target collection
+    pub vector_size: usize,
+    /// Skip records committed by an earlier, interrupted import of the same file
+    pub resume: bool,
+    /// Re-embed records whose vector is missing or has a different dimension
+    pub embedder: Option<Arc<dyn EmbeddingProvider>>,
+}
+
+impl ImportOptions {
+    pub fn new(vector_size: usize) -> Self {
+        Self {
+            batch_size: EXPORT_PAGE_SIZE,
+            vector_size,
+            resume: false,
+            embedder: None,
+        }
+    }
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Points written by this run
+    pub imported: usize,
+    /// Records skipped because a previous run already committed them
+    pub skipped: usize,
+    /// Points whose vector was regenerated
+    pub reembedded: usize,
+}
+
+/// Write every point of `collection` to `path`. Returns the number of points exported.
+///
+/// The export is written to a temporary file next to `path` and renamed into
+/// place once complete, so a failed run never leaves a truncated export.
+pub async fn export_collection(
+    store: &dyn VectorStore,
+    collection: &str,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<usize> {
+    info!("Exporting {} to {} ({:?})", collection, path.display(), options.format);
+
+    let partial = partial_path(path);
+    let result = write_export(store, collection, &partial, options).await;
+    let exported = match result.and_then(|exported| commit_export(&partial, path).map(|_| exported)) {
+        Ok(exported) => exported,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+
+    info!("Exported {} points from {}", exported, collection);
+    Ok(exported)
+}
+
+/// Write the export of `collection` to `path` and fsync it
+async fn write_export(
+    store: &dyn VectorStore,
+    collection: &str,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<usize> {
+    let file = File::create(path).map_err(storage_err)?;
+    let mut writer = BufWriter::new(file);
+
+    let header = ExportHeader {
+        version: EXPORT_VERSION,
+        collection: collection.to_string(),
+        with_vectors: options.with_vectors,
+        exported_at: Utc::now(),
+    };
+    write_record(&mut writer, options.format, &header)?;
+
+    let mut exported = 0;
+    let mut cursor = None;
+    loop {
+        let page = store.scroll(collection, None, EXPORT_PAGE_SIZE, cursor).await?;
+
+        for point in page.points {
+            let record = ExportRecord {
+                id: point.id,
+                vector: options.with_vectors.then_some(point.vector),
+                payload: point.payload,
+            };
+            write_record(&mut writer, options.format, &record)?;
+            exported += 1;
+        }
+
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    let file = writer.into_inner().map_err(|e| storage_err(e.into_error()))?;
+    file.sync_all().map_err(storage_err)?;
+    Ok(exported)
+}
+
+/// Rename a complete export from `partial` to `path` and make the rename durable
+fn commit_export(partial: &Path, path: &Path) -> Result<()> {
+    fs::rename(partial, path).map_err(storage_err)?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        File::open(dir).and_then(|d| d.sync_all()).map_err(storage_err)?;
+    }
+    Ok(())
+}
+
+/// Load an export from `path` into `collection`, creating it if needed.
+///
+/// Progress is checkpointed next to the file after every batch, so a run
+/// started with `resume` continues where an interrupted one stopped.
+pub async fn import_collection(
+    store: &dyn VectorStore,
+    collection: &str,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportReport> {
+    let mut reader = ExportReader::open(path)?;
+    let header = reader.header().clone();
+
+    info!(
+        "Importing {} (exported from {} at {}) into {}",
+        path.display(),
+        header.collection,
+        header.exported_at,
+        collection
+    );
+
+    if !store.collection_exists(collection).await? {
+        store.create_collection(collection).await?;
+    }
+
+    let checkpoint = checkpoint_path(path, collection);
+    let committed = if options.resume { read_checkpoint(&checkpoint)? } else { 0 };
+
+    let mut report = ImportReport::default();
+    let mut batch = Vec::with_capacity(options.batch_size.max(1));
+    let mut position = 0;
+
+    while let Some(record) = reader.next_record()? {
+        position += 1;
+        if position <= committed {
+            report.skipped += 1;
+            continue;
+        }
+
+        batch.push(record);
+        if batch.len() >= options.batch_size.max(1) {
+            flush_batch(store, collection, &mut batch, options, &mut report).await?;
+            write_checkpoint(&checkpoint, position)?;
+        }
+    }
+
+    flush_batch(store, collection, &mut batch, options, &mut report).await?;
+
+    if checkpoint.exists() {
+        fs::remove_file(&checkpoint).map_err(storage_err)?;
+    }
+
+    info!(
+        "Imported {} points into {} ({} skipped, {} re-embedded)",
+        report.imported, collection, report.skipped, report.reembedded
+    );
+    Ok(report)
+}
+
+/// Sequential reader of an export file in either format
+pub struct ExportReader {
+    reader: BufReader<File>,
+    format: ExportFormat,
+    header: ExportHeader,
+}
+
+impl ExportReader {
+    /// Open `path`, detecting its format and reading the header
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(storage_err)?;
+        let mut reader = BufReader::new(file);
+
+        let format = match reader.fill_buf().map_err(storage_err)?.first() {
+            Some(byte) => ExportFormat::detect(*byte),
+            None => return Err(VectorDbError::SerializationError("Export file is empty".to_string()).into()),
+        };
+
+        let header: ExportHeader = read_record(&mut reader, format)?
+            .ok_or_else(|| VectorDbError::SerializationError("Missing export header".to_string()))?;
+        if header.version > EXPORT_VERSION {
+            return Err(VectorDbError::SerializationError(format!(
+                "Unsupported export version {} (max {})",
+                header.version, EXPORT_VERSION
+            ))
+            .into());
+        }
+
+        Ok(Self { reader, format, header })
+    }
+
+    pub fn header(&self) -> &ExportHeader {
+        &self.header
+    }
+
+    /// Next record, or `None` at end of file
+    pub fn next_record(&mut self) -> Result<Option<ExportRecord>> {
+        read_record(&mut self.reader, self.format)
+    }
+}
+
+/// Insert a batch, re-embedding records whose vector does not fit the target collection
+async fn flush_batch(
+    store: &dyn VectorStore,
+    collection: &str,
+    batch: &mut Vec<ExportRecord>,
+    options: &ImportOptions,
+    report: &mut ImportReport,
+) -> Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let stale: Vec<usize> = batch
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.vector.as_ref().is_none_or(|v| v.len() != options.vector_size))
+        .map(|(i, _)| i)
+        .collect();
+
+    if !stale.is_empty() {
+        let Some(embedder) = &options.embedder else {
+            let actual = batch[stale[0]].vector.as_ref().map(|v| v.len()).unwrap_or(0);
+            return Err(VectorDbError::InvalidDimension {
+                expected: options.vector_size,
+                actual,
+            }
+            .into());
+        };
+
+        let texts: Vec<String> = stale.iter().map(|i| batch[*i].payload.text.clone()).collect();
+        let vectors = embedder.embed_batch(&texts).await?;
+        let model_id = embedder.model_id();
+        for (i, vector) in stale.iter().zip(vectors) {
+            batch[*i].vector = Some(vector);
+            batch[*i].payload.metadata.insert(EMBEDDING_MODEL_KEY.to_string(), model_id.clone().into());
+        }
+        report.reembedded += stale.len();
+    }
+
+    let points: Vec<VectorPoint> = batch
+        .drain(..)
+        .map(|record| VectorPoint {
+            id: record.id,
+            vector: record.vector.unwrap_or_default(),
+            payload: record.payload,
+        })
+        .collect();
+
+    debug!("Inserting {} imported points into {}", points.len(), collection);
+    report.imported += points.len();
+    store.insert_points(collection, points).await
+}
+
+fn write_record<T: Serialize>(writer: &mut impl Write, format: ExportFormat, record: &T) -> Result<()> {
+    match format {
+        ExportFormat::Jsonl => {
+            serde_json::to_writer(&mut *writer, record)
+                .map_err(|e| VectorDbError::SerializationError(e.to_string()))?;
+            writer.write_all(b"\n").map_err(storage_err)?;
+        }
+        ExportFormat::Msgpack => {
+            rmp_serde::encode::write_named(writer, record)
+                .map_err(|e| VectorDbError::SerializationError(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the next record, or `None` at end of file
+fn read_record<T: DeserializeOwned>(reader: &mut impl BufRead, format: ExportFormat) -> Result<Option<T>> {
+    match format {
+        ExportFormat::Jsonl => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).map_err(storage_err)? == 0 {
+                    return Ok(None);
+                }
+                if !line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            serde_json::from_str(&line)
+                .map(Some)
+                .map_err(|e| VectorDbError::SerializationError(e.to_string()).into())
+        }
+        ExportFormat::Msgpack => {
+            if reader.fill_buf().map_err(storage_err)?.is_empty() {
+                return Ok(None);
+            }
+
+            rmp_serde::from_read(reader)
+                .map(Some)
+                .map_err(|e| VectorDbError::SerializationError(e.to_string()).into())
+        }
+    }
+}
+
+/// Checkpoint file for importing `path` into `collection`
+/// Temporary file an export to `path` is written to
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+fn checkpoint_path(path: &Path, collection: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}.checkpoint", collection));
+    PathBuf::from(name)
+}
+
+fn read_checkpoint(path: &Path) -> Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let content = fs::read_to_string(path).map_err(storage_err)?;
+    content
+        .trim()
+        .parse()
+        .map_err(|_| VectorDbError::StorageError(format!("Corrupt import checkpoint: {}", path.display())).into())
+}
+
+fn write_checkpoint(path: &Path, position: usize) -> Result<()> {
+    fs::write(path, position.to_string()).map_err(storage_err)?;
+    Ok(())
+}
+
+fn storage_err(e: std::io::Error) -> VectorDbError {
+    VectorDbError::StorageError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vector_db::models::ContextLevel;
+    use crate::vector_db::InMemoryVectorStore;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+
+    /// Embeds every text as `[len, 1, 0]`
+    struct FixedEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for FixedEmbedder {
+        async fn embed_single(&self, text: &str) -> Result<Vec<f32>> {
+            Ok(vec![text.len() as f32, 1.0, 0.0])
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0, 0.0]).collect())
+        }
+
+        fn embedding_dimension(&self) -> usize {
+            3
+        }
+    }
+
+    fn store(vector_size: usize) -> InMemoryVectorStore {
+        let mut config = Config::default_config().vector_db;
+        config.vector_size = vector_size;
+        InMemoryVectorStore::new(config)
+    }
+
+    async fn seeded(count: usize) -> InMemoryVectorStore {
+        let store = store(3);
+        store.create_collection("source").await.unwrap();
+
+        let points = (0..count)
+            .map(|i| {
+                let mut metadata = HashMap::new();
+                metadata.insert("tags".to_string(), serde_json::json!(["a", i]));
+                VectorPoint {
+                    id: Uuid::new_v4(),
+                    vector: vec![i as f32, 1.0, 0.0],
+                    payload: Payload {
+                        text: format!("context {}", i),
+                        level: ContextLevel::LongTerm,
+                        timestamp: i as i64,
+                        agent_id: "agent".to_string(),
+                        session_id: Some("s1".to_string()),
+                        metadata,
+                    },
+                }
+            })
+            .collect();
+        store.insert_points("source", points).await.unwrap();
+        store
+    }
+
+    fn temp_file(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("export-{}.{}", Uuid::new_v4(), extension))
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_both_formats() {
+        let source = seeded(5).await;
+
+        for format in [ExportFormat::Jsonl, ExportFormat::Msgpack] {
+            let path = temp_file("export");
+            let options = ExportOptions { format, with_vectors: true };
+            assert_eq!(export_collection(&source, "source", &path, &options).await.unwrap(), 5);
+
+            let target = store(3);
+            let report = import_collection(&target, "target", &path, &ImportOptions::new(3))
+                .await
+                .unwrap();
+            assert_eq!(report.imported, 5);
+            assert_eq!(report.reembedded, 0);
+
+            let original = source.scroll("source", None, 10, None).await.unwrap().points;
+            for point in original {
+                let copy = target.get_point("target", point.id).await.unwrap().unwrap();
+                assert_eq!(copy.vector, point.vector);
+                assert_eq!(copy.payload.metadata, point.payload.metadata);
+                assert_eq!(copy.payload.session_id, point.payload.session_id);
+            }
+
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_export_keeps_previous_file() {
+        let source = seeded(3).await;
+        let path = temp_file("export");
+        let options = ExportOptions::default();
+        export_collection(&source, "source", &path, &options).await.unwrap();
+        let previous = fs::read(&path).unwrap();
+
+        assert!(export_collection(&source, "missing", &path, &options).await.is_err());
+        assert_eq!(fs::read(&path).unwrap(), previous);
+        assert!(!partial_path(&path).exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_reembeds_on_dimension_mismatch() {
+        let source = seeded(3).await;
+        let path = temp_file("jsonl");
+        let options = ExportOptions { format: ExportFormat::Jsonl, with_vectors: false };
+        export_collection(&source, "source", &path, &options).await.unwrap();
+
+        let target = store(3);
+        assert!(import_collection(&target, "target", &path, &ImportOptions::new(3)).await.is_err());
+
+        let mut import = ImportOptions::new(3);
+        import.embedder = Some(Arc::new(FixedEmbedder));
+        let report = import_collection(&target, "target", &path, &import).await.unwrap();
+        assert_eq!(report.reembedded, 3);
+        assert_eq!(target.count("target", None).await.unwrap(), 3);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_resumes_from_checkpoint() {
+        let source = seeded(5).await;
+        let path = temp_file("msgpack");
+        let options = ExportOptions { format: ExportFormat::from_path(&path), with_vectors: true };
+        export_collection(&source, "source", &path, &options).await.unwrap();
+
+        // Pretend a previous run committed the first three records
+        write_checkpoint(&checkpoint_path(&path, "target"), 3).unwrap();
+
+        let target = store(3);
+        let mut import = ImportOptions::new(3);
+        import.resume = true;
+        let report = import_collection(&target, "target", &path, &import).await.unwrap();
+        assert_eq!(report.skipped, 3);
+        assert_eq!(report.imported, 2);
+        assert!(!checkpoint_path(&path, "target").exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
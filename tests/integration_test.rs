@@ -1,6 +1,7 @@
 //! Integration tests for Context Manager
 //! 
-//! These tests require external services:
+//! `test_hirag_manager_offline` runs hermetically with the hashing embedding
+//! provider and the in-memory store. The others require external services:
 //! - Qdrant vector database (http://localhost:6333)
 //! - Embedding API endpoint
 //! 
@@ -11,11 +12,12 @@
 
 use context_manager::{
     Config,
+    config::{EmbeddingProviderKind, VectorDbBackend},
     v2::{VectorDbClientV2, EmbeddingClientV2, HiRAGManagerV2},
-    vector_db::{VectorStore, ContextLevel},
-    embedding::EmbeddingProvider,
+    vector_db::{create_vector_store, VectorStore, ContextLevel},
+    embedding::{create_embedding_provider, EmbeddingProvider},
     observability::{HealthChecker, MetricsCollector},
-    hirag::ContextManager,
+    hirag::{ContextManager, Namespace},
 };
 use std::sync::Arc;
 use std::collections::HashMap;
@@ -187,7 +189,7 @@ async fn test_hirag_manager_end_to_end() {
 
     let mut stored_ids = Vec::new();
     for (text, level) in contexts {
-        match manager.store_context(text, level, HashMap::new()).await {
+        match manager.store_context(&Namespace::default(), text, level, HashMap::new(), None).await {
             Ok(id) => {
                 stored_ids.push(id);
                 println!("Stored context: {} with id: {}", text, id);
@@ -209,9 +211,14 @@ async fn test_hirag_manager_end_to_end() {
         filters: None,
         priority: context_manager::hirag::Priority::Normal,
         session_id: None,
+        session_mode: context_manager::hirag::SessionMode::Global,
+        explain: false,
+        rerank: None,
+        ranking_weights: None,
+        recency_decay: None,
     };
 
-    match manager.retrieve_context(request).await {
+    match manager.retrieve_context(&Namespace::default(), request).await {
         Ok(response) => {
             println!("Retrieved {} contexts", response.contexts.len());
             println!("Total tokens: {}", response.total_tokens);
@@ -227,10 +234,41 @@ async fn test_hirag_manager_end_to_end() {
 
     // Cleanup - delete stored contexts
     for id in stored_ids {
-        let _ = manager.delete_context(id).await;
+        let _ = manager.delete_context(&Namespace::default(), id).await;
     }
 }
 
+#[tokio::test]
+async fn test_hirag_manager_offline() {
+    let mut config = Config::default_config();
+    config.embedding.provider = EmbeddingProviderKind::Hashing;
+    config.vector_db.backend = VectorDbBackend::Memory;
+    config.hirag.access_tracking_enabled = false;
+    
+    let embedding_client = create_embedding_provider(config.embedding.clone()).unwrap();
+    let vector_db = create_vector_store(config.vector_db.clone()).await.unwrap();
+    let manager = HiRAGManagerV2::new(config.hirag.clone(), embedding_client, vector_db)
+        .await
+        .unwrap();
+    manager.initialize().await.unwrap();
+    
+    let namespace = Namespace::default();
+    let wanted = manager
+        .store_context(&namespace, "User prefers dark mode in the editor", ContextLevel::LongTerm, HashMap::new(), None)
+        .await
+        .unwrap();
+    manager
+        .store_context(&namespace, "Deployment runs every Friday afternoon", ContextLevel::LongTerm, HashMap::new(), None)
+        .await
+        .unwrap();
+    
+    let request = context_manager::hirag::ContextRequest::new("dark mode preference".to_string(), 1000)
+        .with_levels(vec![ContextLevel::LongTerm]);
+    let response = manager.retrieve_context(&namespace, request).await.unwrap();
+    
+    assert_eq!(response.contexts[0].id, wanted);
+}
+
 #[tokio::test]
 #[ignore] // Requires Qdrant
 async fn test_health_checks_with_real_services() {